@@ -0,0 +1,284 @@
+extern crate browser_engine;
+use browser_engine::atom::Atom;
+use browser_engine::dom::{ElementData, Node};
+use browser_engine::media::MediaEnvironment;
+use browser_engine::serializer::{serialize_selector, Mode};
+use browser_engine::{css_parser, css_tokenizer, html_parser, style};
+
+use std::collections::{HashMap, HashSet};
+use std::hint::black_box;
+use std::iter::Peekable;
+use std::str::Chars;
+use std::time::Instant;
+
+const EXAMPLE_HTML: &str = include_str!("../example/example.html");
+const EXAMPLE_CSS: &str = include_str!("../example/example.css");
+
+const SCALE: usize = 50;
+const ITERATIONS: u32 = 20;
+
+fn scaled_html(scale: usize) -> String {
+    let start = EXAMPLE_HTML.find("<body>").unwrap() + "<body>".len();
+    let end = EXAMPLE_HTML.find("</body>").unwrap();
+
+    format!(
+        "<html><body>{0}</body></html>",
+        EXAMPLE_HTML[start..end].repeat(scale)
+    )
+}
+
+fn main() {
+    let html_input = scaled_html(SCALE);
+    let css_input = EXAMPLE_CSS.repeat(SCALE);
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(html_parser::HtmlParser::new(&html_input).parse_nodes());
+        black_box(css_parser::CssParser::new(&css_input).parse_stylesheet());
+    }
+    let elapsed = start.elapsed();
+
+    println!(
+        "parse html and css, example page x{0}: {1:?} per iteration",
+        SCALE,
+        elapsed / ITERATIONS
+    );
+
+    compare_tokenizing(&css_input);
+
+    let nodes = html_parser::HtmlParser::new(&html_input).parse_nodes();
+    let stylesheet = css_parser::CssParser::new(EXAMPLE_CSS).parse_stylesheet();
+    let root_node = &nodes[0];
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(style::StyledNode::new(root_node, &stylesheet));
+    }
+    let elapsed = start.elapsed();
+
+    println!(
+        "style tree, example page x{0}: {1:?} per iteration",
+        SCALE,
+        elapsed / ITERATIONS
+    );
+
+    compare_name_matching(root_node, &stylesheet);
+}
+
+// Splits the stylesheet into tokens twice, once the way the parsers did
+// before they scanned by byte offsets, pushing each char of a token into a
+// fresh String, and once with the tokenizer, which borrows from the input.
+fn compare_tokenizing(css_input: &str) {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(char_tokens(css_input));
+    }
+    let strings = start.elapsed() / ITERATIONS;
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(css_tokenizer::tokenize(css_input));
+    }
+    let slices = start.elapsed() / ITERATIONS;
+
+    println!(
+        "tokenize css, example page x{0}: strings {1:?}, slices {2:?} per iteration",
+        SCALE, strings, slices
+    );
+}
+
+// Runs of name characters and of whitespace, and single other characters.
+fn char_tokens(input: &str) -> Vec<String> {
+    let mut chars = input.chars().peekable();
+    let mut tokens = Vec::new();
+
+    while let Some(&c) = chars.peek() {
+        let token = if c.is_alphanumeric() || c == '-' || c == '_' {
+            consume_while(&mut chars, |c| c.is_alphanumeric() || c == '-' || c == '_')
+        } else if c.is_whitespace() {
+            consume_while(&mut chars, char::is_whitespace)
+        } else {
+            chars.next();
+            c.to_string()
+        };
+        tokens.push(token);
+    }
+
+    tokens
+}
+
+fn consume_while<F>(chars: &mut Peekable<Chars>, condition: F) -> String
+where
+    F: Fn(char) -> bool,
+{
+    let mut result = String::new();
+    while let Some(&c) = chars.peek() {
+        if !condition(c) {
+            break;
+        }
+        result.push(chars.next().unwrap());
+    }
+    result
+}
+
+// The tag, id and classes of a compound selector.
+struct Compound<T> {
+    tag_name: Option<T>,
+    id: Option<T>,
+    classes: Vec<T>,
+}
+
+// An element the way the DOM stored it before interning: names are plain
+// strings and classes are split out of the attribute on every match.
+struct StringElement {
+    tag_name: String,
+    attributes: HashMap<String, String>,
+}
+
+// Runs the type, id and class part of selector matching over every element
+// and compound selector twice, once with the plain strings the DOM used
+// before atoms and once with the atoms it uses now.
+fn compare_name_matching(root_node: &Node, stylesheet: &browser_engine::css::StyleSheet) {
+    let mut elements = Vec::new();
+    collect_elements(root_node, &mut elements);
+
+    let compounds: Vec<Compound<String>> = stylesheet
+        .style_rules(&MediaEnvironment::default())
+        .iter()
+        .flat_map(|rule| rule.get_selectors())
+        .filter_map(|selector| parse_compound(&serialize_selector(selector, Mode::Minify)))
+        .collect();
+
+    let string_elements: Vec<StringElement> = elements
+        .iter()
+        .map(|element| {
+            let mut attributes = HashMap::new();
+            for name in &["id", "class"] {
+                if let Some(value) = element.get_attribute(name) {
+                    attributes.insert(name.to_string(), value.to_string());
+                }
+            }
+            StringElement {
+                tag_name: element.get_tag_name().to_string(),
+                attributes,
+            }
+        })
+        .collect();
+    let atom_compounds: Vec<Compound<Atom>> = compounds
+        .iter()
+        .map(|compound| Compound {
+            tag_name: compound.tag_name.as_ref().map(|name| Atom::from(&name[..])),
+            id: compound.id.as_ref().map(|id| Atom::from(&id[..])),
+            classes: compound
+                .classes
+                .iter()
+                .map(|class| Atom::from(&class[..]))
+                .collect(),
+        })
+        .collect();
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        for element in &string_elements {
+            for compound in &compounds {
+                black_box(string_matches(element, compound));
+            }
+        }
+    }
+    let strings = start.elapsed() / ITERATIONS;
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        for element in &elements {
+            for compound in &atom_compounds {
+                black_box(atom_matches(element, compound));
+            }
+        }
+    }
+    let atoms = start.elapsed() / ITERATIONS;
+
+    println!(
+        "tag, id and class matching, example page x{0}: strings {1:?}, atoms {2:?} per iteration",
+        SCALE, strings, atoms
+    );
+}
+
+fn collect_elements<'a>(node: &'a Node, elements: &mut Vec<&'a ElementData>) {
+    if let Some(element) = node.get_element() {
+        elements.push(element);
+    }
+    for child in node.get_children() {
+        collect_elements(child, elements);
+    }
+}
+
+// Only plain `tag#id.class` compounds take part; anything else is skipped.
+fn parse_compound(selector: &str) -> Option<Compound<String>> {
+    let is_name = |c: char| c.is_alphanumeric() || c == '-' || c == '_';
+    if !selector.chars().all(|c| is_name(c) || c == '#' || c == '.') {
+        return None;
+    }
+
+    let mut compound = Compound {
+        tag_name: None,
+        id: None,
+        classes: Vec::new(),
+    };
+    let mut rest = selector;
+    while !rest.is_empty() {
+        let prefix = rest.chars().next().filter(|&c| c == '#' || c == '.');
+        let start = prefix.map_or(0, |_| 1);
+        let end = rest[start..]
+            .find(['#', '.'])
+            .map_or(rest.len(), |i| i + start);
+        let name = rest[start..end].to_string();
+
+        match prefix {
+            Some('#') => compound.id = Some(name),
+            Some(_) => compound.classes.push(name),
+            None => compound.tag_name = Some(name),
+        }
+        rest = &rest[end..];
+    }
+
+    Some(compound)
+}
+
+fn string_matches(element: &StringElement, compound: &Compound<String>) -> bool {
+    if compound
+        .tag_name
+        .as_ref()
+        .is_some_and(|name| *name != element.tag_name)
+    {
+        return false;
+    }
+    if compound.id.is_some() && compound.id.as_ref() != element.attributes.get("id") {
+        return false;
+    }
+
+    let classes: HashSet<&str> = match element.attributes.get("class") {
+        Some(classes) => classes.split(' ').collect(),
+        None => HashSet::new(),
+    };
+    compound
+        .classes
+        .iter()
+        .all(|class| classes.contains(&class[..]))
+}
+
+fn atom_matches(element: &ElementData, compound: &Compound<Atom>) -> bool {
+    if compound
+        .tag_name
+        .is_some_and(|name| name != *element.get_tag_name())
+    {
+        return false;
+    }
+    if compound.id.is_some() && compound.id.as_ref() != element.get_id() {
+        return false;
+    }
+
+    compound
+        .classes
+        .iter()
+        .all(|class| element.get_classes().contains(class))
+}
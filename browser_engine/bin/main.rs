@@ -1,13 +1,21 @@
 extern crate browser_engine;
-use browser_engine::{command, css, css_parser, dom, html_parser, layout, render, style};
+use browser_engine::{
+    command, css, css_loader, css_parser, dom, html_parser, layout, media, render, style,
+    xml_parser,
+};
 
 use std::env;
 use std::fs::File;
 use std::io::{BufReader, Read};
 
+// The document named on the command line, or the example page. XML and
+// XHTML files go through the XML parser.
 fn get_html() -> Vec<dom::Node> {
     let mut path = env::current_dir().unwrap();
-    path.push("example/example.html");
+    match env::args().nth(1) {
+        Some(file) => path.push(file),
+        None => path.push("example/example.html"),
+    }
 
     let mut file_reader = match File::open(&path) {
         Ok(file) => BufReader::new(file),
@@ -16,9 +24,14 @@ fn get_html() -> Vec<dom::Node> {
 
     let mut html_input = String::new();
     file_reader.read_to_string(&mut html_input).unwrap();
-    let nodes = html_parser::HtmlParser::new(&html_input).parse_nodes();
+    let file = path.to_string_lossy();
 
-    nodes
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("xhtml") | Some("xml") => xml_parser::XmlParser::new_with_file(&html_input, &file)
+            .parse_nodes()
+            .unwrap_or_else(|error| panic!(" {0}", error)),
+        _ => html_parser::HtmlParser::new_with_file(&html_input, &file).parse_nodes(),
+    }
 }
 
 fn get_css() -> css::StyleSheet {
@@ -32,7 +45,12 @@ fn get_css() -> css::StyleSheet {
 
     let mut css_input = String::new();
     file_reader.read_to_string(&mut css_input).unwrap();
-    let stylesheet = css_parser::CssParser::new(&css_input).parse_stylesheet();
+    let url = path.to_string_lossy();
+    let mut stylesheet = css_parser::CssParser::new_with_file(&css_input, &url).parse_stylesheet();
+
+    for error in css_loader::load_imports(&mut stylesheet, &url, &mut css_loader::FileLoader) {
+        eprintln!("{0}", error);
+    }
 
     stylesheet
 }
@@ -43,19 +61,20 @@ fn main() {
         dom::pretty_print(node, 0);
     }
 
-    let ref root_node = nodes[0];
+    let root_node = &nodes[0];
 
     let stylesheet = get_css();
     println!("{:?}", stylesheet);
 
-    let style_tree_root = style::StyledNode::new(&root_node, &stylesheet);
-    style::pretty_print(&style_tree_root, 0);
-
     let mut viewport = layout::Dimensions::default();
     viewport.content.width = 1024.0;
     viewport.content.height = 768.0;
 
-    let layout_tree = layout::layout_tree(&style_tree_root, viewport);
+    let media = media::MediaEnvironment::new(viewport.content.width, viewport.content.height);
+    let style_tree_root = style::StyledNode::new_with_media(root_node, &stylesheet, &media);
+    style::pretty_print(&style_tree_root, 0);
+
+    let layout_tree = layout::layout_tree(&style_tree_root, viewport, &media);
     layout::pretty_print(&layout_tree, 0);
 
     let display_commands = command::build_display_commands(&layout_tree);
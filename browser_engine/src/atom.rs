@@ -0,0 +1,114 @@
+use std::borrow::Borrow;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::ptr;
+use std::sync::{Mutex, OnceLock};
+
+// Interned strings live for the rest of the program, so two atoms are equal
+// exactly when they point at the same table entry and copying one is free.
+// The cost is that nothing is ever freed: memory grows with the number of
+// distinct names, ids and classes seen, not with the size of the documents.
+// The table is split into shards so threads interning different strings
+// rarely wait on the same lock.
+#[derive(Clone, Copy)]
+pub struct Atom(&'static str);
+
+const SHARDS: usize = 16;
+
+type Shard = Mutex<HashSet<&'static str>>;
+
+fn shard(string: &str) -> &'static Shard {
+    static TABLE: OnceLock<Vec<Shard>> = OnceLock::new();
+    let table = TABLE.get_or_init(|| (0..SHARDS).map(|_| Mutex::new(HashSet::new())).collect());
+
+    let mut hasher = DefaultHasher::new();
+    string.hash(&mut hasher);
+    &table[hasher.finish() as usize % SHARDS]
+}
+
+impl Atom {
+    pub fn new(string: &str) -> Atom {
+        let mut shard = shard(string).lock().unwrap();
+
+        match shard.get(string) {
+            Some(interned) => Atom(interned),
+            None => {
+                let interned: &'static str = Box::leak(string.to_string().into_boxed_str());
+                shard.insert(interned);
+                Atom(interned)
+            }
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        self.0
+    }
+}
+
+impl PartialEq for Atom {
+    fn eq(&self, other: &Atom) -> bool {
+        ptr::eq(self.0, other.0)
+    }
+}
+
+impl Eq for Atom {}
+
+impl PartialEq<str> for Atom {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl<'a> PartialEq<&'a str> for Atom {
+    fn eq(&self, other: &&'a str) -> bool {
+        self.0 == *other
+    }
+}
+
+// Hashes like the underlying str so maps keyed by Atom can be queried with &str.
+impl Hash for Atom {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+impl Borrow<str> for Atom {
+    fn borrow(&self) -> &str {
+        self.0
+    }
+}
+
+impl Deref for Atom {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.0
+    }
+}
+
+impl<'a> From<&'a str> for Atom {
+    fn from(string: &'a str) -> Atom {
+        Atom::new(string)
+    }
+}
+
+impl From<String> for Atom {
+    fn from(string: String) -> Atom {
+        Atom::new(&string)
+    }
+}
+
+impl fmt::Debug for Atom {
+    fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
+        write!(format, "{0:?}", self.0)
+    }
+}
+
+impl fmt::Display for Atom {
+    fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
+        write!(format, "{0}", self.0)
+    }
+}
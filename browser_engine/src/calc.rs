@@ -0,0 +1,230 @@
+use crate::css::Calc;
+use crate::css_parser::{lowercase, split_commas, translate_unit};
+use crate::css_tokenizer::Token;
+
+#[derive(PartialEq, Clone, Copy)]
+enum CalcType {
+    Number,
+    Length,
+    Percentage,
+    LengthPercentage,
+}
+
+struct CalcParser<'t, 'a> {
+    tokens: &'t [Token<'a>],
+    index: usize,
+}
+
+impl<'t, 'a> CalcParser<'t, 'a> {
+    fn new(tokens: &'t [Token<'a>]) -> CalcParser<'t, 'a> {
+        CalcParser { tokens, index: 0 }
+    }
+
+    fn peek(&self) -> Option<&'t Token<'a>> {
+        self.tokens.get(self.index)
+    }
+
+    // Returns whether any whitespace was skipped.
+    fn skip_whitespace(&mut self) -> bool {
+        let start = self.index;
+        while let Some(Token::Whitespace) = self.peek() {
+            self.index += 1;
+        }
+        self.index > start
+    }
+
+    // A whole argument: a sum with nothing after it.
+    fn parse_argument(&mut self) -> Option<Calc> {
+        let calc = self.parse_sum()?;
+        self.skip_whitespace();
+
+        match self.peek() {
+            None => Some(calc),
+            Some(_) => None,
+        }
+    }
+
+    // `+` and `-` must have whitespace on both sides, so `1px -2px` is not
+    // a subtraction.
+    fn parse_sum(&mut self) -> Option<Calc> {
+        let mut left = self.parse_product()?;
+
+        loop {
+            let start = self.index;
+            let spaced = self.skip_whitespace();
+
+            match self.peek() {
+                Some(&Token::Delim(operator)) if spaced && (operator == '+' || operator == '-') => {
+                    self.index += 1;
+                    if !self.skip_whitespace() {
+                        return None;
+                    }
+                    let right = self.parse_product()?;
+                    left = Calc::Operation(operator, Box::new(left), Box::new(right));
+                }
+                _ => {
+                    self.index = start;
+                    return Some(left);
+                }
+            }
+        }
+    }
+
+    fn parse_product(&mut self) -> Option<Calc> {
+        let mut left = self.parse_value()?;
+
+        loop {
+            let start = self.index;
+            self.skip_whitespace();
+
+            match self.peek() {
+                Some(&Token::Delim(operator)) if operator == '*' || operator == '/' => {
+                    self.index += 1;
+                    let right = self.parse_value()?;
+                    left = Calc::Operation(operator, Box::new(left), Box::new(right));
+                }
+                _ => {
+                    self.index = start;
+                    return Some(left);
+                }
+            }
+        }
+    }
+
+    fn parse_value(&mut self) -> Option<Calc> {
+        self.skip_whitespace();
+        let token = self.peek()?;
+        self.index += 1;
+
+        let calc = match *token {
+            Token::Number(ref number) => Calc::Number(number.value),
+            Token::Percentage(ref number) => Calc::Percentage(number.value),
+            Token::Dimension(ref number, ref unit) => {
+                Calc::Length(number.value, translate_unit(unit)?)
+            }
+            Token::OpenParen => {
+                let calc = self.parse_sum()?;
+                self.skip_whitespace();
+                match self.peek() {
+                    Some(Token::CloseParen) => self.index += 1,
+                    _ => return None,
+                }
+                calc
+            }
+            Token::Function(ref name) => {
+                self.index -= 1;
+                self.parse_function(name)?
+            }
+            _ => return None,
+        };
+
+        Some(calc)
+    }
+
+    fn parse_function(&mut self, name: &str) -> Option<Calc> {
+        let close = self.index + 1 + closing_paren(&self.tokens[self.index + 1..])?;
+        let arguments = split_commas(&self.tokens[self.index + 1..close])
+            .into_iter()
+            .map(|argument| CalcParser::new(argument).parse_argument())
+            .collect::<Option<Vec<_>>>()?;
+        self.index = close + 1;
+
+        let mut arguments = arguments.into_iter();
+        let calc = match &name.to_ascii_lowercase()[..] {
+            "calc" if arguments.len() == 1 => arguments.next()?,
+            "min" => Calc::Min(arguments.collect()),
+            "max" => Calc::Max(arguments.collect()),
+            "clamp" if arguments.len() == 3 => Calc::Clamp(
+                Box::new(arguments.next()?),
+                Box::new(arguments.next()?),
+                Box::new(arguments.next()?),
+            ),
+            _ => return None,
+        };
+
+        Some(calc)
+    }
+}
+
+pub fn is_math_function(name: &str) -> bool {
+    matches!(&lowercase(name)[..], "calc" | "min" | "max" | "clamp")
+}
+
+// Parses a single math function that resolves to a length, a percentage or
+// a mix of both.
+pub fn parse_length_percentage(tokens: &[Token]) -> Option<Calc> {
+    let calc = match *tokens {
+        [Token::Function(ref name), ..] if is_math_function(name) => {
+            let mut parser = CalcParser::new(tokens);
+            let calc = parser.parse_value()?;
+            parser.skip_whitespace();
+            if parser.peek().is_some() {
+                return None;
+            }
+            calc
+        }
+        _ => return None,
+    };
+
+    match calc_type(&calc)? {
+        CalcType::Number => None,
+        _ => Some(calc),
+    }
+}
+
+// Whether a math function resolves to a length without any percentage.
+pub fn is_length(calc: &Calc) -> bool {
+    calc_type(calc) == Some(CalcType::Length)
+}
+
+// Sums and comparisons need operands of the same type, although lengths and
+// percentages mix; products need a number on one side and quotients on the
+// right.
+fn calc_type(calc: &Calc) -> Option<CalcType> {
+    match *calc {
+        Calc::Number(_) => Some(CalcType::Number),
+        Calc::Percentage(_) => Some(CalcType::Percentage),
+        Calc::Length(..) => Some(CalcType::Length),
+        Calc::Operation(operator, ref left, ref right) => {
+            let (left, right) = (calc_type(left)?, calc_type(right)?);
+            match (operator, left, right) {
+                ('*', CalcType::Number, other) | ('*', other, CalcType::Number) => Some(other),
+                ('/', other, CalcType::Number) => Some(other),
+                ('+', ..) | ('-', ..) => combine(left, right),
+                _ => None,
+            }
+        }
+        Calc::Min(ref calcs) | Calc::Max(ref calcs) => {
+            let mut types = calcs.iter().map(calc_type);
+            let first = types.next()??;
+            types.try_fold(first, |combined, calc_type| combine(combined, calc_type?))
+        }
+        Calc::Clamp(ref min, ref value, ref max) => combine(
+            combine(calc_type(min)?, calc_type(value)?)?,
+            calc_type(max)?,
+        ),
+    }
+}
+
+fn combine(left: CalcType, right: CalcType) -> Option<CalcType> {
+    match (left, right) {
+        _ if left == right => Some(left),
+        (CalcType::Number, _) | (_, CalcType::Number) => None,
+        _ => Some(CalcType::LengthPercentage),
+    }
+}
+
+fn closing_paren(tokens: &[Token]) -> Option<usize> {
+    let mut depth = 0;
+
+    for (index, token) in tokens.iter().enumerate() {
+        match *token {
+            Token::Function(_) | Token::OpenParen => depth += 1,
+            Token::CloseParen if depth == 0 => return Some(index),
+            Token::CloseParen => depth -= 1,
+            _ => {}
+        }
+    }
+
+    None
+}
@@ -0,0 +1,913 @@
+use crate::css::{Color, ColorSpace, Value};
+use crate::css_parser::{split_commas, trim_whitespace, TokenList};
+use crate::css_tokenizer::Token;
+
+use std::collections::HashMap;
+use std::f32::consts::PI;
+use std::sync::OnceLock;
+
+#[derive(Clone, Copy)]
+enum Component {
+    Number(f32),
+    Percentage(f32),
+    Angle(f32),
+    None,
+}
+
+// Parses a complete <color> value. `currentcolor` is kept symbolic since it
+// depends on the element's own `color`.
+pub fn parse_color(tokens: &TokenList) -> Option<Value> {
+    match *tokens {
+        [(Token::Hash(ref hex), _)] | [(Token::IdHash(ref hex), _)] => {
+            parse_hex(hex).map(Value::Color)
+        }
+        [(Token::Ident(ref name), _)] if name.eq_ignore_ascii_case("currentcolor") => {
+            Some(Value::CurrentColor)
+        }
+        [(Token::Ident(ref name), _)] => named_color(&name.to_ascii_lowercase()).map(Value::Color),
+        [(Token::Function(ref name), _), ref arguments @ .., (Token::CloseParen, _)] => {
+            let color = match &name.to_ascii_lowercase()[..] {
+                "rgb" | "rgba" => parse_rgb(arguments)?,
+                "hsl" | "hsla" => parse_hsl(arguments)?,
+                "hwb" => parse_hwb(arguments)?,
+                "lab" => parse_lab(ColorSpace::Lab, arguments)?,
+                "lch" => parse_lab(ColorSpace::Lch, arguments)?,
+                "oklab" => parse_lab(ColorSpace::Oklab, arguments)?,
+                "oklch" => parse_lab(ColorSpace::Oklch, arguments)?,
+                "color" => parse_color_function(arguments)?,
+                "color-mix" => parse_color_mix(arguments)?,
+                _ => return None,
+            };
+            Some(Value::Color(color))
+        }
+        _ => None,
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<Color> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).unwrap() as f32 / 15.0;
+    let pair = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap() as f32 / 255.0;
+
+    match hex.len() {
+        3 => Some(Color::new(digit(0), digit(1), digit(2), 1.0)),
+        4 => Some(Color::new(digit(0), digit(1), digit(2), digit(3))),
+        6 => Some(Color::new(pair(0), pair(2), pair(4), 1.0)),
+        8 => Some(Color::new(pair(0), pair(2), pair(4), pair(6))),
+        _ => None,
+    }
+}
+
+// rgb(255 0 0 / 50%), rgb(100%, 0%, 0%) or rgba(255, 0, 0, 0.5)
+fn parse_rgb(arguments: &TokenList) -> Option<Color> {
+    let (channels, alpha, legacy) = parse_arguments(arguments)?;
+
+    if legacy {
+        let numbers = channels.iter().filter(|c| is_number(c)).count();
+        if numbers != 0 && numbers != channels.len() {
+            return None;
+        }
+    }
+
+    let mut rgb = [0.0; 3];
+    for (i, channel) in channels.iter().enumerate() {
+        rgb[i] = match *channel {
+            Component::Number(n) => n / 255.0,
+            Component::Percentage(p) => p / 100.0,
+            Component::None => 0.0,
+            Component::Angle(_) => return None,
+        };
+    }
+
+    Some(Color::new(
+        clamp(rgb[0]),
+        clamp(rgb[1]),
+        clamp(rgb[2]),
+        parse_alpha(alpha)?,
+    ))
+}
+
+// hsl(120deg 100% 50%) or hsl(120, 100%, 50%)
+fn parse_hsl(arguments: &TokenList) -> Option<Color> {
+    let (channels, alpha, legacy) = parse_arguments(arguments)?;
+
+    let hue = parse_hue(channels[0])?;
+    let saturation = clamp(parse_percentage(channels[1], legacy)?);
+    let lightness = clamp(parse_percentage(channels[2], legacy)?);
+
+    let (red, green, blue) = hsl_to_rgb(hue, saturation, lightness);
+    Some(Color::new(red, green, blue, parse_alpha(alpha)?))
+}
+
+// hwb(120deg 10% 20%); there is no legacy comma syntax.
+fn parse_hwb(arguments: &TokenList) -> Option<Color> {
+    let (channels, alpha, legacy) = parse_arguments(arguments)?;
+    if legacy {
+        return None;
+    }
+
+    let hue = parse_hue(channels[0])?;
+    let white = clamp(parse_percentage(channels[1], false)?);
+    let black = clamp(parse_percentage(channels[2], false)?);
+    let alpha = parse_alpha(alpha)?;
+
+    if white + black >= 1.0 {
+        let gray = white / (white + black);
+        return Some(Color::new(gray, gray, gray, alpha));
+    }
+
+    let (red, green, blue) = hsl_to_rgb(hue, 1.0, 0.5);
+    let scale = 1.0 - white - black;
+
+    Some(Color::new(
+        red * scale + white,
+        green * scale + white,
+        blue * scale + white,
+        alpha,
+    ))
+}
+
+// lab(), lch(), oklab() and oklch(). Percentages scale to each channel's
+// reference range.
+fn parse_lab(space: ColorSpace, arguments: &TokenList) -> Option<Color> {
+    let (channels, alpha, legacy) = parse_arguments(arguments)?;
+    if legacy {
+        return None;
+    }
+
+    let (lightness, chroma) = match space {
+        ColorSpace::Lab => (100.0, 125.0),
+        ColorSpace::Lch => (100.0, 150.0),
+        ColorSpace::Oklab => (1.0, 0.4),
+        _ => (1.0, 0.4),
+    };
+    let polar = space == ColorSpace::Lch || space == ColorSpace::Oklch;
+
+    let l = parse_scaled(channels[0], lightness)?.max(0.0);
+    let (c1, c2) = if polar {
+        (
+            parse_scaled(channels[1], chroma)?.max(0.0),
+            parse_hue(channels[2])?.rem_euclid(360.0),
+        )
+    } else {
+        (
+            parse_scaled(channels[1], chroma)?,
+            parse_scaled(channels[2], chroma)?,
+        )
+    };
+
+    Some(Color::new_with_space(
+        space,
+        [l, c1, c2],
+        parse_alpha(alpha)?,
+    ))
+}
+
+// color(display-p3 1 0 0 / 0.5)
+fn parse_color_function(arguments: &TokenList) -> Option<Color> {
+    let start = arguments
+        .iter()
+        .position(|(token, _)| *token != Token::Whitespace)?;
+
+    let space = match arguments[start].0 {
+        Token::Ident(ref name) => match &name.to_ascii_lowercase()[..] {
+            "srgb" => ColorSpace::Srgb,
+            "srgb-linear" => ColorSpace::SrgbLinear,
+            "display-p3" => ColorSpace::DisplayP3,
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    let (channels, alpha, legacy) = parse_arguments(&arguments[start + 1..])?;
+    if legacy {
+        return None;
+    }
+
+    let mut values = [0.0; 3];
+    for (i, channel) in channels.into_iter().enumerate() {
+        values[i] = parse_scaled(channel, 1.0)?;
+    }
+
+    Some(Color::new_with_space(space, values, parse_alpha(alpha)?))
+}
+
+// color-mix(in <space> [<hue-method> hue]?, <color> <percentage>?, <color> <percentage>?)
+fn parse_color_mix(arguments: &TokenList) -> Option<Color> {
+    let parts = split_commas(arguments);
+    if parts.len() != 3 {
+        return None;
+    }
+
+    let method: Vec<String> = parts[0]
+        .iter()
+        .filter(|(token, _)| *token != Token::Whitespace)
+        .map(|(token, _)| match *token {
+            Token::Ident(ref name) => name.to_ascii_lowercase(),
+            _ => String::new(),
+        })
+        .collect();
+
+    let (space, hue_method) = match method.iter().map(|s| &s[..]).collect::<Vec<&str>>()[..] {
+        ["in", space] => (interpolation_space(space)?, HueMethod::Shorter),
+        ["in", space, method, "hue"] => {
+            let space = interpolation_space(space)?;
+            if space != ColorSpace::Lch && space != ColorSpace::Oklch {
+                return None;
+            }
+            let method = match method {
+                "shorter" => HueMethod::Shorter,
+                "longer" => HueMethod::Longer,
+                "increasing" => HueMethod::Increasing,
+                "decreasing" => HueMethod::Decreasing,
+                _ => return None,
+            };
+            (space, method)
+        }
+        _ => return None,
+    };
+
+    let (first, p1) = parse_mix_component(parts[1])?;
+    let (second, p2) = parse_mix_component(parts[2])?;
+
+    let (p1, p2) = match (p1, p2) {
+        (None, None) => (0.5, 0.5),
+        (Some(p1), None) => (p1, 1.0 - p1),
+        (None, Some(p2)) => (1.0 - p2, p2),
+        (Some(p1), Some(p2)) => (p1, p2),
+    };
+    let total = p1 + p2;
+    if total <= 0.0 {
+        return None;
+    }
+
+    let mut color = mix(&first, &second, p2 / total, space, hue_method);
+    if total < 1.0 {
+        color.alpha *= total;
+    }
+
+    Some(color)
+}
+
+fn parse_mix_component(tokens: &TokenList) -> Option<(Color, Option<f32>)> {
+    let tokens = trim_whitespace(tokens);
+
+    let (color, percentage) = match *tokens {
+        [(Token::Percentage(ref p), _), ref color @ ..] => (trim_whitespace(color), Some(p.value)),
+        [ref color @ .., (Token::Percentage(ref p), _)] => (trim_whitespace(color), Some(p.value)),
+        _ => (tokens, None),
+    };
+
+    let percentage = match percentage {
+        Some(p) if !(0.0..=100.0).contains(&p) => return None,
+        Some(p) => Some(p / 100.0),
+        None => None,
+    };
+
+    match parse_color(color)? {
+        Value::Color(color) => Some((color, percentage)),
+        _ => None,
+    }
+}
+
+fn interpolation_space(name: &str) -> Option<ColorSpace> {
+    match name {
+        "srgb" => Some(ColorSpace::Srgb),
+        "srgb-linear" => Some(ColorSpace::SrgbLinear),
+        "display-p3" => Some(ColorSpace::DisplayP3),
+        "lab" => Some(ColorSpace::Lab),
+        "lch" => Some(ColorSpace::Lch),
+        "oklab" => Some(ColorSpace::Oklab),
+        "oklch" => Some(ColorSpace::Oklch),
+        _ => None,
+    }
+}
+
+// Splits the arguments of a color function into three channels and an
+// optional alpha. The legacy syntax separates every argument with commas and
+// does not allow `none`; the modern one uses spaces and `/ alpha`.
+fn parse_arguments(arguments: &TokenList) -> Option<(Vec<Component>, Option<Component>, bool)> {
+    let tokens: Vec<&Token> = arguments
+        .iter()
+        .map(|(token, _)| token)
+        .filter(|token| **token != Token::Whitespace)
+        .collect();
+
+    let legacy = tokens.contains(&&Token::Comma);
+    let mut channels = Vec::new();
+    let mut alpha = None;
+
+    if legacy {
+        for (i, token) in tokens.iter().enumerate() {
+            match (i % 2, *token) {
+                (1, Token::Comma) => {}
+                (0, token) => match parse_component(token)? {
+                    Component::None => return None,
+                    component => channels.push(component),
+                },
+                _ => return None,
+            }
+        }
+        if tokens.len().is_multiple_of(2) || channels.len() < 3 || channels.len() > 4 {
+            return None;
+        }
+        if channels.len() == 4 {
+            alpha = channels.pop();
+        }
+    } else {
+        let slash = tokens.iter().position(|token| **token == Token::Delim('/'));
+
+        for token in &tokens[..slash.unwrap_or(tokens.len())] {
+            channels.push(parse_component(token)?);
+        }
+        if let Some(slash) = slash {
+            match tokens[slash + 1..] {
+                [token] => alpha = Some(parse_component(token)?),
+                _ => return None,
+            }
+        }
+        if channels.len() != 3 {
+            return None;
+        }
+    }
+
+    Some((channels, alpha, legacy))
+}
+
+fn parse_component(token: &Token) -> Option<Component> {
+    match *token {
+        Token::Number(ref n) => Some(Component::Number(n.value)),
+        Token::Percentage(ref n) => Some(Component::Percentage(n.value)),
+        Token::Dimension(ref n, ref unit) => {
+            let degrees = match &unit.to_ascii_lowercase()[..] {
+                "deg" => n.value,
+                "grad" => n.value * 360.0 / 400.0,
+                "rad" => n.value * 180.0 / PI,
+                "turn" => n.value * 360.0,
+                _ => return None,
+            };
+            Some(Component::Angle(degrees))
+        }
+        Token::Ident(ref name) if name.eq_ignore_ascii_case("none") => Some(Component::None),
+        _ => None,
+    }
+}
+
+fn parse_hue(component: Component) -> Option<f32> {
+    match component {
+        Component::Number(degrees) | Component::Angle(degrees) => Some(degrees),
+        Component::None => Some(0.0),
+        Component::Percentage(_) => None,
+    }
+}
+
+// The modern syntax accepts plain numbers where percentages are expected.
+fn parse_percentage(component: Component, legacy: bool) -> Option<f32> {
+    match component {
+        Component::Percentage(p) => Some(p / 100.0),
+        Component::Number(n) if !legacy => Some(n / 100.0),
+        Component::None => Some(0.0),
+        _ => None,
+    }
+}
+
+fn parse_scaled(component: Component, reference: f32) -> Option<f32> {
+    match component {
+        Component::Number(n) => Some(n),
+        Component::Percentage(p) => Some(p / 100.0 * reference),
+        Component::None => Some(0.0),
+        Component::Angle(_) => None,
+    }
+}
+
+fn parse_alpha(alpha: Option<Component>) -> Option<f32> {
+    match alpha {
+        None => Some(1.0),
+        Some(Component::Number(n)) => Some(clamp(n)),
+        Some(Component::Percentage(p)) => Some(clamp(p / 100.0)),
+        Some(Component::None) => Some(0.0),
+        Some(Component::Angle(_)) => None,
+    }
+}
+
+fn is_number(component: &Component) -> bool {
+    matches!(*component, Component::Number(_))
+}
+
+fn hsl_to_rgb(hue: f32, saturation: f32, lightness: f32) -> (f32, f32, f32) {
+    let hue = hue.rem_euclid(360.0);
+    let a = saturation * lightness.min(1.0 - lightness);
+
+    let channel = |n: f32| {
+        let k = (n + hue / 30.0) % 12.0;
+        lightness - a * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0)
+    };
+
+    (channel(0.0), channel(8.0), channel(4.0))
+}
+
+fn clamp(value: f32) -> f32 {
+    value.clamp(0.0, 1.0)
+}
+
+#[derive(PartialEq, Clone, Copy)]
+enum HueMethod {
+    Shorter,
+    Longer,
+    Increasing,
+    Decreasing,
+}
+
+type Matrix = [[f32; 3]; 3];
+
+const LINEAR_SRGB_TO_XYZ: Matrix = [
+    [0.412_390_8, 0.357_584_33, 0.180_480_8],
+    [0.212_639, 0.715_168_65, 0.072_192_32],
+    [0.019_330_818, 0.119_194_78, 0.950_532_14],
+];
+const XYZ_TO_LINEAR_SRGB: Matrix = [
+    [3.240_97, -1.537_383_2, -0.498_610_76],
+    [-0.969_243_65, 1.875_967_5, 0.041_555_06],
+    [0.055_630_08, -0.203_976_96, 1.056_971_5],
+];
+const LINEAR_P3_TO_XYZ: Matrix = [
+    [0.486_570_95, 0.265_667_7, 0.198_217_29],
+    [0.228_974_57, 0.691_738_55, 0.079_286_91],
+    [0.0, 0.045_113_38, 1.043_944_4],
+];
+const XYZ_TO_LINEAR_P3: Matrix = [
+    [2.493_497, -0.931_383_6, -0.402_710_8],
+    [-0.829_489, 1.762_664_1, 0.023_624_687],
+    [0.035_845_83, -0.076_172_39, 0.956_884_5],
+];
+const D65_TO_D50: Matrix = [
+    [1.047_929_8, 0.022_946_794, -0.050_192_23],
+    [0.029_627_815, 0.990_434_47, -0.017_073_825],
+    [-0.009_243_058, 0.015_055_145, 0.751_874_27],
+];
+const D50_TO_D65: Matrix = [
+    [0.955_473_4, -0.023_098_538, 0.063_259_31],
+    [-0.028_369_706, 1.009_995_5, 0.021_041_399],
+    [0.012_314_002, -0.020_507_697, 1.330_365_9],
+];
+const XYZ_TO_LMS: Matrix = [
+    [0.819_022_4, 0.361_906_26, -0.128_873_78],
+    [0.032_983_653, 0.929_286_84, 0.036_144_666],
+    [0.048_177_19, 0.264_239_52, 0.633_547_84],
+];
+const LMS_TO_XYZ: Matrix = [
+    [1.226_879_8, -0.557_815, 0.281_391_05],
+    [-0.040_575_746, 1.112_286_8, -0.071_711_056],
+    [-0.076_372_94, -0.421_493_32, 1.586_924_1],
+];
+const LMS_TO_OKLAB: Matrix = [
+    [0.210_454_27, 0.793_617_8, -0.004_072_043],
+    [1.977_998_5, -2.428_592_2, 0.450_593_7],
+    [0.025_904_043, 0.782_771_7, -0.808_675_77],
+];
+const OKLAB_TO_LMS: Matrix = [
+    [1.0, 0.396_337_78, 0.215_803_76],
+    [1.0, -0.105_561_346, -0.063_854_17],
+    [1.0, -0.089_484_18, -1.291_485_5],
+];
+const D50_WHITE: [f32; 3] = [0.964_295_7, 1.0, 0.825_104_6];
+
+const LAB_EPSILON: f32 = 216.0 / 24389.0;
+const LAB_KAPPA: f32 = 24389.0 / 27.0;
+
+// Colors are interpolated with premultiplied alpha; hues follow the
+// requested hue interpolation method.
+fn mix(first: &Color, second: &Color, amount: f32, space: ColorSpace, method: HueMethod) -> Color {
+    let a = first.convert(space);
+    let b = second.convert(space);
+    let alpha = a.alpha + (b.alpha - a.alpha) * amount;
+    let hue = match space {
+        ColorSpace::Lch | ColorSpace::Oklch => Some(2),
+        _ => None,
+    };
+
+    let mut channels = [0.0; 3];
+    for (i, channel) in channels.iter_mut().enumerate() {
+        if Some(i) == hue {
+            let (h1, h2) = adjust_hues(a.channels[i], b.channels[i], method);
+            *channel = (h1 + (h2 - h1) * amount).rem_euclid(360.0);
+        } else {
+            let premultiplied = a.channels[i] * a.alpha
+                + (b.channels[i] * b.alpha - a.channels[i] * a.alpha) * amount;
+            *channel = if alpha > 0.0 {
+                premultiplied / alpha
+            } else {
+                0.0
+            };
+        }
+    }
+
+    Color::new_with_space(space, channels, alpha)
+}
+
+fn adjust_hues(mut h1: f32, mut h2: f32, method: HueMethod) -> (f32, f32) {
+    let difference = h2 - h1;
+
+    match method {
+        HueMethod::Shorter if difference > 180.0 => h1 += 360.0,
+        HueMethod::Shorter if difference < -180.0 => h2 += 360.0,
+        HueMethod::Longer if difference > 0.0 && difference < 180.0 => h1 += 360.0,
+        HueMethod::Longer if difference > -180.0 && difference <= 0.0 => h2 += 360.0,
+        HueMethod::Increasing if difference < 0.0 => h2 += 360.0,
+        HueMethod::Decreasing if difference > 0.0 => h1 += 360.0,
+        _ => {}
+    }
+
+    (h1, h2)
+}
+
+impl Color {
+    pub fn convert(&self, space: ColorSpace) -> Color {
+        if self.space == space {
+            return self.clone();
+        }
+
+        let channels = from_xyz(space, to_xyz(self.space, self.channels));
+        Color::new_with_space(space, channels, self.alpha)
+    }
+
+    // Colors outside the sRGB gamut are brought in by reducing OKLCH chroma
+    // until clipping is no longer noticeable, as in CSS Color 4.
+    pub fn to_srgb(&self) -> Color {
+        let [r, g, b] = self.convert(ColorSpace::Srgb).channels;
+        if in_gamut([r, g, b]) {
+            return Color::new(clamp(r), clamp(g), clamp(b), self.alpha);
+        }
+
+        let origin = self.convert(ColorSpace::Oklch);
+        let [lightness, chroma, hue] = origin.channels;
+        if lightness >= 1.0 {
+            return Color::new(1.0, 1.0, 1.0, self.alpha);
+        }
+        if lightness <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0, self.alpha);
+        }
+
+        const JND: f32 = 0.02;
+        const EPSILON: f32 = 0.0001;
+
+        let oklch =
+            |chroma: f32| Color::new_with_space(ColorSpace::Oklch, [lightness, chroma, hue], 1.0);
+        let clip = |color: &Color| {
+            let [r, g, b] = color.convert(ColorSpace::Srgb).channels;
+            Color::new(clamp(r), clamp(g), clamp(b), 1.0)
+        };
+
+        let mut current = oklch(chroma);
+        let mut clipped = clip(&current);
+        if delta_eok(&clipped, &current) < JND {
+            return Color::new_with_space(ColorSpace::Srgb, clipped.channels, self.alpha);
+        }
+
+        let mut min = 0.0;
+        let mut max = chroma;
+        let mut min_in_gamut = true;
+
+        while max - min > EPSILON {
+            let chroma = (min + max) / 2.0;
+            current = oklch(chroma);
+
+            if min_in_gamut && in_gamut(current.convert(ColorSpace::Srgb).channels) {
+                min = chroma;
+                continue;
+            }
+
+            clipped = clip(&current);
+            let error = delta_eok(&clipped, &current);
+            if error < JND {
+                if JND - error < EPSILON {
+                    break;
+                }
+                min_in_gamut = false;
+                min = chroma;
+            } else {
+                max = chroma;
+            }
+        }
+
+        Color::new_with_space(ColorSpace::Srgb, clipped.channels, self.alpha)
+    }
+}
+
+fn delta_eok(first: &Color, second: &Color) -> f32 {
+    let [l1, a1, b1] = first.convert(ColorSpace::Oklab).channels;
+    let [l2, a2, b2] = second.convert(ColorSpace::Oklab).channels;
+
+    ((l1 - l2).powi(2) + (a1 - a2).powi(2) + (b1 - b2).powi(2)).sqrt()
+}
+
+fn in_gamut(channels: [f32; 3]) -> bool {
+    const EPSILON: f32 = 0.000_1;
+    channels
+        .iter()
+        .all(|c| (-EPSILON..=1.0 + EPSILON).contains(c))
+}
+
+// Converts to CIE XYZ relative to D65.
+fn to_xyz(space: ColorSpace, channels: [f32; 3]) -> [f32; 3] {
+    match space {
+        ColorSpace::Srgb => multiply(&LINEAR_SRGB_TO_XYZ, linearize(channels)),
+        ColorSpace::SrgbLinear => multiply(&LINEAR_SRGB_TO_XYZ, channels),
+        ColorSpace::DisplayP3 => multiply(&LINEAR_P3_TO_XYZ, linearize(channels)),
+        ColorSpace::Lab => multiply(&D50_TO_D65, lab_to_xyz_d50(channels)),
+        ColorSpace::Lch => multiply(&D50_TO_D65, lab_to_xyz_d50(polar_to_rectangular(channels))),
+        ColorSpace::Oklab => oklab_to_xyz(channels),
+        ColorSpace::Oklch => oklab_to_xyz(polar_to_rectangular(channels)),
+    }
+}
+
+fn from_xyz(space: ColorSpace, xyz: [f32; 3]) -> [f32; 3] {
+    match space {
+        ColorSpace::Srgb => gamma_encode(multiply(&XYZ_TO_LINEAR_SRGB, xyz)),
+        ColorSpace::SrgbLinear => multiply(&XYZ_TO_LINEAR_SRGB, xyz),
+        ColorSpace::DisplayP3 => gamma_encode(multiply(&XYZ_TO_LINEAR_P3, xyz)),
+        ColorSpace::Lab => xyz_d50_to_lab(multiply(&D65_TO_D50, xyz)),
+        ColorSpace::Lch => rectangular_to_polar(xyz_d50_to_lab(multiply(&D65_TO_D50, xyz))),
+        ColorSpace::Oklab => xyz_to_oklab(xyz),
+        ColorSpace::Oklch => rectangular_to_polar(xyz_to_oklab(xyz)),
+    }
+}
+
+// sRGB and Display P3 share a transfer function; it is extended to negative
+// values so out-of-gamut colors survive the round trip.
+fn linearize(channels: [f32; 3]) -> [f32; 3] {
+    let linear = |c: f32| {
+        let abs = c.abs();
+        if abs <= 0.04045 {
+            c / 12.92
+        } else {
+            c.signum() * ((abs + 0.055) / 1.055).powf(2.4)
+        }
+    };
+
+    [
+        linear(channels[0]),
+        linear(channels[1]),
+        linear(channels[2]),
+    ]
+}
+
+fn gamma_encode(channels: [f32; 3]) -> [f32; 3] {
+    let encode = |c: f32| {
+        let abs = c.abs();
+        if abs > 0.0031308 {
+            c.signum() * (1.055 * abs.powf(1.0 / 2.4) - 0.055)
+        } else {
+            12.92 * c
+        }
+    };
+
+    [
+        encode(channels[0]),
+        encode(channels[1]),
+        encode(channels[2]),
+    ]
+}
+
+fn lab_to_xyz_d50(lab: [f32; 3]) -> [f32; 3] {
+    let [l, a, b] = lab;
+    let f1 = (l + 16.0) / 116.0;
+    let f0 = a / 500.0 + f1;
+    let f2 = f1 - b / 200.0;
+
+    let x = if f0.powi(3) > LAB_EPSILON {
+        f0.powi(3)
+    } else {
+        (116.0 * f0 - 16.0) / LAB_KAPPA
+    };
+    let y = if l > LAB_KAPPA * LAB_EPSILON {
+        f1.powi(3)
+    } else {
+        l / LAB_KAPPA
+    };
+    let z = if f2.powi(3) > LAB_EPSILON {
+        f2.powi(3)
+    } else {
+        (116.0 * f2 - 16.0) / LAB_KAPPA
+    };
+
+    [x * D50_WHITE[0], y * D50_WHITE[1], z * D50_WHITE[2]]
+}
+
+fn xyz_d50_to_lab(xyz: [f32; 3]) -> [f32; 3] {
+    let f = |i: usize| {
+        let value = xyz[i] / D50_WHITE[i];
+        if value > LAB_EPSILON {
+            value.cbrt()
+        } else {
+            (LAB_KAPPA * value + 16.0) / 116.0
+        }
+    };
+    let (f0, f1, f2) = (f(0), f(1), f(2));
+
+    [116.0 * f1 - 16.0, 500.0 * (f0 - f1), 200.0 * (f1 - f2)]
+}
+
+fn oklab_to_xyz(oklab: [f32; 3]) -> [f32; 3] {
+    let lms = multiply(&OKLAB_TO_LMS, oklab);
+    multiply(
+        &LMS_TO_XYZ,
+        [lms[0].powi(3), lms[1].powi(3), lms[2].powi(3)],
+    )
+}
+
+fn xyz_to_oklab(xyz: [f32; 3]) -> [f32; 3] {
+    let lms = multiply(&XYZ_TO_LMS, xyz);
+    multiply(&LMS_TO_OKLAB, [lms[0].cbrt(), lms[1].cbrt(), lms[2].cbrt()])
+}
+
+fn polar_to_rectangular(lch: [f32; 3]) -> [f32; 3] {
+    let [l, c, h] = lch;
+    let radians = h.to_radians();
+    [l, c * radians.cos(), c * radians.sin()]
+}
+
+fn rectangular_to_polar(lab: [f32; 3]) -> [f32; 3] {
+    let [l, a, b] = lab;
+    let hue = b.atan2(a).to_degrees().rem_euclid(360.0);
+    [l, (a * a + b * b).sqrt(), hue]
+}
+
+fn multiply(matrix: &Matrix, vector: [f32; 3]) -> [f32; 3] {
+    let row = |r: &[f32; 3]| r[0] * vector[0] + r[1] * vector[1] + r[2] * vector[2];
+    [row(&matrix[0]), row(&matrix[1]), row(&matrix[2])]
+}
+
+pub fn named_color(name: &str) -> Option<Color> {
+    static COLORS: OnceLock<HashMap<&'static str, Color>> = OnceLock::new();
+
+    if name == "transparent" {
+        return Some(Color::new(0.0, 0.0, 0.0, 0.0));
+    }
+
+    let colors = COLORS.get_or_init(|| {
+        NAMED_COLORS
+            .iter()
+            .map(|&(name, red, green, blue)| {
+                let channel = |value: u8| value as f32 / 255.0;
+                (
+                    name,
+                    Color::new(channel(red), channel(green), channel(blue), 1.0),
+                )
+            })
+            .collect()
+    });
+    colors.get(name).cloned()
+}
+
+const NAMED_COLORS: [(&str, u8, u8, u8); 146] = [
+    ("black", 0, 0, 0),
+    ("silver", 192, 192, 192),
+    ("gray", 128, 128, 128),
+    ("grey", 128, 128, 128),
+    ("white", 255, 255, 255),
+    ("maroon", 128, 0, 0),
+    ("red", 255, 0, 0),
+    ("purple", 128, 0, 128),
+    ("fuchsia", 255, 0, 255),
+    ("green", 0, 128, 0),
+    ("lime", 0, 255, 0),
+    ("olive", 128, 128, 0),
+    ("yellow", 255, 255, 0),
+    ("navy", 0, 0, 128),
+    ("blue", 0, 0, 255),
+    ("teal", 0, 128, 128),
+    ("aqua", 0, 255, 255),
+    ("orange", 255, 165, 0),
+    ("aliceblue", 240, 248, 255),
+    ("antiquewhite", 250, 235, 215),
+    ("aquamarine", 127, 255, 212),
+    ("azure", 240, 255, 255),
+    ("beige", 245, 245, 220),
+    ("bisque", 255, 228, 196),
+    ("blanchedalmond", 255, 235, 205),
+    ("blueviolet", 138, 43, 226),
+    ("brown", 165, 42, 42),
+    ("burlywood", 222, 184, 135),
+    ("cadetblue", 95, 158, 160),
+    ("chartreuse", 127, 255, 0),
+    ("chocolate", 210, 105, 30),
+    ("coral", 255, 127, 80),
+    ("cornflowerblue", 100, 149, 237),
+    ("cornsilk", 255, 248, 220),
+    ("crimson", 220, 20, 60),
+    ("darkblue", 0, 0, 139),
+    ("darkcyan", 0, 139, 139),
+    ("darkgoldenrod", 184, 134, 11),
+    ("darkgray", 169, 169, 169),
+    ("darkgrey", 169, 169, 169),
+    ("darkgreen", 0, 100, 0),
+    ("darkkhaki", 189, 183, 107),
+    ("darkmagenta", 139, 0, 139),
+    ("darkolivegreen", 85, 107, 47),
+    ("darkorange", 255, 140, 0),
+    ("darkorchid", 153, 50, 204),
+    ("darkred", 139, 0, 0),
+    ("darksalmon", 233, 150, 122),
+    ("darkseagreen", 143, 188, 143),
+    ("darkslateblue", 72, 61, 139),
+    ("darkslategray", 47, 79, 79),
+    ("darkslategrey", 47, 79, 79),
+    ("darkturquoise", 0, 206, 209),
+    ("darkviolet", 148, 0, 211),
+    ("deeppink", 255, 20, 147),
+    ("deepskyblue", 0, 191, 255),
+    ("dimgray", 105, 105, 105),
+    ("dimgrey", 105, 105, 105),
+    ("dodgerblue", 30, 144, 255),
+    ("firebrick", 178, 34, 34),
+    ("floralwhite", 255, 250, 240),
+    ("forestgreen", 34, 139, 34),
+    ("gainsboro", 220, 220, 220),
+    ("ghostwhite", 248, 248, 255),
+    ("gold", 255, 215, 0),
+    ("goldenrod", 218, 165, 32),
+    ("greenyellow", 173, 255, 47),
+    ("honeydew", 240, 255, 240),
+    ("hotpink", 255, 105, 180),
+    ("indianred", 205, 92, 92),
+    ("indigo", 75, 0, 130),
+    ("ivory", 255, 255, 240),
+    ("khaki", 240, 230, 140),
+    ("lavender", 230, 230, 250),
+    ("lavenderblush", 255, 240, 245),
+    ("lawngreen", 124, 252, 0),
+    ("lemonchiffon", 255, 250, 205),
+    ("lightblue", 173, 216, 230),
+    ("lightcoral", 240, 128, 128),
+    ("lightcyan", 224, 255, 255),
+    ("lightgoldenrodyellow", 250, 250, 210),
+    ("lightgray", 211, 211, 211),
+    ("lightgrey", 211, 211, 211),
+    ("lightgreen", 144, 238, 144),
+    ("lightpink", 255, 182, 193),
+    ("lightsalmon", 255, 160, 122),
+    ("lightseagreen", 32, 178, 170),
+    ("lightskyblue", 135, 206, 250),
+    ("lightslategray", 119, 136, 153),
+    ("lightslategrey", 119, 136, 153),
+    ("lightsteelblue", 176, 196, 222),
+    ("lightyellow", 255, 255, 224),
+    ("limegreen", 50, 205, 50),
+    ("linen", 250, 240, 230),
+    ("mediumaquamarine", 102, 205, 170),
+    ("mediumblue", 0, 0, 205),
+    ("mediumorchid", 186, 85, 211),
+    ("mediumpurple", 147, 112, 219),
+    ("mediumseagreen", 60, 179, 113),
+    ("mediumslateblue", 123, 104, 238),
+    ("mediumspringgreen", 0, 250, 154),
+    ("mediumturquoise", 72, 209, 204),
+    ("mediumvioletred", 199, 21, 133),
+    ("midnightblue", 25, 25, 112),
+    ("mintcream", 245, 255, 250),
+    ("mistyrose", 255, 228, 225),
+    ("moccasin", 255, 228, 181),
+    ("navajowhite", 255, 222, 173),
+    ("oldlace", 253, 245, 230),
+    ("olivedrab", 107, 142, 35),
+    ("orangered", 255, 69, 0),
+    ("orchid", 218, 112, 214),
+    ("palegoldenrod", 238, 232, 170),
+    ("palegreen", 152, 251, 152),
+    ("paleturquoise", 175, 238, 238),
+    ("palevioletred", 219, 112, 147),
+    ("papayawhip", 255, 239, 213),
+    ("peachpuff", 255, 218, 185),
+    ("peru", 205, 133, 63),
+    ("pink", 255, 192, 203),
+    ("plum", 221, 160, 221),
+    ("powderblue", 176, 224, 230),
+    ("rosybrown", 188, 143, 143),
+    ("royalblue", 65, 105, 225),
+    ("saddlebrown", 139, 69, 19),
+    ("salmon", 250, 128, 114),
+    ("sandybrown", 244, 164, 96),
+    ("seagreen", 46, 139, 87),
+    ("seashell", 255, 245, 238),
+    ("sienna", 160, 82, 45),
+    ("skyblue", 135, 206, 235),
+    ("slateblue", 106, 90, 205),
+    ("slategray", 112, 128, 144),
+    ("slategrey", 112, 128, 144),
+    ("snow", 255, 250, 250),
+    ("springgreen", 0, 255, 127),
+    ("steelblue", 70, 130, 180),
+    ("tan", 210, 180, 140),
+    ("thistle", 216, 191, 216),
+    ("tomato", 255, 99, 71),
+    ("turquoise", 64, 224, 208),
+    ("violet", 238, 130, 238),
+    ("wheat", 245, 222, 179),
+    ("whitesmoke", 245, 245, 245),
+    ("yellowgreen", 154, 205, 50),
+    ("rebeccapurple", 102, 51, 153),
+];
@@ -27,72 +27,73 @@ fn render_layout_box(commands: &mut DisplayList, layout_box: &LayoutBox) {
 }
 
 fn render_background(commands: &mut DisplayList, layout_box: &LayoutBox) {
-    get_color(layout_box, "background-color").map(|color| {
+    if let Some(color) = get_color(layout_box, "background-color") {
         commands.push(DisplayCommand::SolidRectangle(
             color,
             layout_box.dimensions.border_box(),
-        ))
-    });
+        ));
+    }
 }
 
 fn get_color(layout_box: &LayoutBox, name: &str) -> Option<Color> {
-    match layout_box.styled_node.value(name) {
-        Some(value) => match **value {
-            Value::Color(ref color) => return Some(color.clone()),
-            _ => return None,
-        },
-        None => return None,
+    match *layout_box.styled_node.value(name)? {
+        Value::Color(ref color) if color.alpha > 0.0 => Some(color.to_srgb()),
+        Value::CurrentColor if name != "color" => get_color(layout_box, "color"),
+        _ => None,
     }
 }
 
 fn render_borders(commands: &mut DisplayList, layout_box: &LayoutBox) {
-    let color = match get_color(layout_box, "border-color") {
-        Some(color) => color,
-        _ => return,
-    };
-
     let dimension = &layout_box.dimensions;
     let border_box = dimension.border_box();
 
-    commands.push(DisplayCommand::SolidRectangle(
-        color.clone(),
-        Rectangle {
-            x: border_box.x,
-            y: border_box.y,
-            width: dimension.border.left,
-            height: border_box.height,
-        },
-    ));
-
-    commands.push(DisplayCommand::SolidRectangle(
-        color.clone(),
-        Rectangle {
-            x: border_box.x + border_box.width - dimension.border.right,
-            y: border_box.y,
-            width: dimension.border.left,
-            height: border_box.height,
-        },
-    ));
-
-    commands.push(DisplayCommand::SolidRectangle(
-        color.clone(),
-        Rectangle {
-            x: border_box.x,
-            y: border_box.y,
-            width: border_box.width,
-            height: dimension.border.top,
-        },
-    ));
-
-    commands.push(DisplayCommand::SolidRectangle(
-        color,
-        Rectangle {
-            x: border_box.x,
-            y: border_box.y + border_box.height - dimension.border.bottom,
-            width: border_box.width,
-            height: dimension.border.bottom,
-        },
-    ));
+    if let Some(color) = get_color(layout_box, "border-left-color") {
+        commands.push(DisplayCommand::SolidRectangle(
+            color,
+            Rectangle {
+                x: border_box.x,
+                y: border_box.y,
+                width: dimension.border.left,
+                height: border_box.height,
+            },
+        ));
+    }
+
+    if let Some(color) = get_color(layout_box, "border-right-color") {
+        commands.push(DisplayCommand::SolidRectangle(
+            color,
+            Rectangle {
+                x: border_box.x + border_box.width - dimension.border.right,
+                y: border_box.y,
+                width: dimension.border.left,
+                height: border_box.height,
+            },
+        ));
+    }
+
+    if let Some(color) = get_color(layout_box, "border-top-color") {
+        commands.push(DisplayCommand::SolidRectangle(
+            color,
+            Rectangle {
+                x: border_box.x,
+                y: border_box.y,
+                width: border_box.width,
+                height: dimension.border.top,
+            },
+        ));
+    }
+
+    if let Some(color) = get_color(layout_box, "border-bottom-color") {
+        commands.push(DisplayCommand::SolidRectangle(
+            color,
+            Rectangle {
+                x: border_box.x,
+                y: border_box.y + border_box.height - dimension.border.bottom,
+                width: border_box.width,
+                height: dimension.border.bottom,
+            },
+        ));
+    }
 }
 
 impl fmt::Debug for DisplayCommand {
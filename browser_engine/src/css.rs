@@ -1,15 +1,32 @@
 use std::default::Default;
-use std::fmt;
+use std::{fmt, ops};
+
+use crate::atom::Atom;
+use crate::css_tokenizer::{serialize_identifier, serialize_string, Token};
+use crate::generated::{Content, Quotes};
+use crate::media::{MediaEnvironment, MediaList};
+use crate::serializer::{self, Mode};
+use crate::source::SourceLocation;
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum ColorSpace {
+    Srgb,
+    SrgbLinear,
+    DisplayP3,
+    Lab,
+    Lch,
+    Oklab,
+    Oklch,
+}
 
 #[derive(PartialEq, Clone)]
 pub struct Color {
-    pub(crate) red: f32,
-    pub(crate) green: f32,
-    pub(crate) blue: f32,
+    pub(crate) space: ColorSpace,
+    pub(crate) channels: [f32; 3],
     pub(crate) alpha: f32,
 }
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone, Copy)]
 pub enum Unit {
     Em,
     Ex,
@@ -26,112 +43,384 @@ pub enum Unit {
     In,
     Pt,
     Pc,
-    Pct,
 }
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone)]
 pub enum Value {
+    Keyword(String),
+    Number(f32),
+    Percentage(f32),
+    Length(f32, Unit),
     Color(Color),
+    CurrentColor,
+    String(String),
+    Url(String),
+    List(Vec<Value>, Separator),
+    // A function other than a color or math function, with its
+    // comma-separated arguments.
+    Function(String, Vec<Value>),
+    // The value of a custom property, or a part of a value there is no other
+    // form for, kept as written.
+    Tokens(Vec<Token<'static>>),
+    // A value with var() references, parsed once the element's custom
+    // properties are known. Longhands set through a shorthand keep its name.
+    Unresolved(Option<String>, Vec<Token<'static>>),
+    Calc(Calc),
+    // `content`, `quotes` and the counter properties, parsed when the
+    // declaration is so generating boxes does not parse them again.
+    Content(Content),
+    Quotes(Quotes),
+    CounterChanges(Vec<(String, i32)>),
+}
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum Separator {
+    Space,
+    Comma,
+    Slash,
+}
+
+// The expression inside calc(), min(), max() or clamp(). Percentages are
+// kept until layout knows what they refer to.
+#[derive(PartialEq, Clone)]
+pub enum Calc {
+    Number(f32),
+    Percentage(f32),
     Length(f32, Unit),
-    Other(String),
+    Operation(char, Box<Calc>, Box<Calc>),
+    Min(Vec<Calc>),
+    Max(Vec<Calc>),
+    Clamp(Box<Calc>, Box<Calc>, Box<Calc>),
 }
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone)]
 pub struct Declarations {
     pub(crate) property: String,
     pub(crate) value: Value,
+    pub(crate) important: bool,
+    pub(crate) location: SourceLocation,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum AttributeOperator {
+    Exists,
+    Equals,
+    Includes,
+    DashMatch,
+    Prefix,
+    Suffix,
+    Substring,
+}
+
+// The `i` and `s` flags; without one, values are compared case-sensitively.
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum CaseSensitivity {
+    Default,
+    Insensitive,
+    Sensitive,
 }
 
 #[derive(PartialEq, Eq)]
-pub struct SimpleSelector {
-    pub(crate) tag_name: Option<String>,
-    pub(crate) id: Option<String>,
-    pub(crate) classes: Vec<String>,
+pub struct AttributeSelector {
+    pub(crate) name: Atom,
+    pub(crate) operator: AttributeOperator,
+    pub(crate) value: String,
+    pub(crate) lower_value: String,
+    pub(crate) case_sensitivity: CaseSensitivity,
 }
 
+// The `An+B` argument of the :nth-* pseudo-classes.
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub struct Nth {
+    pub(crate) a: i32,
+    pub(crate) b: i32,
+}
+
+#[derive(PartialEq, Eq)]
+pub enum PseudoClass {
+    Root,
+    Empty,
+    FirstChild,
+    LastChild,
+    OnlyChild,
+    FirstOfType,
+    LastOfType,
+    OnlyOfType,
+    NthChild(Nth, Vec<Selector>),
+    NthLastChild(Nth, Vec<Selector>),
+    NthOfType(Nth),
+    NthLastOfType(Nth),
+    Not(Vec<Selector>),
+    Is(Vec<Selector>),
+    Where(Vec<Selector>),
+    Has(Vec<RelativeSelector>),
+}
+
+// A selector inside :has(), matched relative to the element being tested.
+#[derive(PartialEq, Eq)]
+pub struct RelativeSelector {
+    pub(crate) combinator: char,
+    pub(crate) selector: Selector,
+}
+
+// The namespace part of a type or universal selector. Selectors without an
+// explicit prefix get the stylesheet's default namespace, if it has one.
+#[derive(PartialEq, Eq, Clone)]
+pub enum NamespaceSelector {
+    Any,
+    None,
+    Prefix(Atom, Atom),
+    Default(Atom),
+}
+
+// A type selector's name as written, and lowercased for matching elements in
+// the HTML namespace, so matching either way is a pointer comparison.
 #[derive(PartialEq, Eq)]
+pub struct LocalName {
+    pub(crate) name: Atom,
+    pub(crate) lower_name: Atom,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum PseudoElement {
+    Before,
+    After,
+    Marker,
+}
+
+#[derive(PartialEq, Eq, Default)]
+pub struct SimpleSelector {
+    pub(crate) namespace: Option<NamespaceSelector>,
+    pub(crate) tag_name: Option<LocalName>,
+    pub(crate) id: Option<Atom>,
+    pub(crate) classes: Vec<Atom>,
+    pub(crate) attributes: Vec<AttributeSelector>,
+    pub(crate) pseudo_classes: Vec<PseudoClass>,
+    pub(crate) pseudo_element: Option<PseudoElement>,
+}
+
+// (ids, classes, type selectors), compared in that order.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Default)]
+pub struct Specificity(pub u32, pub u32, pub u32);
+
+#[derive(PartialEq, Eq, Default)]
 pub struct Selector {
     pub(crate) simple: Vec<SimpleSelector>,
     pub(crate) combinators: Vec<char>,
 }
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Default)]
 pub struct Rule {
     pub(crate) selectors: Vec<Selector>,
     pub(crate) declarations: Vec<Declarations>,
+    pub(crate) location: SourceLocation,
+}
+
+#[derive(PartialEq)]
+pub struct MediaRule {
+    pub(crate) media: MediaList,
+    pub(crate) rules: Vec<CssRule>,
+    pub(crate) location: SourceLocation,
+}
+
+#[derive(PartialEq)]
+pub struct ImportRule {
+    pub(crate) url: String,
+    pub(crate) media: MediaList,
+    pub(crate) layer: Option<String>,
+    pub(crate) sheet: Option<StyleSheet>,
+    pub(crate) location: SourceLocation,
+}
+
+#[derive(PartialEq)]
+pub struct NamespaceRule {
+    pub(crate) prefix: Option<String>,
+    pub(crate) url: String,
+    pub(crate) location: SourceLocation,
+}
+
+#[derive(PartialEq)]
+pub enum CssRule {
+    Style(Rule),
+    Media(MediaRule),
+    Import(ImportRule),
+    Namespace(NamespaceRule),
+}
+
+// Ordered from the lowest origin to the highest.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+pub enum Origin {
+    UserAgent,
+    User,
+    Author,
 }
 
 #[derive(PartialEq)]
 pub struct StyleSheet {
-    pub(crate) rules: Vec<Rule>,
+    pub(crate) rules: Vec<CssRule>,
+    pub(crate) origin: Origin,
 }
 
 impl StyleSheet {
-    pub fn new(rules: Vec<Rule>) -> StyleSheet {
-        StyleSheet { rules }
+    pub fn new(rules: Vec<CssRule>) -> StyleSheet {
+        StyleSheet::new_with_origin(rules, Origin::Author)
+    }
+
+    pub fn new_with_origin(rules: Vec<CssRule>, origin: Origin) -> StyleSheet {
+        StyleSheet { rules, origin }
+    }
+
+    pub fn get_origin(&self) -> Origin {
+        self.origin
+    }
+
+    pub fn set_origin(&mut self, origin: Origin) {
+        self.origin = origin;
+    }
+
+    pub fn style_rules(&self, env: &MediaEnvironment) -> Vec<&Rule> {
+        let mut rules = Vec::new();
+        collect_style_rules(&self.rules, env, &mut rules);
+        rules
     }
 }
 
 impl Default for StyleSheet {
     fn default() -> Self {
-        StyleSheet { rules: Vec::new() }
+        StyleSheet {
+            rules: Vec::new(),
+            origin: Origin::Author,
+        }
     }
 }
 
 impl fmt::Debug for StyleSheet {
     fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
-        let mut rules = String::new();
-
-        for rule in &self.rules {
-            if rules.len() > 0 {
-                rules.push_str("\n\n");
-            }
-            rules.push_str(&format!("{0:?}", rule));
-        }
+        write!(
+            format,
+            "{0}",
+            serializer::serialize_stylesheet(self, Mode::Pretty)
+        )
+    }
+}
 
-        write!(format, "{0}", rules)
+impl fmt::Debug for CssRule {
+    fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            format,
+            "{0}",
+            serializer::serialize_rule(self, Mode::Pretty)
+        )
     }
 }
 
-impl Rule {
-    pub fn new(selectors: Vec<Selector>, declarations: Vec<Declarations>) -> Rule {
-        Rule {
-            selectors,
-            declarations,
+impl ImportRule {
+    pub fn new(url: String, media: MediaList, layer: Option<String>) -> ImportRule {
+        ImportRule {
+            url,
+            media,
+            layer,
+            sheet: None,
+            location: SourceLocation::default(),
         }
     }
+
+    pub fn get_url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn get_sheet(&self) -> Option<&StyleSheet> {
+        self.sheet.as_ref()
+    }
+
+    pub fn get_location(&self) -> &SourceLocation {
+        &self.location
+    }
 }
 
-impl Default for Rule {
-    fn default() -> Self {
-        Rule {
-            selectors: Vec::new(),
-            declarations: Vec::new(),
+impl NamespaceRule {
+    pub fn new(prefix: Option<String>, url: String) -> NamespaceRule {
+        NamespaceRule {
+            prefix,
+            url,
+            location: SourceLocation::default(),
         }
     }
+
+    pub fn get_prefix(&self) -> Option<&str> {
+        self.prefix.as_ref().map(|prefix| &prefix[..])
+    }
+
+    pub fn get_url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn get_location(&self) -> &SourceLocation {
+        &self.location
+    }
 }
 
-impl fmt::Debug for Rule {
+impl fmt::Debug for NamespaceRule {
     fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
-        let mut selectors = String::new();
-        let mut declarations = String::new();
-        let tab = "    ";
+        write!(format, "{0}", serializer::serialize_namespace_rule(self))
+    }
+}
 
-        for selector in &self.selectors {
-            if selectors.len() > 0 {
-                selectors.push_str(", ");
-            }
+impl fmt::Debug for ImportRule {
+    fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
+        write!(format, "{0}", serializer::serialize_import_rule(self))
+    }
+}
 
-            selectors.push_str(&format!("{0:?}", selector));
+impl MediaRule {
+    pub fn new(media: MediaList, rules: Vec<CssRule>) -> MediaRule {
+        MediaRule {
+            media,
+            rules,
+            location: SourceLocation::default(),
         }
+    }
+
+    pub fn get_location(&self) -> &SourceLocation {
+        &self.location
+    }
+}
+
+impl fmt::Debug for MediaRule {
+    fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            format,
+            "{0}",
+            serializer::serialize_media_rule(self, Mode::Pretty)
+        )
+    }
+}
 
-        for declaration in &self.declarations {
-            declarations.push_str(tab);
-            declarations.push_str(&format!("{0:?}", declaration));
-            declarations.push('\n');
+impl Rule {
+    pub fn new(selectors: Vec<Selector>, declarations: Vec<Declarations>) -> Rule {
+        Rule {
+            selectors,
+            declarations,
+            location: SourceLocation::default(),
         }
+    }
+
+    pub fn get_selectors(&self) -> &[Selector] {
+        &self.selectors
+    }
 
-        write!(format, "{} {{\n{}}}", selectors, declarations)
+    pub fn get_location(&self) -> &SourceLocation {
+        &self.location
+    }
+}
+
+impl fmt::Debug for Rule {
+    fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            format,
+            "{0}",
+            serializer::serialize_style_rule(self, Mode::Pretty)
+        )
     }
 }
 
@@ -142,53 +431,79 @@ impl Selector {
             combinators,
         }
     }
+
+    // A pseudo-element can only appear in the last compound.
+    pub fn pseudo_element(&self) -> Option<PseudoElement> {
+        self.simple.last().and_then(|simple| simple.pseudo_element)
+    }
+
+    pub fn specificity(&self) -> Specificity {
+        self.simple
+            .iter()
+            .fold(Specificity::default(), |total, simple| {
+                total + simple.specificity()
+            })
+    }
 }
 
-impl Default for Selector {
-    fn default() -> Self {
-        Selector {
-            simple: Vec::new(),
-            combinators: Vec::new(),
-        }
+impl ops::Add for Specificity {
+    type Output = Specificity;
+
+    fn add(self, other: Specificity) -> Specificity {
+        Specificity(self.0 + other.0, self.1 + other.1, self.2 + other.2)
+    }
+}
+
+impl fmt::Debug for Specificity {
+    fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
+        write!(format, "({0}, {1}, {2})", self.0, self.1, self.2)
     }
 }
 
 impl fmt::Debug for Selector {
     fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
-        let mut simple_selector = String::new();
+        write!(
+            format,
+            "{0}",
+            serializer::serialize_selector(self, Mode::Pretty)
+        )
+    }
+}
 
-        for selector in &self.simple {
-            if simple_selector.len() > 0 {
-                simple_selector.push_str(", ");
-            }
-            simple_selector.push_str(&format!("{0:?}", selector));
+impl LocalName {
+    pub fn new(name: &str) -> LocalName {
+        LocalName {
+            name: Atom::from(name),
+            lower_name: Atom::from(name.to_ascii_lowercase()),
         }
-
-        write!(format, "{}", simple_selector)
     }
 }
 
 impl SimpleSelector {
-    pub fn new(
-        tag_name: Option<String>,
-        id: Option<String>,
-        classes: Vec<String>,
-    ) -> SimpleSelector {
+    pub fn new(tag_name: Option<Atom>, id: Option<Atom>, classes: Vec<Atom>) -> SimpleSelector {
         SimpleSelector {
-            tag_name,
+            namespace: None,
+            tag_name: tag_name.map(|name| LocalName::new(&name)),
             id,
             classes,
+            attributes: Vec::new(),
+            pseudo_classes: Vec::new(),
+            pseudo_element: None,
         }
     }
-}
 
-impl Default for SimpleSelector {
-    fn default() -> Self {
-        SimpleSelector {
-            tag_name: None,
-            id: None,
-            classes: Vec::new(),
-        }
+    pub fn specificity(&self) -> Specificity {
+        let specificity = Specificity(
+            self.id.iter().count() as u32,
+            (self.classes.len() + self.attributes.len()) as u32,
+            (self.tag_name.iter().count() + self.pseudo_element.iter().count()) as u32,
+        );
+
+        self.pseudo_classes
+            .iter()
+            .fold(specificity, |total, pseudo_class| {
+                total + pseudo_class.specificity()
+            })
     }
 }
 
@@ -196,31 +511,305 @@ impl fmt::Debug for SimpleSelector {
     fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
         let mut selector = String::new();
 
-        match self.tag_name {
-            Some(ref tag) => selector.push_str(tag),
-            None => {}
+        match self.namespace {
+            Some(NamespaceSelector::Any) => selector.push_str("*|"),
+            Some(NamespaceSelector::None) => selector.push('|'),
+            Some(NamespaceSelector::Prefix(ref prefix, _)) => {
+                selector.push_str(&serialize_identifier(prefix));
+                selector.push('|');
+            }
+            Some(NamespaceSelector::Default(_)) | None => {}
         }
 
-        match self.id {
-            Some(ref id) => {
-                selector.push('#');
-                selector.push_str(id);
+        match self.tag_name {
+            Some(ref tag) => selector.push_str(&tag.name),
+            None if !selector.is_empty()
+                || (self.id.is_none()
+                    && self.classes.is_empty()
+                    && self.attributes.is_empty()
+                    && self.pseudo_classes.is_empty()
+                    && self.pseudo_element.is_none()) =>
+            {
+                selector.push('*')
             }
             None => {}
         }
 
+        if let Some(ref id) = self.id {
+            selector.push('#');
+            selector.push_str(id);
+        }
+
         for class in &self.classes {
             selector.push('.');
             selector.push_str(class);
         }
 
+        for attribute in &self.attributes {
+            selector.push_str(&format!("{0:?}", attribute));
+        }
+
+        for pseudo_class in &self.pseudo_classes {
+            selector.push_str(&format!("{0:?}", pseudo_class));
+        }
+
+        if let Some(pseudo_element) = self.pseudo_element {
+            selector.push_str(&format!("{0:?}", pseudo_element));
+        }
+
         write!(format, "{}", selector)
     }
 }
 
+impl AttributeSelector {
+    pub fn new(
+        name: Atom,
+        operator: AttributeOperator,
+        value: String,
+        case_sensitivity: CaseSensitivity,
+    ) -> AttributeSelector {
+        AttributeSelector {
+            name,
+            operator,
+            lower_value: value.to_ascii_lowercase(),
+            value,
+            case_sensitivity,
+        }
+    }
+
+    // The selector's value is lowercased once, when it is built, so only the
+    // attribute's side is folded while matching, and nothing is allocated.
+    pub fn matches(&self, value: &str) -> bool {
+        let insensitive = self.case_sensitivity == CaseSensitivity::Insensitive;
+        let expected = match insensitive {
+            true => self.lower_value.as_bytes(),
+            false => self.value.as_bytes(),
+        };
+        let equals = |part: &[u8]| {
+            part.len() == expected.len()
+                && part
+                    .iter()
+                    .zip(expected)
+                    .all(|(&a, &b)| a == b || (insensitive && a.to_ascii_lowercase() == b))
+        };
+
+        let value = value.as_bytes();
+        let length = expected.len();
+        match self.operator {
+            AttributeOperator::Exists => true,
+            AttributeOperator::Equals => equals(value),
+            AttributeOperator::Includes => value
+                .split(u8::is_ascii_whitespace)
+                .any(|word| !word.is_empty() && equals(word)),
+            AttributeOperator::DashMatch => {
+                equals(value)
+                    || (value.len() > length && value[length] == b'-' && equals(&value[..length]))
+            }
+            _ if expected.is_empty() || value.len() < length => false,
+            AttributeOperator::Prefix => equals(&value[..length]),
+            AttributeOperator::Suffix => equals(&value[value.len() - length..]),
+            AttributeOperator::Substring => value.windows(length).any(equals),
+        }
+    }
+}
+
+impl fmt::Debug for PseudoElement {
+    fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PseudoElement::Before => write!(format, "::before"),
+            PseudoElement::After => write!(format, "::after"),
+            PseudoElement::Marker => write!(format, "::marker"),
+        }
+    }
+}
+
+impl Nth {
+    pub fn new(a: i32, b: i32) -> Nth {
+        Nth { a, b }
+    }
+
+    // Whether `position` (counting from 1) equals An+B for some n >= 0.
+    pub fn matches(&self, position: usize) -> bool {
+        let offset = position as i32 - self.b;
+
+        match self.a {
+            0 => offset == 0,
+            a => offset % a == 0 && offset / a >= 0,
+        }
+    }
+}
+
+impl fmt::Debug for Nth {
+    fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
+        match (self.a, self.b) {
+            (0, b) => write!(format, "{}", b),
+            (1, 0) => write!(format, "n"),
+            (-1, 0) => write!(format, "-n"),
+            (a, 0) => write!(format, "{}n", a),
+            (1, b) => write!(format, "n{:+}", b),
+            (-1, b) => write!(format, "-n{:+}", b),
+            (a, b) => write!(format, "{}n{:+}", a, b),
+        }
+    }
+}
+
+impl PseudoClass {
+    pub fn specificity(&self) -> Specificity {
+        match *self {
+            PseudoClass::Where(_) => Specificity::default(),
+            PseudoClass::Not(ref selectors) | PseudoClass::Is(ref selectors) => {
+                max_specificity(selectors.iter())
+            }
+            PseudoClass::Has(ref selectors) => {
+                max_specificity(selectors.iter().map(|relative| &relative.selector))
+            }
+            PseudoClass::NthChild(_, ref selectors)
+            | PseudoClass::NthLastChild(_, ref selectors) => {
+                Specificity(0, 1, 0) + max_specificity(selectors.iter())
+            }
+            _ => Specificity(0, 1, 0),
+        }
+    }
+}
+
+impl fmt::Debug for PseudoClass {
+    fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PseudoClass::Root => write!(format, ":root"),
+            PseudoClass::Empty => write!(format, ":empty"),
+            PseudoClass::FirstChild => write!(format, ":first-child"),
+            PseudoClass::LastChild => write!(format, ":last-child"),
+            PseudoClass::OnlyChild => write!(format, ":only-child"),
+            PseudoClass::FirstOfType => write!(format, ":first-of-type"),
+            PseudoClass::LastOfType => write!(format, ":last-of-type"),
+            PseudoClass::OnlyOfType => write!(format, ":only-of-type"),
+            PseudoClass::NthChild(nth, ref selectors) if selectors.is_empty() => {
+                write!(format, ":nth-child({:?})", nth)
+            }
+            PseudoClass::NthChild(nth, ref selectors) => {
+                write!(
+                    format,
+                    ":nth-child({:?} of {:?})",
+                    nth,
+                    SelectorList(selectors)
+                )
+            }
+            PseudoClass::NthLastChild(nth, ref selectors) if selectors.is_empty() => {
+                write!(format, ":nth-last-child({:?})", nth)
+            }
+            PseudoClass::NthLastChild(nth, ref selectors) => {
+                write!(
+                    format,
+                    ":nth-last-child({:?} of {:?})",
+                    nth,
+                    SelectorList(selectors)
+                )
+            }
+            PseudoClass::NthOfType(nth) => write!(format, ":nth-of-type({:?})", nth),
+            PseudoClass::NthLastOfType(nth) => write!(format, ":nth-last-of-type({:?})", nth),
+            PseudoClass::Not(ref selectors) => {
+                write!(format, ":not({:?})", SelectorList(selectors))
+            }
+            PseudoClass::Is(ref selectors) => write!(format, ":is({:?})", SelectorList(selectors)),
+            PseudoClass::Where(ref selectors) => {
+                write!(format, ":where({:?})", SelectorList(selectors))
+            }
+            PseudoClass::Has(ref selectors) => {
+                let mut list = String::new();
+
+                for relative in selectors {
+                    if !list.is_empty() {
+                        list.push_str(", ");
+                    }
+                    if relative.combinator != ' ' {
+                        list.push(relative.combinator);
+                        list.push(' ');
+                    }
+                    list.push_str(&format!("{:?}", relative.selector));
+                }
+
+                write!(format, ":has({})", list)
+            }
+        }
+    }
+}
+
+impl RelativeSelector {
+    pub fn new(combinator: char, selector: Selector) -> RelativeSelector {
+        RelativeSelector {
+            combinator,
+            selector,
+        }
+    }
+}
+
+struct SelectorList<'a>(&'a [Selector]);
+
+impl<'a> fmt::Debug for SelectorList<'a> {
+    fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
+        let mut list = String::new();
+
+        for selector in self.0 {
+            if !list.is_empty() {
+                list.push_str(", ");
+            }
+            list.push_str(&format!("{:?}", selector));
+        }
+
+        write!(format, "{}", list)
+    }
+}
+
+impl fmt::Debug for AttributeSelector {
+    fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
+        let operator = match self.operator {
+            AttributeOperator::Exists => {
+                return write!(format, "[{0}]", serialize_identifier(&self.name))
+            }
+            AttributeOperator::Equals => "=",
+            AttributeOperator::Includes => "~=",
+            AttributeOperator::DashMatch => "|=",
+            AttributeOperator::Prefix => "^=",
+            AttributeOperator::Suffix => "$=",
+            AttributeOperator::Substring => "*=",
+        };
+        let flag = match self.case_sensitivity {
+            CaseSensitivity::Default => "",
+            CaseSensitivity::Insensitive => " i",
+            CaseSensitivity::Sensitive => " s",
+        };
+
+        write!(
+            format,
+            "[{0}{1}{2}{3}]",
+            serialize_identifier(&self.name),
+            operator,
+            serialize_string(&self.value),
+            flag
+        )
+    }
+}
+
 impl Declarations {
     pub fn new(property: String, value: Value) -> Declarations {
-        Declarations { property, value }
+        Declarations {
+            property,
+            value,
+            important: false,
+            location: SourceLocation::default(),
+        }
+    }
+
+    pub fn get_value(&self) -> &Value {
+        &self.value
+    }
+
+    pub fn is_important(&self) -> bool {
+        self.important
+    }
+
+    pub fn get_location(&self) -> &SourceLocation {
+        &self.location
     }
 }
 
@@ -228,33 +817,147 @@ impl Default for Declarations {
     fn default() -> Self {
         Declarations {
             property: String::from(""),
-            value: Value::Other(String::from("")),
+            value: Value::Keyword(String::from("")),
+            important: false,
+            location: SourceLocation::default(),
         }
     }
 }
 
 impl fmt::Debug for Declarations {
     fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
-        write!(format, "{}: {:?}", self.property, self.value)
+        write!(
+            format,
+            "{0}",
+            serializer::serialize_declaration(self, Mode::Pretty)
+        )
     }
 }
 
 impl fmt::Debug for Value {
     fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            format,
+            "{0}",
+            serializer::serialize_value(self, Mode::Pretty)
+        )
+    }
+}
+
+impl Unit {
+    pub fn to_px(self, value: f32, env: &MediaEnvironment) -> f32 {
+        match self {
+            Unit::Em => value * env.font_size,
+            Unit::Rem => value * env.root_font_size,
+            Unit::Ex | Unit::Ch => value * env.font_size / 2.0,
+            Unit::Vw => value * env.width / 100.0,
+            Unit::Vh => value * env.height / 100.0,
+            Unit::Vmin => value * env.width.min(env.height) / 100.0,
+            Unit::Vmax => value * env.width.max(env.height) / 100.0,
+            Unit::Px => value,
+            Unit::Mm => value * 96.0 / 25.4,
+            Unit::Q => value * 96.0 / 101.6,
+            Unit::Cm => value * 96.0 / 2.54,
+            Unit::In => value * 96.0,
+            Unit::Pt => value * 96.0 / 72.0,
+            Unit::Pc => value * 16.0,
+        }
+    }
+}
+
+impl fmt::Debug for Unit {
+    fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
+        let unit = match *self {
+            Unit::Em => "em",
+            Unit::Ex => "ex",
+            Unit::Ch => "ch",
+            Unit::Rem => "rem",
+            Unit::Vh => "vh",
+            Unit::Vw => "vw",
+            Unit::Vmin => "vmin",
+            Unit::Vmax => "vmax",
+            Unit::Px => "px",
+            Unit::Mm => "mm",
+            Unit::Q => "q",
+            Unit::Cm => "cm",
+            Unit::In => "in",
+            Unit::Pt => "pt",
+            Unit::Pc => "pc",
+        };
+        write!(format, "{0}", unit)
+    }
+}
+
+impl Calc {
+    // Percentages resolve against `percentage_basis`; without one the
+    // expression has no value.
+    pub fn resolve(&self, percentage_basis: Option<f32>, env: &MediaEnvironment) -> Option<f32> {
+        let resolve_all = |calcs: &[Calc]| {
+            calcs
+                .iter()
+                .map(|calc| calc.resolve(percentage_basis, env))
+                .collect::<Option<Vec<_>>>()
+        };
+
+        match *self {
+            Calc::Number(number) => Some(number),
+            Calc::Percentage(percentage) => {
+                percentage_basis.map(|basis| percentage * basis / 100.0)
+            }
+            Calc::Length(length, unit) => Some(unit.to_px(length, env)),
+            Calc::Operation(operator, ref left, ref right) => {
+                let left = left.resolve(percentage_basis, env)?;
+                let right = right.resolve(percentage_basis, env)?;
+                match operator {
+                    '+' => Some(left + right),
+                    '-' => Some(left - right),
+                    '*' => Some(left * right),
+                    _ => Some(left / right),
+                }
+            }
+            Calc::Min(ref calcs) => resolve_all(calcs)?.into_iter().fold(None, |min, value| {
+                Some(min.map_or(value, |min: f32| min.min(value)))
+            }),
+            Calc::Max(ref calcs) => resolve_all(calcs)?.into_iter().fold(None, |max, value| {
+                Some(max.map_or(value, |max: f32| max.max(value)))
+            }),
+            Calc::Clamp(ref min, ref value, ref max) => {
+                let min = min.resolve(percentage_basis, env)?;
+                let value = value.resolve(percentage_basis, env)?;
+                let max = max.resolve(percentage_basis, env)?;
+                Some(min.max(value.min(max)))
+            }
+        }
+    }
+
+    pub(crate) fn precedence(&self) -> u8 {
         match *self {
-            Value::Color(ref color) => write!(format, "{0:?}", color),
-            Value::Length(ref length, _) => write!(format, "{0:?}", length),
-            Value::Other(ref string) => write!(format, "{0:?}", string),
+            Calc::Operation('+', ..) | Calc::Operation('-', ..) => 1,
+            Calc::Operation(..) => 2,
+            _ => 3,
         }
     }
 }
 
+impl fmt::Debug for Calc {
+    fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            format,
+            "{0}",
+            serializer::serialize_calc(self, Mode::Pretty)
+        )
+    }
+}
+
 impl Color {
     pub fn new(red: f32, green: f32, blue: f32, alpha: f32) -> Self {
+        Color::new_with_space(ColorSpace::Srgb, [red, green, blue], alpha)
+    }
+
+    pub fn new_with_space(space: ColorSpace, channels: [f32; 3], alpha: f32) -> Self {
         Color {
-            red,
-            green,
-            blue,
+            space,
+            channels,
             alpha,
         }
     }
@@ -268,10 +971,55 @@ impl Default for Color {
 
 impl fmt::Debug for Color {
     fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            format,
-            "r: {0} g: {1} b: {2} a: {3}",
-            self.red, self.green, self.blue, self.alpha
-        )
+        let [c0, c1, c2] = self.channels;
+
+        match self.space {
+            ColorSpace::Srgb => write!(
+                format,
+                "color(srgb {0} {1} {2} / {3})",
+                c0, c1, c2, self.alpha
+            ),
+            ColorSpace::SrgbLinear => write!(
+                format,
+                "color(srgb-linear {0} {1} {2} / {3})",
+                c0, c1, c2, self.alpha
+            ),
+            ColorSpace::DisplayP3 => write!(
+                format,
+                "color(display-p3 {0} {1} {2} / {3})",
+                c0, c1, c2, self.alpha
+            ),
+            ColorSpace::Lab => write!(format, "lab({0} {1} {2} / {3})", c0, c1, c2, self.alpha),
+            ColorSpace::Lch => write!(format, "lch({0} {1} {2} / {3})", c0, c1, c2, self.alpha),
+            ColorSpace::Oklab => write!(format, "oklab({0} {1} {2} / {3})", c0, c1, c2, self.alpha),
+            ColorSpace::Oklch => write!(format, "oklch({0} {1} {2} / {3})", c0, c1, c2, self.alpha),
+        }
     }
 }
+
+fn collect_style_rules<'a>(rules: &'a [CssRule], env: &MediaEnvironment, out: &mut Vec<&'a Rule>) {
+    for rule in rules {
+        match *rule {
+            CssRule::Style(ref rule) => out.push(rule),
+            CssRule::Media(ref media_rule) => {
+                if media_rule.media.matches(env) {
+                    collect_style_rules(&media_rule.rules, env, out);
+                }
+            }
+            CssRule::Namespace(_) => {}
+            CssRule::Import(ref import) => match import.sheet {
+                Some(ref sheet) if import.media.matches(env) => {
+                    collect_style_rules(&sheet.rules, env, out)
+                }
+                _ => {}
+            },
+        }
+    }
+}
+
+fn max_specificity<'a>(selectors: impl Iterator<Item = &'a Selector>) -> Specificity {
+    selectors
+        .map(|selector| selector.specificity())
+        .max()
+        .unwrap_or_default()
+}
@@ -0,0 +1,195 @@
+use crate::css::{CssRule, Origin, StyleSheet};
+use crate::css_parser::CssParser;
+use crate::source::SourceLocation;
+
+use std::error::Error;
+use std::{fmt, fs, io};
+
+pub trait StyleSheetLoader {
+    fn load(&mut self, url: &str) -> io::Result<String>;
+}
+
+pub struct FileLoader;
+
+#[derive(PartialEq, Clone)]
+pub struct ImportError {
+    pub(crate) url: String,
+    pub(crate) message: String,
+    pub(crate) location: SourceLocation,
+}
+
+impl StyleSheetLoader for FileLoader {
+    fn load(&mut self, url: &str) -> io::Result<String> {
+        fs::read_to_string(url.trim_start_matches("file://"))
+    }
+}
+
+// Imported sheets are parsed and attached to their @import rule, which is
+// where the cascade picks them up, so rule order is preserved. A sheet that
+// imports one of its own ancestors is reported and left unloaded. Imported
+// sheets belong to the same origin as the sheet that imports them.
+pub fn load_imports(
+    stylesheet: &mut StyleSheet,
+    url: &str,
+    loader: &mut dyn StyleSheetLoader,
+) -> Vec<ImportError> {
+    let mut errors = Vec::new();
+    let mut stack = vec![url.to_string()];
+
+    let origin = stylesheet.origin;
+    load_rules(
+        &mut stylesheet.rules,
+        origin,
+        url,
+        loader,
+        &mut stack,
+        &mut errors,
+    );
+
+    errors
+}
+
+fn load_rules(
+    rules: &mut [CssRule],
+    origin: Origin,
+    base_url: &str,
+    loader: &mut dyn StyleSheetLoader,
+    stack: &mut Vec<String>,
+    errors: &mut Vec<ImportError>,
+) {
+    for rule in rules {
+        let import = match *rule {
+            CssRule::Import(ref mut import) => import,
+            _ => continue,
+        };
+
+        let url = resolve_url(base_url, &import.url);
+        if stack.contains(&url) {
+            errors.push(ImportError::new(url, "import cycle", import.location));
+            continue;
+        }
+
+        let text = match loader.load(&url) {
+            Ok(text) => text,
+            Err(error) => {
+                let message = error.to_string();
+                errors.push(ImportError::new(url, &message, import.location));
+                continue;
+            }
+        };
+
+        let mut sheet = CssParser::new_with_file(&text, &url).parse_stylesheet();
+        sheet.set_origin(origin);
+
+        stack.push(url.clone());
+        load_rules(&mut sheet.rules, origin, &url, loader, stack, errors);
+        stack.pop();
+
+        import.sheet = Some(sheet);
+    }
+}
+
+impl ImportError {
+    fn new(url: String, message: &str, location: SourceLocation) -> ImportError {
+        ImportError {
+            url,
+            message: message.to_string(),
+            location,
+        }
+    }
+
+    pub fn get_url(&self) -> &str {
+        &self.url
+    }
+}
+
+impl fmt::Debug for ImportError {
+    fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
+        write!(format, "{0}", self)
+    }
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
+        let start = self.location.start;
+
+        match self.location.file {
+            Some(ref file) => write!(
+                format,
+                "{0}:{1}:{2}: cannot import {3}: {4}",
+                file, start.line, start.column, self.url, self.message
+            ),
+            None => write!(
+                format,
+                "{0}:{1}: cannot import {2}: {3}",
+                start.line, start.column, self.url, self.message
+            ),
+        }
+    }
+}
+
+impl Error for ImportError {}
+
+// Resolves `url` against the URL or file path of the sheet that imports it.
+// A protocol-relative URL takes the scheme of the base, and is left as it is
+// when the base has none.
+pub fn resolve_url(base: &str, url: &str) -> String {
+    if has_scheme(url) {
+        return url.to_string();
+    }
+    if url.starts_with("//") {
+        return match base.find("://") {
+            Some(i) => format!("{0}:{1}", &base[..i], url),
+            None => url.to_string(),
+        };
+    }
+
+    let (origin, base_path) = match base.find("://") {
+        Some(i) => {
+            let path_start = base[i + 3..].find('/').map_or(base.len(), |j| i + 3 + j);
+            base.split_at(path_start)
+        }
+        None => ("", base),
+    };
+
+    let absolute = url.starts_with('/') || base_path.starts_with('/') || !origin.is_empty();
+
+    // The last segment of the base is its file name, unless it ends in `/`.
+    let mut segments: Vec<&str> = Vec::new();
+    if !url.starts_with('/') {
+        let directory = match base_path.rfind('/') {
+            Some(i) => &base_path[..i],
+            None => "",
+        };
+        segments.extend(directory.split('/').filter(|s| !s.is_empty() && *s != "."));
+    }
+
+    for segment in url.split('/') {
+        match segment {
+            "" | "." => {}
+            // Relative paths may climb above their starting directory.
+            ".." if !absolute && segments.last().is_none_or(|s| *s == "..") => segments.push(".."),
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+
+    format!(
+        "{0}{1}{2}",
+        origin,
+        if absolute { "/" } else { "" },
+        segments.join("/")
+    )
+}
+
+// A single letter before the colon is a Windows drive, not a scheme.
+fn has_scheme(url: &str) -> bool {
+    match url.find(':') {
+        Some(i) if i > 1 => url[..i]
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.'),
+        _ => false,
+    }
+}
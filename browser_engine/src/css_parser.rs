@@ -1,783 +1,1289 @@
-use crate::css::{Color, Declarations, Rule, Selector, SimpleSelector, StyleSheet, Unit, Value};
+use crate::atom::Atom;
+use crate::calc;
+use crate::color::parse_color;
+use crate::css::{
+    AttributeOperator, AttributeSelector, CaseSensitivity, CssRule, Declarations, ImportRule,
+    LocalName, MediaRule, NamespaceRule, NamespaceSelector, Nth, PseudoClass, PseudoElement,
+    RelativeSelector, Rule, Selector, Separator, SimpleSelector, StyleSheet, Unit, Value,
+};
+use crate::css_tokenizer::{tokenize, Token};
+use crate::custom_properties;
+use crate::generated;
+use crate::media::parse_media_list;
+use crate::properties::{self, Grammar};
+use crate::shorthand;
+use crate::source::{LineIndex, SourceLocation, Span};
 
-use std::iter::Peekable;
-use std::str::Chars;
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+pub(crate) type TokenList<'a> = [(Token<'a>, Span)];
+
+// Namespace prefixes declared by @namespace rules. The default namespace is
+// stored under the empty prefix, which no identifier can spell.
+type Namespaces = HashMap<String, Atom>;
 
 pub struct CssParser<'a> {
-    chars: Peekable<Chars<'a>>,
+    tokens: Vec<(Token<'a>, Span)>,
+    index: usize,
+    lines: LineIndex<'a>,
+    namespaces: Namespaces,
 }
 
 impl<'a> CssParser<'a> {
-    pub fn new(full_css: &str) -> CssParser {
+    pub fn new(full_css: &'a str) -> CssParser<'a> {
+        CssParser {
+            tokens: tokenize(full_css),
+            index: 0,
+            lines: LineIndex::new(full_css, None),
+            namespaces: Namespaces::new(),
+        }
+    }
+
+    pub fn new_with_file(full_css: &'a str, file: &str) -> CssParser<'a> {
         CssParser {
-            chars: full_css.chars().peekable(),
+            tokens: tokenize(full_css),
+            index: 0,
+            lines: LineIndex::new(full_css, Some(Atom::from(file))),
+            namespaces: Namespaces::new(),
         }
     }
 
     pub fn parse_stylesheet(&mut self) -> StyleSheet {
-        let mut stylesheet = StyleSheet::default();
+        StyleSheet::new(self.parse_rule_list(true))
+    }
 
-        while self.chars.peek().is_some() {
-            let selectors = self.parse_selectors();
-            let styles = self.parse_declarations();
-            let rule = Rule::new(selectors, styles);
+    // The contents of a style attribute: declarations with no selector or
+    // braces around them. A stray `}` is an error like any other, so the
+    // whole input is read.
+    pub fn parse_declaration_list(&mut self) -> Vec<Declarations> {
+        self.parse_declarations(false)
+    }
 
-            stylesheet.rules.push(rule);
+    // A declaration that must make up the whole input, such as one built
+    // from a property and value given separately.
+    pub fn parse_single_declaration(&mut self) -> Vec<Declarations> {
+        self.skip_declaration(false);
+        if self.peek().is_some() {
+            return Vec::new();
         }
 
-        stylesheet
+        self.parse_declaration(0, self.index)
     }
 
-    fn parse_selectors(&mut self) -> Vec<Selector> {
-        let mut selectors = Vec::new();
+    // Nested lists, such as the block of an @media rule, end at their
+    // closing brace.
+    fn parse_rule_list(&mut self, top_level: bool) -> Vec<CssRule> {
+        let mut rules = Vec::new();
 
-        while self.chars.peek().map_or(false, |c| *c != '{') {
-            let selector = self.parse_selector();
-
-            if selector != Selector::default() {
-                selectors.push(selector);
+        while let Some(token) = self.peek() {
+            match *token {
+                Token::CloseCurly if !top_level => {
+                    self.index += 1;
+                    break;
+                }
+                Token::Whitespace => self.index += 1,
+                Token::Cdo | Token::Cdc if top_level => self.index += 1,
+                Token::AtKeyword(ref name) if name.eq_ignore_ascii_case("media") => {
+                    if let Some(rule) = self.parse_media_rule(!top_level) {
+                        rules.push(CssRule::Media(rule));
+                    }
+                }
+                // @import is only valid before any other rule.
+                Token::AtKeyword(ref name)
+                    if name.eq_ignore_ascii_case("import")
+                        && top_level
+                        && rules.iter().all(|rule| matches!(*rule, CssRule::Import(_))) =>
+                {
+                    if let Some(rule) = self.parse_import_rule() {
+                        rules.push(CssRule::Import(rule));
+                    }
+                }
+                // @namespace may only follow @import and other @namespace rules.
+                Token::AtKeyword(ref name)
+                    if name.eq_ignore_ascii_case("namespace")
+                        && top_level
+                        && rules.iter().all(|rule| {
+                            matches!(*rule, CssRule::Import(_) | CssRule::Namespace(_))
+                        }) =>
+                {
+                    if let Some(rule) = self.parse_namespace_rule() {
+                        rules.push(CssRule::Namespace(rule));
+                    }
+                }
+                Token::AtKeyword(_) => self.skip_at_rule(!top_level),
+                _ => {
+                    if let Some(rule) = self.parse_rule(!top_level) {
+                        rules.push(CssRule::Style(rule));
+                    }
+                }
             }
+        }
+
+        rules
+    }
 
-            self.consume_while(char::is_whitespace);
-            if self.chars.peek().map_or(false, |c| *c == ',') {
-                self.chars.next();
+    fn parse_media_rule(&mut self, nested: bool) -> Option<MediaRule> {
+        let start = self.index;
+        self.index += 1;
+        let prelude_start = self.index;
+
+        while let Some(token) = self.peek() {
+            match *token {
+                Token::OpenCurly => break,
+                Token::CloseCurly if nested => return None,
+                Token::Semicolon => {
+                    self.index += 1;
+                    return None;
+                }
+                _ => self.skip_component_value(),
             }
         }
+        self.peek()?;
 
-        self.chars.next();
-        selectors
+        let prelude: Vec<Token> = self.tokens[prelude_start..self.index]
+            .iter()
+            .map(|(token, _)| token.clone())
+            .collect();
+        self.index += 1;
+
+        let mut rule = MediaRule::new(parse_media_list(&prelude), self.parse_rule_list(false));
+        rule.location = self.location(start, self.index);
+        Some(rule)
     }
 
-    fn parse_selector(&mut self) -> Selector {
-        let mut sselector = SimpleSelector::default();
-        let mut selector = Selector::default();
+    // In a nested rule list an unmatched `}` closes the enclosing block, so
+    // it ends the rule without being consumed. At the top level it is just
+    // part of the prelude.
+    fn parse_rule(&mut self, nested: bool) -> Option<Rule> {
+        let prelude_start = self.index;
 
-        self.consume_while(char::is_whitespace);
+        while let Some(token) = self.peek() {
+            match *token {
+                Token::OpenCurly => break,
+                Token::CloseCurly if nested => return None,
+                _ => self.skip_component_value(),
+            }
+        }
+        self.peek()?;
 
-        sselector.tag_name = match self.chars.peek() {
-            Some(&c) if is_valid_start_ident(c) => Some(self.parse_identifier()),
-            _ => None,
-        };
+        let prelude_end = self.index;
+        self.index += 1;
 
-        let mut multiple_ids = false;
-        while self
-            .chars
-            .peek()
-            .map_or(false, |c| *c != ',' && *c != '{' && !(*c).is_whitespace())
-        {
-            match self.chars.peek() {
-                Some(&c) if c == '#' => {
-                    self.chars.next();
-                    if sselector.id.is_some() || multiple_ids {
-                        sselector.id = None;
-                        multiple_ids = true;
-                        self.parse_id();
-                    } else {
-                        sselector.id = self.parse_id();
-                    }
-                }
-                Some(&c) if c == '.' => {
-                    self.chars.next();
-                    let class_name = self.parse_identifier();
+        let selectors = parse_selectors(&self.tokens[prelude_start..prelude_end], &self.namespaces);
+        let styles = self.parse_declarations(true);
 
-                    if class_name != String::from("") {
-                        sselector.classes.push(class_name);
-                    }
+        // An invalid selector drops the whole rule, but only after its block
+        // has been consumed so parsing resumes at the next rule.
+        let mut rule = Rule::new(selectors?, styles);
+        rule.location = self.location(prelude_start, self.index);
+        Some(rule)
+    }
+
+    // The block of a rule ends at its closing brace.
+    fn parse_declarations(&mut self, nested: bool) -> Vec<Declarations> {
+        let mut declarations = Vec::<Declarations>::new();
+
+        while let Some(token) = self.peek() {
+            match *token {
+                Token::CloseCurly if nested => {
+                    self.index += 1;
+                    break;
                 }
+                Token::Whitespace | Token::Semicolon => self.index += 1,
+                Token::AtKeyword(_) => self.skip_at_rule(nested),
                 _ => {
-                    self.consume_while(|c| c != ',' && c != '{');
+                    let start = self.index;
+                    self.skip_declaration(nested);
+
+                    declarations.extend(self.parse_declaration(start, self.index));
                 }
             }
         }
 
-        if sselector != SimpleSelector::default() {
-            selector.simple.push(sselector);
+        declarations
+    }
+
+    // Shorthands are expanded here, so only longhands reach the cascade.
+    // Custom properties and values with var() references are kept unparsed.
+    fn parse_declaration(&self, start: usize, end: usize) -> Vec<Declarations> {
+        let tokens = trim_whitespace(&self.tokens[start..end]);
+
+        let property = match tokens.first() {
+            Some((Token::Ident(ref name), _)) if custom_properties::is_custom_property(name) => {
+                Cow::Borrowed(&name[..])
+            }
+            Some((Token::Ident(ref name), _)) => lowercase(name),
+            _ => return Vec::new(),
+        };
+
+        let rest = trim_whitespace(&tokens[1..]);
+        match rest.first() {
+            Some((Token::Colon, _)) => {}
+            _ => return Vec::new(),
         }
 
-        selector
+        let (value_tokens, important) = split_important(trim_whitespace(&rest[1..]));
+        let location = self
+            .lines
+            .location(tokens[0].1.start, tokens[tokens.len() - 1].1.end);
+
+        let mut declarations = translate_property(&property, value_tokens, location);
+        for declaration in &mut declarations {
+            declaration.important = important;
+        }
+        declarations
     }
 
-    fn parse_identifier(&mut self) -> String {
-        let mut ident = String::new();
+    fn parse_import_rule(&mut self) -> Option<ImportRule> {
+        let start = self.index;
+        self.index += 1;
+        let prelude_start = self.index;
 
-        match self.chars.peek() {
-            Some(&c) => {
-                if is_valid_start_ident(c) {
-                    ident.push_str(&self.consume_while(is_valid_ident))
+        while let Some(token) = self.peek() {
+            match *token {
+                Token::Semicolon => break,
+                Token::OpenCurly => {
+                    self.skip_component_value();
+                    return None;
                 }
+                _ => self.skip_component_value(),
             }
-            None => {}
         }
 
-        ident.to_lowercase()
+        let prelude: Vec<Token> = self.tokens[prelude_start..self.index]
+            .iter()
+            .map(|(token, _)| token.clone())
+            .collect();
+        if self.peek().is_some() {
+            self.index += 1;
+        }
+
+        let (url, rest) = parse_url(skip_whitespace(&prelude))?;
+
+        let rest = skip_whitespace(rest);
+        let (layer, rest) = match *rest {
+            [Token::Ident(ref name), ..] if name.eq_ignore_ascii_case("layer") => {
+                (Some(String::new()), &rest[1..])
+            }
+            [Token::Function(ref name), ..] if name.eq_ignore_ascii_case("layer") => {
+                let close = rest.iter().position(|token| *token == Token::CloseParen)?;
+                (Some(parse_layer_name(&rest[1..close])?), &rest[close + 1..])
+            }
+            _ => (None, rest),
+        };
+
+        let mut rule = ImportRule::new(url, parse_media_list(rest), layer);
+        rule.location = self.location(start, self.index);
+        Some(rule)
     }
 
-    fn parse_id(&mut self) -> Option<String> {
-        match &self.parse_identifier()[..] {
-            "" => None,
-            s @ _ => Some(s.to_string()),
+    // `@namespace prefix? url;` declares a prefix, or the default namespace
+    // when there is none, for the selectors that follow.
+    fn parse_namespace_rule(&mut self) -> Option<NamespaceRule> {
+        let start = self.index;
+        self.index += 1;
+        let prelude_start = self.index;
+
+        while let Some(token) = self.peek() {
+            match *token {
+                Token::Semicolon => break,
+                Token::OpenCurly => {
+                    self.skip_component_value();
+                    return None;
+                }
+                _ => self.skip_component_value(),
+            }
         }
-    }
 
-    fn parse_declarations(&mut self) -> Vec<Declarations> {
-        let mut declarations = Vec::<Declarations>::new();
+        let prelude: Vec<Token> = self.tokens[prelude_start..self.index]
+            .iter()
+            .map(|(token, _)| token.clone())
+            .collect();
+        if self.peek().is_some() {
+            self.index += 1;
+        }
+
+        let rest = skip_whitespace(&prelude);
+        let (prefix, rest) = match *rest {
+            [Token::Ident(ref prefix), ..] => {
+                (Some(prefix.to_string()), skip_whitespace(&rest[1..]))
+            }
+            _ => (None, rest),
+        };
 
-        while self.chars.peek().map_or(false, |c| *c != '}') {
-            self.consume_while(char::is_whitespace);
+        let (url, rest) = parse_url(rest)?;
+        if !skip_whitespace(rest).is_empty() {
+            return None;
+        }
 
-            let property = self.consume_while(|x| x != ':').to_lowercase();
+        self.namespaces
+            .insert(prefix.clone().unwrap_or_default(), Atom::from(&url[..]));
 
-            self.chars.next();
-            self.consume_while(char::is_whitespace);
+        let mut rule = NamespaceRule::new(prefix, url);
+        rule.location = self.location(start, self.index);
+        Some(rule)
+    }
 
-            let value = self
-                .consume_while(|x| x != ';' && x != '\n' && x != '}')
-                .to_lowercase();
+    // Unsupported at-rules have their prelude and block dropped. Like a
+    // nested rule, a nested at-rule stops at an unmatched `}`.
+    fn skip_at_rule(&mut self, nested: bool) {
+        self.index += 1;
 
-            let value_enum = match property.as_ref() {
-                "background-color" | "border-color" | "color" => {
-                    Value::Color(translate_color(&value))
+        while let Some(token) = self.peek() {
+            match *token {
+                Token::CloseCurly if nested => return,
+                Token::Semicolon => {
+                    self.index += 1;
+                    return;
                 }
-                "margin-right"
-                | "margin-bottom"
-                | "margin-left"
-                | "margin-top"
-                | "padding-right"
-                | "padding-bottom"
-                | "padding-left"
-                | "padding-top"
-                | "border-right-width"
-                | "border-bottom-width"
-                | "border-left-width"
-                | "border-top-width"
-                | "height"
-                | "width" => translate_length(&value),
-                _ => Value::Other(value),
-            };
+                Token::OpenCurly => {
+                    self.skip_component_value();
+                    return;
+                }
+                _ => self.skip_component_value(),
+            }
+        }
+    }
+
+    fn skip_declaration(&mut self, nested: bool) {
+        while let Some(token) = self.peek() {
+            match *token {
+                Token::Semicolon => return,
+                Token::CloseCurly if nested => return,
+                _ => self.skip_component_value(),
+            }
+        }
+    }
 
-            let declaration = Declarations::new(property, value_enum);
+    // A block only ends at its own closing token; blocks still open at the
+    // end of input are closed implicitly.
+    fn skip_component_value(&mut self) {
+        let mut closers = Vec::new();
 
-            if self.chars.peek().map_or(false, |c| *c == ';') {
-                declarations.push(declaration);
-                self.chars.next();
-            } else {
-                self.consume_while(char::is_whitespace);
-                if self.chars.peek().map_or(false, |c| *c == '}') {
-                    declarations.push(declaration);
+        while let Some(token) = self.peek() {
+            match (closing_delimiter(token), opening_delimiter(token)) {
+                (Some(closer), _) if closers.last() == Some(&closer) => {
+                    closers.pop();
                 }
+                (_, Some(closer)) => closers.push(closer),
+                _ => {}
+            }
+
+            self.index += 1;
+            if closers.is_empty() {
+                return;
             }
-            self.consume_while(char::is_whitespace);
         }
+    }
 
-        self.chars.next();
-        declarations
+    fn peek(&self) -> Option<&Token<'a>> {
+        self.tokens.get(self.index).map(|(token, _)| token)
     }
 
-    fn consume_while<F>(&mut self, condition: F) -> String
-    where
-        F: Fn(char) -> bool,
-    {
-        let mut result = String::new();
-        while self.chars.peek().map_or(false, |c| condition(*c)) {
-            result.push(self.chars.next().unwrap());
-        }
+    fn location(&self, start: usize, end: usize) -> SourceLocation {
+        let start = self.tokens.get(start).map_or(0, |(_, span)| span.start);
+        let end = self.tokens[..end]
+            .last()
+            .map_or(start, |(_, span)| span.end);
 
-        result
+        self.lines.location(start, end)
     }
 }
 
-fn translate_length(value: &str) -> Value {
-    let mut num_str = String::new();
-    let mut unit = String::new();
-    let mut parsing_num = true;
+// Each comma separated part becomes a complex selector. Anything other than
+// type selectors, #ids, .classes, [attributes], pseudo-classes and
+// combinators makes the whole list invalid.
+fn parse_selectors(tokens: &TokenList, namespaces: &Namespaces) -> Option<Vec<Selector>> {
+    let mut selectors = Vec::new();
+
+    for part in split_commas(tokens) {
+        selectors.extend(parse_complex_selector(part, namespaces)?);
+    }
+
+    Some(selectors)
+}
+
+// :is() and :where() drop invalid selectors instead of failing as a whole.
+fn parse_forgiving_selectors(tokens: &TokenList, namespaces: &Namespaces) -> Vec<Selector> {
+    split_commas(tokens)
+        .into_iter()
+        .filter_map(|part| parse_complex_selector(part, namespaces))
+        .flatten()
+        .filter(|selector| selector.pseudo_element().is_none())
+        .collect()
+}
+
+// The arguments of :has(), which may start with a combinator.
+fn parse_relative_selectors(
+    tokens: &TokenList,
+    namespaces: &Namespaces,
+) -> Option<Vec<RelativeSelector>> {
+    let mut selectors = Vec::new();
+
+    for part in split_commas(tokens) {
+        let part = trim_whitespace(part);
+        let (combinator, part) = match part.first() {
+            Some((Token::Delim(c @ '>'), _))
+            | Some((Token::Delim(c @ '+'), _))
+            | Some((Token::Delim(c @ '~'), _)) => (*c, &part[1..]),
+            _ => (' ', part),
+        };
+
+        match parse_complex_selector(part, namespaces)? {
+            Some(ref selector) if selector.pseudo_element().is_some() => return None,
+            Some(selector) => selectors.push(RelativeSelector::new(combinator, selector)),
+            None => {}
+        }
+    }
+
+    Some(selectors)
+}
+
+// Compound selectors joined by ' ', '>', '+' or '~'. A selector that can
+// never match, such as `#a#b`, is valid but parsed as `Some(None)`.
+fn parse_complex_selector(tokens: &TokenList, namespaces: &Namespaces) -> Option<Option<Selector>> {
+    let part = trim_whitespace(tokens);
+    let mut compounds = Vec::new();
+    let mut combinators = Vec::new();
+    let mut compound_start = None;
+    let mut combinator = None;
+    let mut depth = 0;
+
+    for (index, (token, _)) in part.iter().enumerate() {
+        // Whitespace inside brackets and parentheses is not a combinator.
+        let nested = depth > 0;
+        if opening_delimiter(token).is_some() {
+            depth += 1;
+        } else if closing_delimiter(token).is_some() && depth > 0 {
+            depth -= 1;
+        }
+
+        let explicit = match *token {
+            Token::Delim(c @ '>') | Token::Delim(c @ '+') | Token::Delim(c @ '~') if !nested => {
+                Some(c)
+            }
+            Token::Whitespace if !nested => None,
+            _ => {
+                if compound_start.is_none() {
+                    if !compounds.is_empty() {
+                        combinators.push(combinator.take().unwrap_or(' '));
+                    }
+                    compound_start = Some(index);
+                }
+                continue;
+            }
+        };
+
+        if let Some(start) = compound_start.take() {
+            compounds.push(parse_simple_selector(&part[start..index], namespaces)?);
+        }
+
+        if let Some(c) = explicit {
+            if compounds.is_empty() || combinator.is_some() {
+                return None;
+            }
+            combinator = Some(c);
+        }
+    }
+
+    match compound_start {
+        Some(start) => compounds.push(parse_simple_selector(&part[start..], namespaces)?),
+        None => return None,
+    }
 
-    for c in value.chars() {
-        if c.is_numeric() && parsing_num {
-            num_str.push(c);
-        } else {
-            unit.push(c);
-            parsing_num = false;
+    let last = compounds.len() - 1;
+    for compound in compounds[..last].iter().flatten() {
+        if compound.pseudo_element.is_some() {
+            return None;
         }
     }
 
-    let number = num_str.parse().unwrap_or(0.0);
+    let compounds: Option<Vec<SimpleSelector>> = compounds.into_iter().collect();
+    Some(compounds.map(|compounds| Selector::new(compounds, combinators)))
+}
+
+// Returns `Some(None)` for a valid compound that can never match.
+fn parse_simple_selector(
+    tokens: &TokenList,
+    namespaces: &Namespaces,
+) -> Option<Option<SimpleSelector>> {
+    let mut sselector = SimpleSelector::default();
+    let mut multiple_ids = false;
 
-    match unit.as_ref() {
-        "em" => Value::Length(number, Unit::Em),
-        "ex" => Value::Length(number, Unit::Ex),
-        "ch" => Value::Length(number, Unit::Ch),
-        "rem" => Value::Length(number, Unit::Rem),
-        "vh" => Value::Length(number, Unit::Vh),
-        "vw" => Value::Length(number, Unit::Vw),
-        "vmin" => Value::Length(number, Unit::Vmin),
-        "vmax" => Value::Length(number, Unit::Vmax),
-        "px" | "" => Value::Length(number, Unit::Px),
-        "mm" => Value::Length(number, Unit::Mm),
-        "q" => Value::Length(number, Unit::Q),
-        "cm" => Value::Length(number, Unit::Cm),
-        "in" => Value::Length(number, Unit::In),
-        "pt" => Value::Length(number, Unit::Pt),
-        "pc" => Value::Length(number, Unit::Pc),
-        "%" => Value::Length(number, Unit::Pct),
-        _ => Value::Length(number, Unit::Px),
+    let (namespace, tag_name, mut index) = parse_type_selector(tokens, namespaces)?;
+    sselector.namespace = namespace;
+    sselector.tag_name = tag_name;
+
+    while index < tokens.len() {
+        match tokens[index].0 {
+            Token::IdHash(ref id) => {
+                let id = Atom::from(&id[..]);
+                match sselector.id {
+                    Some(ref first) if *first != id => multiple_ids = true,
+                    _ => sselector.id = Some(id),
+                }
+            }
+            Token::OpenSquare => {
+                let close = tokens[index..]
+                    .iter()
+                    .position(|(token, _)| *token == Token::CloseSquare)?;
+                sselector
+                    .attributes
+                    .push(parse_attribute_selector(&tokens[index + 1..index + close])?);
+                index += close;
+            }
+            Token::Delim('.') => match tokens.get(index + 1) {
+                Some((Token::Ident(ref class_name), _)) => {
+                    sselector.classes.push(Atom::from(&class_name[..]));
+                    index += 1;
+                }
+                _ => return None,
+            },
+            Token::Colon => match parse_pseudo_element(&tokens[index..]) {
+                Some((pseudo_element, length)) => {
+                    sselector.pseudo_element = Some(pseudo_element);
+                    index += length;
+                    // Nothing may follow a pseudo-element.
+                    if index + 1 < tokens.len() {
+                        return None;
+                    }
+                }
+                None => {
+                    let (pseudo_class, length) =
+                        parse_pseudo_class(&tokens[index + 1..], namespaces)?;
+                    sselector.pseudo_classes.push(pseudo_class);
+                    index += length;
+                }
+            },
+            _ => return None,
+        }
+        index += 1;
     }
+
+    if multiple_ids {
+        return Some(None);
+    }
+    Some(Some(sselector))
 }
 
-fn translate_color(color: &str) -> Color {
-    if color.starts_with("#") {
-        if color.len() == 7 {
-            let red = match u8::from_str_radix(&color[1..3], 16) {
-                Ok(n) => n as f32 / 255.0,
-                Err(_) => 0.0,
+// `E`, `*`, `ns|E`, `*|E` or `|E`, returning the namespace, the tag name (none
+// for `*`) and the number of tokens used. A compound without an explicit
+// prefix is limited to the default namespace when one is declared.
+fn parse_type_selector(
+    tokens: &TokenList,
+    namespaces: &Namespaces,
+) -> Option<(Option<NamespaceSelector>, Option<LocalName>, usize)> {
+    let default = namespaces
+        .get("")
+        .map(|&url| NamespaceSelector::Default(url));
+    let element_name = |token: &Token| match *token {
+        Token::Ident(ref name) => Some(Some(LocalName::new(name))),
+        Token::Delim('*') => Some(None),
+        _ => None,
+    };
+
+    match *tokens {
+        [(ref prefix, _), (Token::Delim('|'), _), (ref name, _), ..]
+            if element_name(prefix).is_some() && element_name(name).is_some() =>
+        {
+            let namespace = match *prefix {
+                Token::Ident(ref prefix) => {
+                    let url = namespaces.get(&prefix[..])?;
+                    NamespaceSelector::Prefix(Atom::from(&prefix[..]), *url)
+                }
+                _ => NamespaceSelector::Any,
             };
-            let green = match u8::from_str_radix(&color[3..5], 16) {
-                Ok(n) => n as f32 / 255.0,
-                Err(_) => 0.0,
+            Some((Some(namespace), element_name(name)?, 3))
+        }
+        [(Token::Delim('|'), _), (ref name, _), ..] if element_name(name).is_some() => {
+            Some((Some(NamespaceSelector::None), element_name(name)?, 2))
+        }
+        [(ref name, _), ..] if element_name(name).is_some() => {
+            Some((default, element_name(name)?, 1))
+        }
+        _ => Some((default, None, 0)),
+    }
+}
+
+// `::before`, `::after` and `::marker`, or the legacy `:before` and `:after`,
+// with the number of tokens after the first colon.
+fn parse_pseudo_element(tokens: &TokenList) -> Option<(PseudoElement, usize)> {
+    let (name, length, legacy) = match *tokens {
+        [(Token::Colon, _), (Token::Colon, _), (Token::Ident(ref name), _), ..] => (name, 2, false),
+        [(Token::Colon, _), (Token::Ident(ref name), _), ..] => (name, 1, true),
+        _ => return None,
+    };
+
+    let pseudo_element = match &lowercase(name)[..] {
+        "before" => PseudoElement::Before,
+        "after" => PseudoElement::After,
+        "marker" if !legacy => PseudoElement::Marker,
+        _ => return None,
+    };
+    Some((pseudo_element, length))
+}
+
+// Parses the pseudo-class after a `:` and returns it with the number of
+// tokens it spans.
+fn parse_pseudo_class(tokens: &TokenList, namespaces: &Namespaces) -> Option<(PseudoClass, usize)> {
+    let name = match tokens.first() {
+        Some((Token::Ident(ref name), _)) => {
+            let pseudo_class = match &lowercase(name)[..] {
+                "root" => PseudoClass::Root,
+                "empty" => PseudoClass::Empty,
+                "first-child" => PseudoClass::FirstChild,
+                "last-child" => PseudoClass::LastChild,
+                "only-child" => PseudoClass::OnlyChild,
+                "first-of-type" => PseudoClass::FirstOfType,
+                "last-of-type" => PseudoClass::LastOfType,
+                "only-of-type" => PseudoClass::OnlyOfType,
+                _ => return None,
             };
-            let blue = match u8::from_str_radix(&color[5..7], 16) {
-                Ok(n) => n as f32 / 255.0,
-                Err(_) => 0.0,
+            return Some((pseudo_class, 1));
+        }
+        Some((Token::Function(ref name), _)) => lowercase(name),
+        _ => return None,
+    };
+
+    let mut depth = 0;
+    let close = tokens.iter().position(|(token, _)| {
+        if opening_delimiter(token).is_some() {
+            depth += 1;
+        } else if closing_delimiter(token).is_some() {
+            depth -= 1;
+        }
+        depth == 0
+    })?;
+    let arguments = &tokens[1..close];
+
+    let no_pseudo_elements = |selectors: Vec<Selector>| match selectors
+        .iter()
+        .any(|selector| selector.pseudo_element().is_some())
+    {
+        true => None,
+        false => Some(selectors),
+    };
+
+    let pseudo_class = match &name[..] {
+        "not" => PseudoClass::Not(no_pseudo_elements(parse_selectors(arguments, namespaces)?)?),
+        "is" => PseudoClass::Is(parse_forgiving_selectors(arguments, namespaces)),
+        "where" => PseudoClass::Where(parse_forgiving_selectors(arguments, namespaces)),
+        "has" => PseudoClass::Has(parse_relative_selectors(arguments, namespaces)?),
+        "nth-child" | "nth-last-child" => {
+            let of = arguments.iter().position(|(token, _)| match *token {
+                Token::Ident(ref ident) => ident.eq_ignore_ascii_case("of"),
+                _ => false,
+            });
+            let (nth, selectors) = match of {
+                Some(of) => (
+                    parse_nth(&arguments[..of])?,
+                    no_pseudo_elements(parse_selectors(&arguments[of + 1..], namespaces)?)?,
+                ),
+                None => (parse_nth(arguments)?, Vec::new()),
             };
-            return Color::new(red, green, blue, 1.0);
-        } else if color.len() == 4 {
-            let red = match u8::from_str_radix(&color[1..2], 16) {
-                Ok(n) => n as f32 / 15.0,
-                Err(_) => 0.0,
+            match &name[..] {
+                "nth-child" => PseudoClass::NthChild(nth, selectors),
+                _ => PseudoClass::NthLastChild(nth, selectors),
+            }
+        }
+        "nth-of-type" => PseudoClass::NthOfType(parse_nth(arguments)?),
+        "nth-last-of-type" => PseudoClass::NthLastOfType(parse_nth(arguments)?),
+        _ => return None,
+    };
+
+    Some((pseudo_class, close + 1))
+}
+
+// `odd`, `even`, `B`, `An`, or `An+B`, where whitespace may surround the sign
+// before B. The tokenizer splits these in several ways (`2n-1` is a single
+// dimension, `-n` an ident), so the tokens are joined back into text first.
+fn parse_nth(tokens: &TokenList) -> Option<Nth> {
+    let mut text = String::new();
+
+    for (token, _) in trim_whitespace(tokens) {
+        match *token {
+            Token::Number(ref n) if n.is_integer => {
+                if n.has_sign && n.value >= 0.0 {
+                    text.push('+');
+                }
+                text.push_str(&(n.value as i32).to_string());
+            }
+            Token::Dimension(ref n, ref unit) if n.is_integer => {
+                if n.has_sign && n.value >= 0.0 {
+                    text.push('+');
+                }
+                text.push_str(&(n.value as i32).to_string());
+                text.push_str(&lowercase(unit));
+            }
+            Token::Ident(ref ident) => text.push_str(&lowercase(ident)),
+            Token::Delim(c @ '+') | Token::Delim(c @ '-') => text.push(c),
+            Token::Whitespace => {}
+            _ => return None,
+        }
+    }
+
+    match &text[..] {
+        "odd" => return Some(Nth::new(2, 1)),
+        "even" => return Some(Nth::new(2, 0)),
+        _ => {}
+    }
+
+    let (a, b) = match text.find('n') {
+        Some(n) => {
+            let a = match &text[..n] {
+                "" | "+" => 1,
+                "-" => -1,
+                a => a.parse().ok()?,
             };
-            let green = match u8::from_str_radix(&color[2..3], 16) {
-                Ok(n) => n as f32 / 15.0,
-                Err(_) => 0.0,
+            let b = match &text[n + 1..] {
+                "" => 0,
+                b if b.starts_with('+') || b.starts_with('-') => b.parse().ok()?,
+                _ => return None,
             };
-            let blue = match u8::from_str_radix(&color[3..4], 16) {
-                Ok(n) => n as f32 / 15.0,
-                Err(_) => 0.0,
+            (a, b)
+        }
+        None => (0, text.parse().ok()?),
+    };
+
+    Some(Nth::new(a, b))
+}
+
+// The inside of `[name]`, `[name op value]` or `[name op value i]`.
+fn parse_attribute_selector(tokens: &TokenList) -> Option<AttributeSelector> {
+    let tokens = trim_whitespace(tokens);
+
+    let name = match tokens.first() {
+        Some((Token::Ident(ref name), _)) => Atom::from(&lowercase(name)[..]),
+        _ => return None,
+    };
+
+    let rest = trim_whitespace(&tokens[1..]);
+    let (operator, rest) = match *rest {
+        [] => {
+            return Some(AttributeSelector::new(
+                name,
+                AttributeOperator::Exists,
+                String::new(),
+                CaseSensitivity::Default,
+            ))
+        }
+        [(Token::Delim('='), _), ref rest @ ..] => (AttributeOperator::Equals, rest),
+        [(Token::Delim(c), _), (Token::Delim('='), _), ref rest @ ..] => {
+            let operator = match c {
+                '~' => AttributeOperator::Includes,
+                '|' => AttributeOperator::DashMatch,
+                '^' => AttributeOperator::Prefix,
+                '$' => AttributeOperator::Suffix,
+                '*' => AttributeOperator::Substring,
+                _ => return None,
             };
-            return Color::new(red, green, blue, 1.0);
-        } else {
-            return Color::default();
-        }
-    } else if color.starts_with("rgb") {
-        return Color::default();
-    } else if color.starts_with("hsl") {
-        return Color::default();
-    } else {
-        return match color {
-            "black" => Color::new(0.0, 0.0, 0.0, 1.0),
-            "silver" => Color::new(
-                0.7529411764705882,
-                0.7529411764705882,
-                0.7529411764705882,
-                1.0,
-            ),
-            "gray" | "grey" => Color::new(
-                0.5019607843137255,
-                0.5019607843137255,
-                0.5019607843137255,
-                1.0,
-            ),
-            "white" => Color::new(1.0, 1.0, 1.0, 1.0),
-            "maroon" => Color::new(0.5019607843137255, 0.0, 0.0, 1.0),
-            "red" => Color::new(1.0, 0.0, 0.0, 1.0),
-            "purple" => Color::new(0.5019607843137255, 0.0, 0.5019607843137255, 1.0),
-            "fuchsia" => Color::new(1.0, 0.0, 1.0, 1.0),
-            "green" => Color::new(0.0, 0.5019607843137255, 0.0, 1.0),
-            "lime" => Color::new(0.0, 1.0, 0.0, 1.0),
-            "olive" => Color::new(0.5019607843137255, 0.5019607843137255, 0.0, 1.0),
-            "yellow" => Color::new(1.0, 1.0, 0.0, 1.0),
-            "navy" => Color::new(0.0, 0.0, 0.5019607843137255, 1.0),
-            "blue" => Color::new(0.0, 0.0, 1.0, 1.0),
-            "teal" => Color::new(0.0, 0.5019607843137255, 0.5019607843137255, 1.0),
-            "aqua" => Color::new(0.0, 1.0, 1.0, 1.0),
-            "orange" => Color::new(1.0, 0.6470588235294118, 0.0, 1.0),
-            "aliceblue" => Color::new(0.9411764705882353, 0.9725490196078431, 1.0, 1.0),
-            "antiquewhite" => Color::new(
-                0.9803921568627451,
-                0.9215686274509803,
-                0.8431372549019608,
-                1.0,
-            ),
-            "aquamarine" => Color::new(0.4980392156862745, 1.0, 0.8313725490196079, 1.0),
-            "azure" => Color::new(0.9411764705882353, 1.0, 1.0, 1.0),
-            "beige" => Color::new(
-                0.9607843137254902,
-                0.9607843137254902,
-                0.8627450980392157,
-                1.0,
-            ),
-            "bisque" => Color::new(1.0, 0.8941176470588236, 0.7686274509803922, 1.0),
-            "blanchedalmond" => Color::new(1.0, 0.9215686274509803, 0.803921568627451, 1.0),
-            "blueviolet" => Color::new(
-                0.5411764705882353,
-                0.16862745098039217,
-                0.8862745098039215,
-                1.0,
-            ),
-            "brown" => Color::new(
-                0.6470588235294118,
-                0.16470588235294117,
-                0.16470588235294117,
-                1.0,
-            ),
-            "burlywood" => Color::new(
-                0.8705882352941177,
-                0.7215686274509804,
-                0.5294117647058824,
-                1.0,
-            ),
-            "cadetblue" => Color::new(
-                0.37254901960784315,
-                0.6196078431372549,
-                0.6274509803921569,
-                1.0,
-            ),
-            "chartreuse" => Color::new(0.4980392156862745, 1.0, 0.0, 1.0),
-            "chocolate" => Color::new(
-                0.8235294117647058,
-                0.4117647058823529,
-                0.11764705882352941,
-                1.0,
-            ),
-            "coral" => Color::new(1.0, 0.4980392156862745, 0.3137254901960784, 1.0),
-            "cornflowerblue" => Color::new(
-                0.39215686274509803,
-                0.5843137254901961,
-                0.9294117647058824,
-                1.0,
-            ),
-            "cornsilk" => Color::new(1.0, 0.9725490196078431, 0.8627450980392157, 1.0),
-            "crimson" => Color::new(
-                0.8627450980392157,
-                0.0784313725490196,
-                0.23529411764705882,
-                1.0,
-            ),
-            "darkblue" => Color::new(0.0, 0.0, 0.5450980392156862, 1.0),
-            "darkcyan" => Color::new(0.0, 0.5450980392156862, 0.5450980392156862, 1.0),
-            "darkgoldenrod" => Color::new(
-                0.7215686274509804,
-                0.5254901960784314,
-                0.043137254901960784,
-                1.0,
-            ),
-            "darkgray" | "darkgrey" => Color::new(
-                0.6627450980392157,
-                0.6627450980392157,
-                0.6627450980392157,
-                1.0,
-            ),
-            "darkgreen" => Color::new(0.0, 0.39215686274509803, 0.0, 1.0),
-            "darkkhaki" => Color::new(
-                0.7411764705882353,
-                0.7176470588235294,
-                0.4196078431372549,
-                1.0,
-            ),
-            "darkmagenta" => Color::new(0.5450980392156862, 0.0, 0.5450980392156862, 1.0),
-            "darkolivegreen" => Color::new(
-                0.3333333333333333,
-                0.4196078431372549,
-                0.1843137254901961,
-                1.0,
-            ),
-            "darkorange" => Color::new(1.0, 0.5490196078431373, 0.0, 1.0),
-            "darkorchid" => Color::new(0.6, 0.19607843137254902, 0.8, 1.0),
-            "darkred" => Color::new(0.5450980392156862, 0.0, 0.0, 1.0),
-            "darksalmon" => Color::new(
-                0.9137254901960784,
-                0.5882352941176471,
-                0.47843137254901963,
-                1.0,
-            ),
-            "darkseagreen" => Color::new(
-                0.5607843137254902,
-                0.7372549019607844,
-                0.5607843137254902,
-                1.0,
-            ),
-            "darkslateblue" => Color::new(
-                0.2823529411764706,
-                0.23921568627450981,
-                0.5450980392156862,
-                1.0,
-            ),
-            "darkslategray" | "darkslategrey" => Color::new(
-                0.1843137254901961,
-                0.30980392156862746,
-                0.30980392156862746,
-                1.0,
-            ),
-            "darkturquoise" => Color::new(0.0, 0.807843137254902, 0.8196078431372549, 1.0),
-            "darkviolet" => Color::new(0.5803921568627451, 0.0, 0.8274509803921568, 1.0),
-            "deeppink" => Color::new(1.0, 0.0784313725490196, 0.5764705882352941, 1.0),
-            "deepskyblue" => Color::new(0.0, 0.7490196078431373, 1.0, 1.0),
-            "dimgray" | "dimgrey" => Color::new(
-                0.4117647058823529,
-                0.4117647058823529,
-                0.4117647058823529,
-                1.0,
-            ),
-            "dodgerblue" => Color::new(0.11764705882352941, 0.5647058823529412, 1.0, 1.0),
-            "firebrick" => Color::new(
-                0.6980392156862745,
-                0.13333333333333333,
-                0.13333333333333333,
-                1.0,
-            ),
-            "floralwhite" => Color::new(1.0, 0.9803921568627451, 0.9411764705882353, 1.0),
-            "forestgreen" => Color::new(
-                0.13333333333333333,
-                0.5450980392156862,
-                0.13333333333333333,
-                1.0,
-            ),
-            "gainsboro" => Color::new(
-                0.8627450980392157,
-                0.8627450980392157,
-                0.8627450980392157,
-                1.0,
-            ),
-            "ghostwhite" => Color::new(0.9725490196078431, 0.9725490196078431, 1.0, 1.0),
-            "gold" => Color::new(1.0, 0.8431372549019608, 0.0, 1.0),
-            "goldenrod" => Color::new(
-                0.8549019607843137,
-                0.6470588235294118,
-                0.12549019607843137,
-                1.0,
-            ),
-            "greenyellow" => Color::new(0.6784313725490196, 1.0, 0.1843137254901961, 1.0),
-            "honeydew" => Color::new(0.9411764705882353, 1.0, 0.9411764705882353, 1.0),
-            "hotpink" => Color::new(1.0, 0.4117647058823529, 0.7058823529411765, 1.0),
-            "indianred" => Color::new(
-                0.803921568627451,
-                0.3607843137254902,
-                0.3607843137254902,
-                1.0,
-            ),
-            "indigo" => Color::new(0.29411764705882354, 0.0, 0.5098039215686274, 1.0),
-            "ivory" => Color::new(1.0, 1.0, 0.9411764705882353, 1.0),
-            "khaki" => Color::new(
-                0.9411764705882353,
-                0.9019607843137255,
-                0.5490196078431373,
-                1.0,
-            ),
-            "lavender" => Color::new(
-                0.9019607843137255,
-                0.9019607843137255,
-                0.9803921568627451,
-                1.0,
-            ),
-            "lavenderblush" => Color::new(1.0, 0.9411764705882353, 0.9607843137254902, 1.0),
-            "lawngreen" => Color::new(0.48627450980392156, 0.9882352941176471, 0.0, 1.0),
-            "lemonchiffon" => Color::new(1.0, 0.9803921568627451, 0.803921568627451, 1.0),
-            "lightblue" => Color::new(
-                0.6784313725490196,
-                0.8470588235294118,
-                0.9019607843137255,
-                1.0,
-            ),
-            "lightcoral" => Color::new(
-                0.9411764705882353,
-                0.5019607843137255,
-                0.5019607843137255,
-                1.0,
-            ),
-            "lightcyan" => Color::new(0.8784313725490196, 1.0, 1.0, 1.0),
-            "lightgoldenrodyellow" => Color::new(
-                0.9803921568627451,
-                0.9803921568627451,
-                0.8235294117647058,
-                1.0,
-            ),
-            "lightgray" | "lightgrey" => Color::new(
-                0.8274509803921568,
-                0.8274509803921568,
-                0.8274509803921568,
-                1.0,
-            ),
-            "lightgreen" => Color::new(
-                0.5647058823529412,
-                0.9333333333333333,
-                0.5647058823529412,
-                1.0,
-            ),
-            "lightpink" => Color::new(1.0, 0.7137254901960784, 0.7568627450980392, 1.0),
-            "lightsalmon" => Color::new(1.0, 0.6274509803921569, 0.47843137254901963, 1.0),
-            "lightseagreen" => Color::new(
-                0.12549019607843137,
-                0.6980392156862745,
-                0.6666666666666666,
-                1.0,
-            ),
-            "lightskyblue" => Color::new(
-                0.5294117647058824,
-                0.807843137254902,
-                0.9803921568627451,
-                1.0,
-            ),
-            "lightslategray" | "lightslategrey" => {
-                Color::new(0.4666666666666667, 0.5333333333333333, 0.6, 1.0)
+            (operator, rest)
+        }
+        _ => return None,
+    };
+
+    let rest = trim_whitespace(rest);
+    let value = match rest.first() {
+        Some((Token::Ident(ref value), _)) | Some((Token::QuotedString(ref value), _)) => {
+            value.to_string()
+        }
+        _ => return None,
+    };
+
+    let case_sensitivity = match *trim_whitespace(&rest[1..]) {
+        [] => CaseSensitivity::Default,
+        [(Token::Ident(ref flag), _)] if flag.eq_ignore_ascii_case("i") => {
+            CaseSensitivity::Insensitive
+        }
+        [(Token::Ident(ref flag), _)] if flag.eq_ignore_ascii_case("s") => {
+            CaseSensitivity::Sensitive
+        }
+        _ => return None,
+    };
+
+    Some(AttributeSelector::new(
+        name,
+        operator,
+        value,
+        case_sensitivity,
+    ))
+}
+
+fn is_balanced(tokens: &TokenList) -> bool {
+    let mut closers = Vec::new();
+
+    for (token, _) in tokens {
+        match *token {
+            Token::BadString | Token::BadUrl => return false,
+            _ => {}
+        }
+
+        if let Some(closer) = closing_delimiter(token) {
+            if closers.pop() != Some(closer) {
+                return false;
             }
-            "lightsteelblue" => Color::new(
-                0.6901960784313725,
-                0.7686274509803922,
-                0.8705882352941177,
-                1.0,
-            ),
-            "lightyellow" => Color::new(1.0, 1.0, 0.8784313725490196, 1.0),
-            "limegreen" => Color::new(
-                0.19607843137254902,
-                0.803921568627451,
-                0.19607843137254902,
-                1.0,
-            ),
-            "linen" => Color::new(
-                0.9803921568627451,
-                0.9411764705882353,
-                0.9019607843137255,
-                1.0,
-            ),
-            "mediumaquamarine" => Color::new(0.4, 0.803921568627451, 0.6666666666666666, 1.0),
-            "mediumblue" => Color::new(0.0, 0.0, 0.803921568627451, 1.0),
-            "mediumorchid" => Color::new(
-                0.7294117647058823,
-                0.3333333333333333,
-                0.8274509803921568,
-                1.0,
-            ),
-            "mediumpurple" => Color::new(
-                0.5764705882352941,
-                0.4392156862745098,
-                0.8588235294117647,
-                1.0,
-            ),
-            "mediumseagreen" => Color::new(
-                0.23529411764705882,
-                0.7019607843137254,
-                0.44313725490196076,
-                1.0,
-            ),
-            "mediumslateblue" => Color::new(
-                0.4823529411764706,
-                0.40784313725490196,
-                0.9333333333333333,
-                1.0,
-            ),
-            "mediumspringgreen" => Color::new(0.0, 0.9803921568627451, 0.6039215686274509, 1.0),
-            "mediumturquoise" => Color::new(0.2823529411764706, 0.8196078431372549, 0.8, 1.0),
-            "mediumvioletred" => Color::new(
-                0.7803921568627451,
-                0.08235294117647059,
-                0.5215686274509804,
-                1.0,
-            ),
-            "midnightblue" => Color::new(
-                0.09803921568627451,
-                0.09803921568627451,
-                0.4392156862745098,
-                1.0,
-            ),
-            "mintcream" => Color::new(0.9607843137254902, 1.0, 0.9803921568627451, 1.0),
-            "mistyrose" => Color::new(1.0, 0.8941176470588236, 0.8823529411764706, 1.0),
-            "moccasin" => Color::new(1.0, 0.8941176470588236, 0.7098039215686275, 1.0),
-            "navajowhite" => Color::new(1.0, 0.8705882352941177, 0.6784313725490196, 1.0),
-            "oldlace" => Color::new(
-                0.9921568627450981,
-                0.9607843137254902,
-                0.9019607843137255,
-                1.0,
-            ),
-            "olivedrab" => Color::new(
-                0.4196078431372549,
-                0.5568627450980392,
-                0.13725490196078433,
-                1.0,
-            ),
-            "orangered" => Color::new(1.0, 0.27058823529411763, 0.0, 1.0),
-            "orchid" => Color::new(
-                0.8549019607843137,
-                0.4392156862745098,
-                0.8392156862745098,
-                1.0,
-            ),
-            "palegoldenrod" => Color::new(
-                0.9333333333333333,
-                0.9098039215686274,
-                0.6666666666666666,
-                1.0,
-            ),
-            "palegreen" => Color::new(0.596078431372549, 0.984313725490196, 0.596078431372549, 1.0),
-            "paleturquoise" => Color::new(
-                0.6862745098039216,
-                0.9333333333333333,
-                0.9333333333333333,
-                1.0,
-            ),
-            "palevioletred" => Color::new(
-                0.8588235294117647,
-                0.4392156862745098,
-                0.5764705882352941,
-                1.0,
-            ),
-            "papayawhip" => Color::new(1.0, 0.9372549019607843, 0.8352941176470589, 1.0),
-            "peachpuff" => Color::new(1.0, 0.8549019607843137, 0.7254901960784313, 1.0),
-            "peru" => Color::new(
-                0.803921568627451,
-                0.5215686274509804,
-                0.24705882352941178,
-                1.0,
-            ),
-            "pink" => Color::new(1.0, 0.7529411764705882, 0.796078431372549, 1.0),
-            "plum" => Color::new(
-                0.8666666666666667,
-                0.6274509803921569,
-                0.8666666666666667,
-                1.0,
-            ),
-            "powderblue" => Color::new(
-                0.6901960784313725,
-                0.8784313725490196,
-                0.9019607843137255,
-                1.0,
-            ),
-            "rosybrown" => Color::new(
-                0.7372549019607844,
-                0.5607843137254902,
-                0.5607843137254902,
-                1.0,
-            ),
-            "royalblue" => Color::new(
-                0.2549019607843137,
-                0.4117647058823529,
-                0.8823529411764706,
-                1.0,
-            ),
-            "saddlebrown" => Color::new(
-                0.5450980392156862,
-                0.27058823529411763,
-                0.07450980392156863,
-                1.0,
-            ),
-            "salmon" => Color::new(
-                0.9803921568627451,
-                0.5019607843137255,
-                0.4470588235294118,
-                1.0,
-            ),
-            "sandybrown" => Color::new(
-                0.9568627450980393,
-                0.6431372549019608,
-                0.3764705882352941,
-                1.0,
-            ),
-            "seagreen" => Color::new(
-                0.1803921568627451,
-                0.5450980392156862,
-                0.3411764705882353,
-                1.0,
-            ),
-            "seashell" => Color::new(1.0, 0.9607843137254902, 0.9333333333333333, 1.0),
-            "sienna" => Color::new(
-                0.6274509803921569,
-                0.3215686274509804,
-                0.17647058823529413,
-                1.0,
-            ),
-            "skyblue" => Color::new(
-                0.5294117647058824,
-                0.807843137254902,
-                0.9215686274509803,
-                1.0,
-            ),
-            "slateblue" => Color::new(
-                0.41568627450980394,
-                0.35294117647058826,
-                0.803921568627451,
-                1.0,
-            ),
-            "slategray" | "slategrey" => Color::new(
-                0.4392156862745098,
-                0.5019607843137255,
-                0.5647058823529412,
-                1.0,
-            ),
-            "snow" => Color::new(1.0, 0.9803921568627451, 0.9803921568627451, 1.0),
-            "springgreen" => Color::new(0.0, 1.0, 0.4980392156862745, 1.0),
-            "steelblue" => Color::new(
-                0.27450980392156865,
-                0.5098039215686274,
-                0.7058823529411765,
-                1.0,
-            ),
-            "tan" => Color::new(
-                0.8235294117647058,
-                0.7058823529411765,
-                0.5490196078431373,
-                1.0,
-            ),
-            "thistle" => Color::new(
-                0.8470588235294118,
-                0.7490196078431373,
-                0.8470588235294118,
-                1.0,
-            ),
-            "tomato" => Color::new(1.0, 0.38823529411764707, 0.2784313725490196, 1.0),
-            "turquoise" => Color::new(
-                0.25098039215686274,
-                0.8784313725490196,
-                0.8156862745098039,
-                1.0,
-            ),
-            "violet" => Color::new(
-                0.9333333333333333,
-                0.5098039215686274,
-                0.9333333333333333,
-                1.0,
-            ),
-            "wheat" => Color::new(
-                0.9607843137254902,
-                0.8705882352941177,
-                0.7019607843137254,
-                1.0,
-            ),
-            "whitesmoke" => Color::new(
-                0.9607843137254902,
-                0.9607843137254902,
-                0.9607843137254902,
-                1.0,
-            ),
-            "yellowgreen" => Color::new(
-                0.6039215686274509,
-                0.803921568627451,
-                0.19607843137254902,
-                1.0,
-            ),
-            "rebeccapurple" => Color::new(0.4, 0.2, 0.6, 1.0),
-            _ => Color::new(0.0, 0.0, 0.0, 1.0),
-        };
+        } else if let Some(closer) = opening_delimiter(token) {
+            closers.push(closer);
+        }
+    }
+
+    true
+}
+
+fn opening_delimiter(token: &Token) -> Option<char> {
+    match *token {
+        Token::OpenCurly => Some('}'),
+        Token::OpenSquare => Some(']'),
+        Token::OpenParen | Token::Function(_) => Some(')'),
+        _ => None,
+    }
+}
+
+fn closing_delimiter(token: &Token) -> Option<char> {
+    match *token {
+        Token::CloseCurly => Some('}'),
+        Token::CloseSquare => Some(']'),
+        Token::CloseParen => Some(')'),
+        _ => None,
+    }
+}
+
+// A URL token, a string, or `url("...")`, followed by the remaining tokens.
+fn parse_url<'t, 'a>(tokens: &'t [Token<'a>]) -> Option<(String, &'t [Token<'a>])> {
+    match *tokens {
+        [Token::Url(ref url), ref rest @ ..] | [Token::QuotedString(ref url), ref rest @ ..] => {
+            Some((url.to_string(), rest))
+        }
+        [Token::Function(ref name), ref arguments @ ..] if name.eq_ignore_ascii_case("url") => {
+            match *skip_whitespace(arguments) {
+                [Token::QuotedString(ref url), ref rest @ ..] => match *skip_whitespace(rest) {
+                    [Token::CloseParen, ref rest @ ..] => Some((url.to_string(), rest)),
+                    _ => None,
+                },
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+// A layer name is a dot-separated sequence of identifiers.
+fn parse_layer_name(tokens: &[Token]) -> Option<String> {
+    let mut name = String::new();
+    let mut expect_ident = true;
+
+    for token in trim_whitespace(tokens) {
+        match *token {
+            Token::Ident(ref ident) if expect_ident => name.push_str(ident),
+            Token::Delim('.') if !expect_ident => name.push('.'),
+            _ => return None,
+        }
+        expect_ident = !expect_ident;
+    }
+
+    if expect_ident {
+        return None;
+    }
+
+    Some(name)
+}
+
+// Removes a trailing `!important`, which may have whitespace around the `!`.
+fn split_important<'t, 'a>(tokens: &'t TokenList<'a>) -> (&'t TokenList<'a>, bool) {
+    if let Some((Token::Ident(ref name), _)) = tokens.last() {
+        if name.eq_ignore_ascii_case("important") {
+            let rest = trim_whitespace(&tokens[..tokens.len() - 1]);
+            if let Some((Token::Delim('!'), _)) = rest.last() {
+                return (trim_whitespace(&rest[..rest.len() - 1]), true);
+            }
+        }
+    }
+    (tokens, false)
+}
+
+// The helpers below work on bare tokens as well as on tokens paired with
+// their source spans.
+pub(crate) trait AsToken<'a> {
+    fn as_token(&self) -> &Token<'a>;
+}
+
+impl<'a> AsToken<'a> for Token<'a> {
+    fn as_token(&self) -> &Token<'a> {
+        self
+    }
+}
+
+impl<'a> AsToken<'a> for (Token<'a>, Span) {
+    fn as_token(&self) -> &Token<'a> {
+        &self.0
+    }
+}
+
+pub(crate) fn skip_whitespace<'a, T: AsToken<'a>>(tokens: &[T]) -> &[T] {
+    let start = tokens
+        .iter()
+        .position(|token| *token.as_token() != Token::Whitespace)
+        .unwrap_or(tokens.len());
+
+    &tokens[start..]
+}
+
+pub(crate) fn trim_whitespace<'a, T: AsToken<'a>>(tokens: &[T]) -> &[T] {
+    let tokens = skip_whitespace(tokens);
+    let end = tokens
+        .iter()
+        .rposition(|token| *token.as_token() != Token::Whitespace)
+        .map_or(0, |i| i + 1);
+
+    &tokens[..end]
+}
+
+// Splits on the separators that are not nested in a block or a function.
+pub(crate) fn split_top_level<'a, T, F>(tokens: &[T], is_separator: F) -> Vec<&[T]>
+where
+    T: AsToken<'a>,
+    F: Fn(&Token) -> bool,
+{
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+
+    for (index, token) in tokens.iter().enumerate() {
+        let token = token.as_token();
+        if opening_delimiter(token).is_some() {
+            depth += 1;
+        } else if closing_delimiter(token).is_some() {
+            depth -= if depth > 0 { 1 } else { 0 };
+        } else if depth == 0 && is_separator(token) {
+            parts.push(&tokens[start..index]);
+            start = index + 1;
+        }
+    }
+    parts.push(&tokens[start..]);
+
+    parts
+}
+
+pub(crate) fn split_commas<'a, T: AsToken<'a>>(tokens: &[T]) -> Vec<&[T]> {
+    split_top_level(tokens, |token| *token == Token::Comma)
+}
+
+// CSS and HTML names are ASCII case-insensitive, and most are already in
+// lower case, so those are borrowed as they are.
+pub(crate) fn lowercase(name: &str) -> Cow<'_, str> {
+    if name.bytes().any(|byte| byte.is_ascii_uppercase()) {
+        Cow::Owned(name.to_ascii_lowercase())
+    } else {
+        Cow::Borrowed(name)
+    }
+}
+
+// A value given apart from its property, as by `style.setProperty()`. The
+// value cannot carry its own `!important` or go on to other declarations.
+pub fn parse_declaration_value(property: &str, value: &str) -> Vec<Declarations> {
+    let tokens = tokenize(value);
+    let tokens = trim_whitespace(&tokens);
+
+    let (_, important) = split_important(tokens);
+    if important || split_top_level(tokens, |token| *token == Token::Semicolon).len() > 1 {
+        return Vec::new();
+    }
+
+    translate_property(property, tokens, SourceLocation::default())
+}
+
+// The declarations a property and its value make up: one for a longhand or
+// custom property, or one for each longhand of a shorthand. None are made
+// when the value is invalid.
+fn translate_property(
+    property: &str,
+    tokens: &TokenList,
+    location: SourceLocation,
+) -> Vec<Declarations> {
+    let is_custom = custom_properties::is_custom_property(property);
+    if (tokens.is_empty() && !is_custom) || !is_balanced(tokens) {
+        return Vec::new();
+    }
+
+    let owned_tokens = || {
+        tokens
+            .iter()
+            .map(|(token, _)| token.clone().into_owned())
+            .collect::<Vec<_>>()
+    };
+    let unparsed = |name: &str, value: Value| {
+        let mut declaration = Declarations::new(name.to_string(), value);
+        declaration.location = location;
+        declaration
+    };
+
+    if is_custom {
+        vec![unparsed(property, Value::Tokens(owned_tokens()))]
+    } else if custom_properties::contains_var(tokens) {
+        match properties::longhands(property) {
+            Some(longhands) => longhands
+                .iter()
+                .map(|name| {
+                    let shorthand = Some(property.to_string());
+                    unparsed(name, Value::Unresolved(shorthand, owned_tokens()))
+                })
+                .collect(),
+            None => vec![unparsed(property, Value::Unresolved(None, owned_tokens()))],
+        }
+    } else {
+        // A shorthand is dropped whole if any of its longhands is invalid.
+        let longhands = shorthand::expand(property, tokens).map(|longhands| {
+            longhands
+                .into_iter()
+                .map(|(name, value)| translate_declaration(name, &value, location))
+                .collect::<Option<Vec<_>>>()
+        });
+        match longhands {
+            Some(Some(longhands)) => longhands,
+            Some(None) => Vec::new(),
+            None if properties::longhands(property).is_some() => Vec::new(),
+            None => translate_declaration(property, tokens, location)
+                .into_iter()
+                .collect(),
+        }
+    }
+}
+
+// Returns None for values the property cannot accept.
+fn translate_declaration(
+    property: &str,
+    tokens: &TokenList,
+    location: SourceLocation,
+) -> Option<Declarations> {
+    let grammar = properties::lookup(property).map(|property| property.grammar);
+
+    let value = match grammar {
+        _ if is_css_wide_keyword(tokens) => {
+            translate_keyword(tokens, &properties::CSS_WIDE_KEYWORDS)?
+        }
+        Some(Grammar::Color) => parse_color(tokens)?,
+        Some(Grammar::SignedLengthOr(keywords)) => translate_length(tokens, true, keywords)?,
+        Some(Grammar::LengthOr(keywords)) => translate_length(tokens, false, keywords)?,
+        Some(Grammar::LineWidth) => translate_line_width(tokens)?,
+        Some(Grammar::Keywords(keywords)) => translate_keyword(tokens, keywords)?,
+        Some(Grammar::Background(_)) | Some(Grammar::Font(_))
+            if !shorthand::is_valid(property, tokens) =>
+        {
+            return None
+        }
+        Some(Grammar::GeneratedContent) => generated::parse(property, tokens)?,
+        Some(Grammar::Background(keywords)) | Some(Grammar::Font(keywords)) => {
+            translate_value(tokens, keywords)
+        }
+        None => translate_value(tokens, &[]),
+    };
+
+    let mut declaration = Declarations::new(property.to_string(), value);
+    declaration.location = location;
+    Some(declaration)
+}
+
+fn is_css_wide_keyword(tokens: &TokenList) -> bool {
+    match *tokens {
+        [(Token::Ident(ref name), _)] => properties::is_css_wide_keyword(name),
+        _ => false,
     }
 }
 
-fn is_valid_ident(c: char) -> bool {
-    is_valid_start_ident(c) || c.is_digit(10) || c == '-'
+// Parses the value of a longhand on its own, such as an initial value or one
+// with var() references substituted, expanding the shorthand it was written
+// in when there is one.
+pub(crate) fn parse_value(
+    property: &str,
+    shorthand: Option<&str>,
+    tokens: &TokenList,
+) -> Option<Value> {
+    let tokens = trim_whitespace(tokens);
+    if tokens.is_empty() || !is_balanced(tokens) {
+        return None;
+    }
+
+    let declaration = match shorthand {
+        Some(shorthand) => {
+            let value = shorthand::expand(shorthand, tokens)?
+                .into_iter()
+                .find(|&(name, _)| name == property)?
+                .1;
+            translate_declaration(property, &value, SourceLocation::default())
+        }
+        None => translate_declaration(property, tokens, SourceLocation::default()),
+    };
+
+    declaration.map(|declaration| declaration.value)
 }
 
-fn is_valid_start_ident(c: char) -> bool {
-    is_letter(c) || is_non_ascii(c) || c == '_'
+// Math functions are parsed so layout can resolve them. Anything else must be
+// one of the keywords or a single length or percentage with a known unit.
+fn translate_length(tokens: &TokenList, allow_negative: bool, keywords: &[&str]) -> Option<Value> {
+    let (number, value) = match *tokens {
+        [(Token::Dimension(ref n, ref unit), _)] => {
+            (n.value, Value::Length(n.value, translate_unit(unit)?))
+        }
+        [(Token::Percentage(ref n), _)] => (n.value, Value::Percentage(n.value)),
+        [(Token::Number(ref n), _)] if n.value == 0.0 => (0.0, Value::Length(0.0, Unit::Px)),
+        [(Token::Function(ref name), _), ..] if calc::is_math_function(name) => {
+            let tokens: Vec<Token> = tokens.iter().map(|(token, _)| token.clone()).collect();
+            return calc::parse_length_percentage(&tokens).map(Value::Calc);
+        }
+        [(Token::Ident(_), _)] => return translate_keyword(tokens, keywords),
+        _ => return None,
+    };
+
+    if number < 0.0 && !allow_negative {
+        return None;
+    }
+
+    Some(value)
 }
 
-fn is_letter(c: char) -> bool {
-    is_upper_letter(c) || is_lower_letter(c)
+// Line widths are lengths only; percentages, even inside math functions, are
+// not allowed.
+fn translate_line_width(tokens: &TokenList) -> Option<Value> {
+    match translate_length(tokens, false, &properties::LINE_WIDTH)? {
+        Value::Percentage(_) => None,
+        Value::Calc(ref calc) if !calc::is_length(calc) => None,
+        value => Some(value),
+    }
 }
 
-fn is_upper_letter(c: char) -> bool {
-    c >= 'A' && c <= 'Z'
+fn translate_keyword(tokens: &TokenList, keywords: &[&str]) -> Option<Value> {
+    match *tokens {
+        [(Token::Ident(ref name), _)] => keywords
+            .iter()
+            .find(|keyword| name.eq_ignore_ascii_case(keyword))
+            .map(|keyword| Value::Keyword(keyword.to_string())),
+        _ => None,
+    }
 }
 
-fn is_lower_letter(c: char) -> bool {
-    c >= 'a' && c <= 'z'
+// Models a value that has no grammar of its own. Commas, then slashes,
+// separate lists of space-separated components.
+fn translate_value(tokens: &TokenList, keywords: &[&str]) -> Value {
+    let ranges = shorthand::components(tokens);
+    let parts: Vec<&TokenList> = ranges.iter().map(|range| &tokens[range.clone()]).collect();
+
+    translate_list(&parts, keywords)
 }
 
-fn is_non_ascii(c: char) -> bool {
-    c >= '\u{0080}'
+fn translate_list(parts: &[&TokenList], keywords: &[&str]) -> Value {
+    let separators = [
+        (Token::Comma, Separator::Comma),
+        (Token::Delim('/'), Separator::Slash),
+    ];
+
+    for (delimiter, separator) in separators.iter() {
+        let is_delimiter = |part: &&TokenList| match **part {
+            [(ref token, _)] => token == delimiter,
+            _ => false,
+        };
+
+        if parts.iter().any(is_delimiter) {
+            let items = parts
+                .split(is_delimiter)
+                .map(|parts| translate_list(parts, keywords))
+                .collect();
+            return Value::List(items, *separator);
+        }
+    }
+
+    match *parts {
+        [part] => translate_component(part, keywords),
+        _ => Value::List(
+            parts
+                .iter()
+                .map(|part| translate_component(part, keywords))
+                .collect(),
+            Separator::Space,
+        ),
+    }
+}
+
+// Anything the value model has no form for is kept as tokens. Identifiers
+// other than `keywords` are kept as written.
+fn translate_component(part: &TokenList, keywords: &[&str]) -> Value {
+    let value = match *part {
+        [(Token::Ident(ref name), _)] => {
+            translate_keyword(part, keywords).or_else(|| Some(Value::Keyword(name.to_string())))
+        }
+        [(Token::Number(ref n), _)] => Some(Value::Number(n.value)),
+        [(Token::Percentage(ref n), _)] => Some(Value::Percentage(n.value)),
+        [(Token::Dimension(ref n, ref unit), _)] => {
+            translate_unit(unit).map(|unit| Value::Length(n.value, unit))
+        }
+        [(Token::QuotedString(ref string), _)] => Some(Value::String(string.to_string())),
+        [(Token::Url(ref url), _)] => Some(Value::Url(url.to_string())),
+        [(Token::Hash(_), _)] | [(Token::IdHash(_), _)] => parse_color(part),
+        [(Token::Function(ref name), _), ..] => translate_function(name, part, keywords),
+        _ => None,
+    };
+
+    value.unwrap_or_else(|| {
+        Value::Tokens(
+            part.iter()
+                .map(|(token, _)| token.clone().into_owned())
+                .collect(),
+        )
+    })
+}
+
+fn translate_function(name: &str, part: &TokenList, keywords: &[&str]) -> Option<Value> {
+    if name.eq_ignore_ascii_case("url") {
+        let tokens: Vec<Token> = part.iter().map(|(token, _)| token.clone()).collect();
+        return match parse_url(&tokens)? {
+            (url, []) => Some(Value::Url(url)),
+            _ => None,
+        };
+    }
+    if calc::is_math_function(name) {
+        let tokens: Vec<Token> = part.iter().map(|(token, _)| token.clone()).collect();
+        if let Some(calc) = calc::parse_length_percentage(&tokens) {
+            return Some(Value::Calc(calc));
+        }
+    }
+    if let Some(color) = parse_color(part) {
+        return Some(color);
+    }
+
+    let arguments = match part.last() {
+        Some((Token::CloseParen, _)) => trim_whitespace(&part[1..part.len() - 1]),
+        _ => return None,
+    };
+    let arguments = match arguments.len() {
+        0 => Vec::new(),
+        _ => split_commas(arguments)
+            .into_iter()
+            .map(|argument| translate_value(trim_whitespace(argument), keywords))
+            .collect(),
+    };
+
+    Some(Value::Function(lowercase(name).into_owned(), arguments))
+}
+
+pub(crate) fn translate_unit(unit: &str) -> Option<Unit> {
+    let unit = match &unit.to_ascii_lowercase()[..] {
+        "em" => Unit::Em,
+        "ex" => Unit::Ex,
+        "ch" => Unit::Ch,
+        "rem" => Unit::Rem,
+        "vh" => Unit::Vh,
+        "vw" => Unit::Vw,
+        "vmin" => Unit::Vmin,
+        "vmax" => Unit::Vmax,
+        "px" => Unit::Px,
+        "mm" => Unit::Mm,
+        "q" => Unit::Q,
+        "cm" => Unit::Cm,
+        "in" => Unit::In,
+        "pt" => Unit::Pt,
+        "pc" => Unit::Pc,
+        _ => return None,
+    };
+
+    Some(unit)
 }
@@ -0,0 +1,651 @@
+use crate::source::Span;
+
+use std::borrow::Cow;
+use std::fmt;
+
+#[derive(PartialEq, Clone, Copy)]
+pub struct Numeric {
+    pub value: f32,
+    pub is_integer: bool,
+    pub has_sign: bool,
+}
+
+#[derive(PartialEq, Clone)]
+pub enum Token<'a> {
+    Ident(Cow<'a, str>),
+    Function(Cow<'a, str>),
+    AtKeyword(Cow<'a, str>),
+    Hash(Cow<'a, str>),
+    IdHash(Cow<'a, str>),
+    QuotedString(Cow<'a, str>),
+    BadString,
+    Url(Cow<'a, str>),
+    BadUrl,
+    Delim(char),
+    Number(Numeric),
+    Percentage(Numeric),
+    Dimension(Numeric, Cow<'a, str>),
+    Whitespace,
+    Cdo,
+    Cdc,
+    Colon,
+    Semicolon,
+    Comma,
+    OpenSquare,
+    CloseSquare,
+    OpenParen,
+    CloseParen,
+    OpenCurly,
+    CloseCurly,
+}
+
+pub struct Tokenizer<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+impl<'a> Tokenizer<'a> {
+    pub fn new(input: &'a str) -> Tokenizer<'a> {
+        Tokenizer { input, position: 0 }
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn next_token(&mut self) -> Option<Token<'a>> {
+        self.next_token_with_span().map(|(token, _)| token)
+    }
+
+    pub fn next_token_with_span(&mut self) -> Option<(Token<'a>, Span)> {
+        self.consume_comments();
+
+        let start = self.position;
+        let token = self.consume_token()?;
+
+        Some((token, Span::new(start, self.position)))
+    }
+
+    fn consume_token(&mut self) -> Option<Token<'a>> {
+        let c = self.peek()?;
+        let token = match c {
+            c if is_whitespace(c) => {
+                self.consume_while(is_whitespace);
+                Token::Whitespace
+            }
+            '"' | '\'' => {
+                self.next_char();
+                self.consume_string(c)
+            }
+            '#' => {
+                self.next_char();
+                if self.peek().is_some_and(is_name) || self.starts_with_escape(0) {
+                    let is_id = self.starts_identifier(0);
+                    let name = self.consume_name();
+                    if is_id {
+                        Token::IdHash(name)
+                    } else {
+                        Token::Hash(name)
+                    }
+                } else {
+                    Token::Delim('#')
+                }
+            }
+            '(' => self.single(Token::OpenParen),
+            ')' => self.single(Token::CloseParen),
+            '[' => self.single(Token::OpenSquare),
+            ']' => self.single(Token::CloseSquare),
+            '{' => self.single(Token::OpenCurly),
+            '}' => self.single(Token::CloseCurly),
+            ',' => self.single(Token::Comma),
+            ':' => self.single(Token::Colon),
+            ';' => self.single(Token::Semicolon),
+            '+' | '.' if self.starts_number(0) => self.consume_numeric(),
+            '-' if self.starts_number(0) => self.consume_numeric(),
+            '-' if self.rest().starts_with("-->") => {
+                self.position += 3;
+                Token::Cdc
+            }
+            '-' if self.starts_identifier(0) => self.consume_ident_like(),
+            '<' if self.rest().starts_with("<!--") => {
+                self.position += 4;
+                Token::Cdo
+            }
+            '@' if self.starts_identifier(1) => {
+                self.next_char();
+                Token::AtKeyword(self.consume_name())
+            }
+            '\\' if self.starts_with_escape(0) => self.consume_ident_like(),
+            c if c.is_ascii_digit() => self.consume_numeric(),
+            c if is_name_start(c) => self.consume_ident_like(),
+            c => self.single(Token::Delim(c)),
+        };
+
+        Some(token)
+    }
+
+    fn single(&mut self, token: Token<'a>) -> Token<'a> {
+        self.next_char();
+        token
+    }
+
+    fn consume_comments(&mut self) {
+        while self.rest().starts_with("/*") {
+            match self.rest()[2..].find("*/") {
+                Some(end) => self.position += end + 4,
+                None => self.position = self.input.len(),
+            }
+        }
+    }
+
+    fn consume_numeric(&mut self) -> Token<'a> {
+        let number = self.consume_number();
+
+        if self.starts_identifier(0) {
+            Token::Dimension(number, self.consume_name())
+        } else if self.peek() == Some('%') {
+            self.next_char();
+            Token::Percentage(number)
+        } else {
+            Token::Number(number)
+        }
+    }
+
+    fn consume_number(&mut self) -> Numeric {
+        let start = self.position;
+        let mut is_integer = true;
+        let has_sign = match self.peek() {
+            Some('+') | Some('-') => {
+                self.next_char();
+                true
+            }
+            _ => false,
+        };
+
+        self.consume_while(|c| c.is_ascii_digit());
+
+        if self.peek() == Some('.') && self.peek_nth(1).is_some_and(|c| c.is_ascii_digit()) {
+            self.next_char();
+            self.consume_while(|c| c.is_ascii_digit());
+            is_integer = false;
+        }
+
+        if let Some('e') | Some('E') = self.peek() {
+            let exponent_digit = match self.peek_nth(1) {
+                Some('+') | Some('-') => 2,
+                _ => 1,
+            };
+            if self
+                .peek_nth(exponent_digit)
+                .is_some_and(|c| c.is_ascii_digit())
+            {
+                self.position += exponent_digit;
+                self.consume_while(|c| c.is_ascii_digit());
+                is_integer = false;
+            }
+        }
+
+        Numeric {
+            value: self.input[start..self.position].parse().unwrap_or(0.0),
+            is_integer,
+            has_sign,
+        }
+    }
+
+    fn consume_ident_like(&mut self) -> Token<'a> {
+        let name = self.consume_name();
+
+        if self.peek() != Some('(') {
+            return Token::Ident(name);
+        }
+        self.next_char();
+
+        if !name.eq_ignore_ascii_case("url") {
+            return Token::Function(name);
+        }
+
+        let after_whitespace = self.rest().trim_start_matches(is_whitespace);
+        if after_whitespace.starts_with('"') || after_whitespace.starts_with('\'') {
+            Token::Function(name)
+        } else {
+            self.consume_url()
+        }
+    }
+
+    fn consume_name(&mut self) -> Cow<'a, str> {
+        let start = self.position;
+        self.consume_while(|c| is_name(c) && c != '\0');
+
+        if !self.starts_with_escape(0) && self.peek() != Some('\0') {
+            return Cow::Borrowed(&self.input[start..self.position]);
+        }
+
+        let mut name = self.input[start..self.position].to_string();
+        loop {
+            if self.starts_with_escape(0) {
+                self.next_char();
+                name.push(self.consume_escape());
+            } else if self.peek() == Some('\0') {
+                self.next_char();
+                name.push('\u{FFFD}');
+            } else if self.peek().is_some_and(is_name) {
+                name.push(self.next_char().unwrap());
+            } else {
+                return Cow::Owned(name);
+            }
+        }
+    }
+
+    fn consume_string(&mut self, quote: char) -> Token<'a> {
+        let start = self.position;
+        self.consume_while(|c| c != quote && c != '\\' && c != '\0' && !is_newline(c));
+
+        match self.peek() {
+            Some(c) if c == quote => {
+                self.next_char();
+                return Token::QuotedString(Cow::Borrowed(&self.input[start..self.position - 1]));
+            }
+            None => return Token::QuotedString(Cow::Borrowed(&self.input[start..])),
+            _ => {}
+        }
+
+        let mut string = self.input[start..self.position].to_string();
+        loop {
+            match self.peek() {
+                Some(c) if c == quote => {
+                    self.next_char();
+                    return Token::QuotedString(Cow::Owned(string));
+                }
+                None => return Token::QuotedString(Cow::Owned(string)),
+                Some(c) if is_newline(c) => return Token::BadString,
+                Some('\\') => match self.peek_nth(1) {
+                    None => {
+                        self.next_char();
+                    }
+                    Some(c) if is_newline(c) => {
+                        self.next_char();
+                        self.consume_newline();
+                    }
+                    Some(_) => {
+                        self.next_char();
+                        string.push(self.consume_escape());
+                    }
+                },
+                Some('\0') => {
+                    self.next_char();
+                    string.push('\u{FFFD}');
+                }
+                Some(c) => {
+                    self.next_char();
+                    string.push(c);
+                }
+            }
+        }
+    }
+
+    fn consume_url(&mut self) -> Token<'a> {
+        self.consume_while(is_whitespace);
+
+        let start = self.position;
+        let mut unescaped: Option<String> = None;
+
+        loop {
+            let end = self.position;
+
+            match self.peek() {
+                Some(')') | None => {
+                    self.next_char();
+                    return url_token(&self.input[start..end], unescaped);
+                }
+                Some(c) if is_whitespace(c) => {
+                    self.consume_while(is_whitespace);
+                    match self.peek() {
+                        Some(')') | None => {
+                            self.next_char();
+                            return url_token(&self.input[start..end], unescaped);
+                        }
+                        _ => {
+                            self.consume_bad_url();
+                            return Token::BadUrl;
+                        }
+                    }
+                }
+                Some('"') | Some('\'') | Some('(') => {
+                    self.consume_bad_url();
+                    return Token::BadUrl;
+                }
+                Some(c) if is_non_printable(c) => {
+                    self.consume_bad_url();
+                    return Token::BadUrl;
+                }
+                Some('\\') => {
+                    if self.starts_with_escape(0) {
+                        self.next_char();
+                        let c = self.consume_escape();
+                        unescaped
+                            .get_or_insert_with(|| self.input[start..end].to_string())
+                            .push(c);
+                    } else {
+                        self.consume_bad_url();
+                        return Token::BadUrl;
+                    }
+                }
+                Some(c) => {
+                    self.next_char();
+                    if let Some(ref mut url) = unescaped {
+                        url.push(if c == '\0' { '\u{FFFD}' } else { c });
+                    } else if c == '\0' {
+                        let mut url = self.input[start..end].to_string();
+                        url.push('\u{FFFD}');
+                        unescaped = Some(url);
+                    }
+                }
+            }
+        }
+    }
+
+    fn consume_bad_url(&mut self) {
+        loop {
+            match self.next_char() {
+                Some(')') | None => return,
+                Some('\\') if self.peek().is_some_and(|c| !is_newline(c)) => {
+                    self.consume_escape();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // Called after the backslash has been consumed.
+    fn consume_escape(&mut self) -> char {
+        let hex = self.consume_while_max(|c| c.is_ascii_hexdigit(), 6);
+
+        if hex.is_empty() {
+            return self.next_char().unwrap_or('\u{FFFD}');
+        }
+
+        if self.peek().is_some_and(is_whitespace) {
+            self.consume_newline();
+        }
+
+        match u32::from_str_radix(hex, 16)
+            .ok()
+            .and_then(std::char::from_u32)
+        {
+            Some('\0') | None => '\u{FFFD}',
+            Some(c) => c,
+        }
+    }
+
+    fn consume_newline(&mut self) {
+        if self.rest().starts_with("\r\n") {
+            self.position += 2;
+        } else {
+            self.next_char();
+        }
+    }
+
+    // A backslash at the end of the input still counts, and escapes to U+FFFD.
+    fn starts_with_escape(&self, offset: usize) -> bool {
+        self.peek_nth(offset) == Some('\\')
+            && self.peek_nth(offset + 1).is_none_or(|c| !is_newline(c))
+    }
+
+    fn starts_identifier(&self, offset: usize) -> bool {
+        match self.peek_nth(offset) {
+            Some('-') => {
+                self.peek_nth(offset + 1)
+                    .is_some_and(|c| is_name_start(c) || c == '-')
+                    || self.starts_with_escape(offset + 1)
+            }
+            Some('\\') => self.starts_with_escape(offset),
+            Some(c) => is_name_start(c),
+            None => false,
+        }
+    }
+
+    fn starts_number(&self, offset: usize) -> bool {
+        let digit_at = |n| self.peek_nth(n).is_some_and(|c: char| c.is_ascii_digit());
+
+        match self.peek_nth(offset) {
+            Some('+') | Some('-') => {
+                digit_at(offset + 1)
+                    || (self.peek_nth(offset + 1) == Some('.') && digit_at(offset + 2))
+            }
+            Some('.') => digit_at(offset + 1),
+            Some(c) => c.is_ascii_digit(),
+            None => false,
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.position..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn peek_nth(&self, n: usize) -> Option<char> {
+        self.rest().chars().nth(n)
+    }
+
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += c.len_utf8();
+        Some(c)
+    }
+
+    fn consume_while<F>(&mut self, condition: F) -> &'a str
+    where
+        F: Fn(char) -> bool,
+    {
+        self.consume_while_max(condition, usize::MAX)
+    }
+
+    fn consume_while_max<F>(&mut self, condition: F, max: usize) -> &'a str
+    where
+        F: Fn(char) -> bool,
+    {
+        let start = self.position;
+        let rest = self.rest();
+
+        self.position += rest
+            .char_indices()
+            .take(max)
+            .find(|&(_, c)| !condition(c))
+            .map_or_else(
+                || rest.chars().take(max).map(char::len_utf8).sum(),
+                |(i, _)| i,
+            );
+
+        &self.input[start..self.position]
+    }
+}
+
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        self.next_token()
+    }
+}
+
+impl<'a> Token<'a> {
+    pub fn into_owned(self) -> Token<'static> {
+        let owned = |s: Cow<'a, str>| Cow::Owned(s.into_owned());
+
+        match self {
+            Token::Ident(s) => Token::Ident(owned(s)),
+            Token::Function(s) => Token::Function(owned(s)),
+            Token::AtKeyword(s) => Token::AtKeyword(owned(s)),
+            Token::Hash(s) => Token::Hash(owned(s)),
+            Token::IdHash(s) => Token::IdHash(owned(s)),
+            Token::QuotedString(s) => Token::QuotedString(owned(s)),
+            Token::Url(s) => Token::Url(owned(s)),
+            Token::Dimension(n, s) => Token::Dimension(n, owned(s)),
+            Token::BadString => Token::BadString,
+            Token::BadUrl => Token::BadUrl,
+            Token::Delim(c) => Token::Delim(c),
+            Token::Number(n) => Token::Number(n),
+            Token::Percentage(n) => Token::Percentage(n),
+            Token::Whitespace => Token::Whitespace,
+            Token::Cdo => Token::Cdo,
+            Token::Cdc => Token::Cdc,
+            Token::Colon => Token::Colon,
+            Token::Semicolon => Token::Semicolon,
+            Token::Comma => Token::Comma,
+            Token::OpenSquare => Token::OpenSquare,
+            Token::CloseSquare => Token::CloseSquare,
+            Token::OpenParen => Token::OpenParen,
+            Token::CloseParen => Token::CloseParen,
+            Token::OpenCurly => Token::OpenCurly,
+            Token::CloseCurly => Token::CloseCurly,
+        }
+    }
+}
+
+impl fmt::Debug for Token<'_> {
+    fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
+        write!(format, "{0}", self)
+    }
+}
+
+impl fmt::Display for Token<'_> {
+    fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Token::Ident(ref name) => write!(format, "{0}", serialize_identifier(name)),
+            Token::Function(ref name) => write!(format, "{0}(", serialize_identifier(name)),
+            Token::AtKeyword(ref name) => write!(format, "@{0}", serialize_identifier(name)),
+            Token::Hash(ref name) => write!(format, "#{0}", serialize_name(name)),
+            Token::IdHash(ref name) => write!(format, "#{0}", serialize_identifier(name)),
+            Token::QuotedString(ref string) => write!(format, "{0}", serialize_string(string)),
+            Token::Url(ref url) => write!(format, "url({0})", serialize_string(url)),
+            Token::BadString => writeln!(format, "\""),
+            Token::BadUrl => write!(format, "url(())"),
+            Token::Delim(c) => write!(format, "{0}", c),
+            Token::Number(ref number) => write!(format, "{0:?}", number),
+            Token::Percentage(ref number) => write!(format, "{0:?}%", number),
+            Token::Dimension(ref number, ref unit) => {
+                write!(format, "{0:?}{1}", number, serialize_identifier(unit))
+            }
+            Token::Whitespace => write!(format, " "),
+            Token::Cdo => write!(format, "<!--"),
+            Token::Cdc => write!(format, "-->"),
+            Token::Colon => write!(format, ":"),
+            Token::Semicolon => write!(format, ";"),
+            Token::Comma => write!(format, ","),
+            Token::OpenSquare => write!(format, "["),
+            Token::CloseSquare => write!(format, "]"),
+            Token::OpenParen => write!(format, "("),
+            Token::CloseParen => write!(format, ")"),
+            Token::OpenCurly => write!(format, "{{"),
+            Token::CloseCurly => write!(format, "}}"),
+        }
+    }
+}
+
+impl fmt::Debug for Numeric {
+    fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
+        if self.has_sign && self.value >= 0.0 {
+            write!(format, "+")?;
+        }
+        write!(format, "{0}", self.value)
+    }
+}
+
+pub fn tokenize(input: &str) -> Vec<(Token<'_>, Span)> {
+    let mut tokenizer = Tokenizer::new(input);
+    let mut tokens = Vec::new();
+
+    while let Some(token) = tokenizer.next_token_with_span() {
+        tokens.push(token);
+    }
+
+    tokens
+}
+
+pub fn serialize_identifier(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+
+    for (i, c) in name.chars().enumerate() {
+        match c {
+            '\0' => result.push('\u{FFFD}'),
+            '\u{1}'..='\u{1F}' | '\u{7F}' => result.push_str(&format!("\\{0:x} ", c as u32)),
+            c if c.is_ascii_digit() && (i == 0 || (i == 1 && name.starts_with('-'))) => {
+                result.push_str(&format!("\\{0:x} ", c as u32))
+            }
+            '-' if i == 0 && name.len() == 1 => result.push_str("\\-"),
+            c if is_name(c) => result.push(c),
+            c => {
+                result.push('\\');
+                result.push(c);
+            }
+        }
+    }
+
+    result
+}
+
+fn serialize_name(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+
+    for c in name.chars() {
+        if is_name(c) {
+            result.push(c);
+        } else {
+            result.push('\\');
+            result.push(c);
+        }
+    }
+
+    result
+}
+
+pub fn serialize_string(string: &str) -> String {
+    let mut result = String::with_capacity(string.len() + 2);
+    result.push('"');
+
+    for c in string.chars() {
+        match c {
+            '\0' => result.push('\u{FFFD}'),
+            '\u{1}'..='\u{1F}' | '\u{7F}' => result.push_str(&format!("\\{0:x} ", c as u32)),
+            '"' | '\\' => {
+                result.push('\\');
+                result.push(c);
+            }
+            c => result.push(c),
+        }
+    }
+
+    result.push('"');
+    result
+}
+
+fn url_token(raw: &str, unescaped: Option<String>) -> Token<'_> {
+    match unescaped {
+        Some(url) => Token::Url(Cow::Owned(url)),
+        None => Token::Url(Cow::Borrowed(raw)),
+    }
+}
+
+fn is_newline(c: char) -> bool {
+    c == '\n' || c == '\r' || c == '\u{C}'
+}
+
+fn is_whitespace(c: char) -> bool {
+    is_newline(c) || c == '\t' || c == ' '
+}
+
+fn is_name_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c >= '\u{0080}' || c == '_' || c == '\0'
+}
+
+fn is_name(c: char) -> bool {
+    is_name_start(c) || c.is_ascii_digit() || c == '-'
+}
+
+// NUL is left out: like everywhere else in the input, it stands for U+FFFD.
+fn is_non_printable(c: char) -> bool {
+    matches!(c, '\u{1}'..='\u{8}' | '\u{B}' | '\u{E}'..='\u{1F}' | '\u{7F}')
+}
@@ -0,0 +1,179 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::css::{Declarations, Value};
+use crate::css_parser::{self, skip_whitespace, TokenList};
+use crate::css_tokenizer::Token;
+use crate::properties;
+use crate::source::Span;
+
+// Computed custom properties by name. A property that is missing holds the
+// guaranteed-invalid value.
+pub type CustomProperties = HashMap<String, Vec<Token<'static>>>;
+
+struct Resolver<'d> {
+    specified: HashMap<&'d str, &'d [Token<'static>]>,
+    computed: CustomProperties,
+    stack: Vec<&'d str>,
+    cyclic: HashSet<&'d str>,
+}
+
+impl<'d> Resolver<'d> {
+    // Declared properties are resolved on first use, so a reference that
+    // leads back to a property still on the stack closes a cycle.
+    fn resolve(&mut self, name: &str) -> Option<Vec<Token<'static>>> {
+        let (name, tokens) = match self.specified.get_key_value(name) {
+            Some((&name, &tokens)) => (name, tokens),
+            None => return self.computed.get(name).cloned(),
+        };
+
+        if let Some(position) = self.stack.iter().position(|&entry| entry == name) {
+            self.cyclic.extend(self.stack[position..].iter().cloned());
+            return None;
+        }
+
+        self.stack.push(name);
+        let value = substitute(tokens, &mut |reference| self.resolve(reference));
+        self.stack.pop();
+        self.specified.remove(name);
+
+        match value {
+            Some(value) if !self.cyclic.contains(name) => {
+                self.computed.insert(name.to_string(), value.clone());
+                Some(value)
+            }
+            _ => {
+                self.computed.remove(name);
+                None
+            }
+        }
+    }
+}
+
+pub fn is_custom_property(name: &str) -> bool {
+    name.len() > 2 && name.starts_with("--")
+}
+
+pub fn contains_var(tokens: &TokenList) -> bool {
+    tokens.iter().any(|(token, _)| match *token {
+        Token::Function(ref name) => name.eq_ignore_ascii_case("var"),
+        _ => false,
+    })
+}
+
+// Custom properties are inherited unless the element declares them. Declared
+// values have their references substituted; one that is part of a cycle, or
+// refers to an invalid property without a fallback, becomes guaranteed-invalid.
+pub fn compute<'d>(
+    inherited: &CustomProperties,
+    declarations: impl Iterator<Item = &'d Declarations>,
+) -> CustomProperties {
+    let mut resolver = Resolver {
+        specified: HashMap::new(),
+        computed: inherited.clone(),
+        stack: Vec::new(),
+        cyclic: HashSet::new(),
+    };
+
+    for declar in declarations {
+        let tokens = match declar.value {
+            Value::Tokens(ref tokens) if is_custom_property(&declar.property) => tokens,
+            _ => continue,
+        };
+
+        match *skip_whitespace(tokens) {
+            [Token::Ident(ref keyword)] if keyword.eq_ignore_ascii_case("initial") => {
+                resolver.computed.remove(&declar.property);
+            }
+            [Token::Ident(ref keyword)] if properties::is_css_wide_keyword(keyword) => {}
+            _ => {
+                resolver.specified.insert(&declar.property, tokens);
+            }
+        }
+    }
+
+    let names: Vec<&str> = resolver.specified.keys().cloned().collect();
+    for name in names {
+        resolver.resolve(name);
+    }
+
+    resolver.computed
+}
+
+// Substitutes an unresolved value and parses it as `property`. None means the
+// value is invalid at computed-value time.
+pub fn resolve_value(
+    property: &str,
+    shorthand: Option<&str>,
+    tokens: &[Token<'static>],
+    properties: &CustomProperties,
+) -> Option<Value> {
+    let tokens: Vec<(Token, Span)> = substitute(tokens, &mut |name| properties.get(name).cloned())?
+        .into_iter()
+        .map(|token| (token, Span::default()))
+        .collect();
+
+    css_parser::parse_value(property, shorthand, &tokens)
+}
+
+// Replaces every var() with the value `lookup` finds for it, or else with its
+// fallback. Returns None when a reference has neither.
+pub fn substitute(
+    tokens: &[Token<'static>],
+    lookup: &mut dyn FnMut(&str) -> Option<Vec<Token<'static>>>,
+) -> Option<Vec<Token<'static>>> {
+    let mut result = Vec::new();
+    let mut index = 0;
+
+    while index < tokens.len() {
+        match tokens[index] {
+            Token::Function(ref name) if name.eq_ignore_ascii_case("var") => {
+                let close = index + 1 + closing_paren(&tokens[index + 1..])?;
+                let (name, fallback) = parse_var(&tokens[index + 1..close])?;
+
+                match lookup(name) {
+                    Some(value) => result.extend(value),
+                    None => result.extend(substitute(fallback?, lookup)?),
+                }
+                index = close + 1;
+            }
+            ref token => {
+                result.push(token.clone());
+                index += 1;
+            }
+        }
+    }
+
+    Some(result)
+}
+
+// The arguments of var(): a custom property name and an optional fallback,
+// which may be empty.
+fn parse_var<'t>(
+    arguments: &'t [Token<'static>],
+) -> Option<(&'t str, Option<&'t [Token<'static>]>)> {
+    match *skip_whitespace(arguments) {
+        [Token::Ident(ref name), ref rest @ ..] if is_custom_property(name) => {
+            match *skip_whitespace(rest) {
+                [] => Some((name, None)),
+                [Token::Comma, ref fallback @ ..] => Some((name, Some(fallback))),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn closing_paren(tokens: &[Token]) -> Option<usize> {
+    let mut depth = 0;
+
+    for (index, token) in tokens.iter().enumerate() {
+        match *token {
+            Token::Function(_) | Token::OpenParen => depth += 1,
+            Token::CloseParen if depth == 0 => return Some(index),
+            Token::CloseParen => depth -= 1,
+            _ => {}
+        }
+    }
+
+    None
+}
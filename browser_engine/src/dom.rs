@@ -1,25 +1,51 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::fmt;
+use std::sync::OnceLock;
 
-pub type AttributeMap = HashMap<String, String>;
+use crate::atom::Atom;
+use crate::css::Declarations;
+use crate::css_parser::{self, CssParser};
+use crate::custom_properties;
+use crate::properties;
+use crate::serializer::{serialize_declaration_block, Mode};
+use crate::source::SourceLocation;
 
-#[derive(PartialEq, Eq, Clone)]
+pub type AttributeMap = HashMap<Atom, String>;
+
+// The style attribute is parsed the first time the cascade asks for its
+// declarations, and written back whenever they change.
+#[derive(Clone)]
 pub struct ElementData {
-    pub(crate) tag_name: String,
+    pub(crate) tag_name: Atom,
+    pub(crate) namespace: Option<Atom>,
     attributes: AttributeMap,
+    id: Option<Atom>,
+    classes: Vec<Atom>,
+    style: OnceLock<Vec<Declarations>>,
 }
 
-#[derive(PartialEq, Eq, Clone)]
+#[derive(PartialEq, Clone)]
 pub enum NodeType {
     Text(String),
     Element(ElementData),
     Comment(String),
+    ProcessingInstruction(String, String),
 }
 
-#[derive(PartialEq, Eq)]
+#[derive(PartialEq)]
 pub struct Node {
     pub(crate) children: Vec<Node>,
     pub(crate) node_type: NodeType,
+    pub(crate) location: SourceLocation,
+}
+
+// The id, classes and style are all derived from the attributes.
+impl PartialEq for ElementData {
+    fn eq(&self, other: &ElementData) -> bool {
+        self.tag_name == other.tag_name
+            && self.namespace == other.namespace
+            && self.attributes == other.attributes
+    }
 }
 
 impl fmt::Debug for ElementData {
@@ -38,6 +64,9 @@ impl fmt::Debug for NodeType {
         match *self {
             NodeType::Text(ref text) | NodeType::Comment(ref text) => write!(format, "{}", text),
             NodeType::Element(ref element) => write!(format, "{:?}", element),
+            NodeType::ProcessingInstruction(ref target, ref data) => {
+                write!(format, "{} {}", target, data)
+            }
         }
     }
 }
@@ -53,27 +82,155 @@ impl Node {
         Node {
             node_type,
             children,
+            location: SourceLocation::default(),
+        }
+    }
+
+    pub fn get_location(&self) -> &SourceLocation {
+        &self.location
+    }
+
+    pub fn get_children(&self) -> &[Node] {
+        &self.children
+    }
+
+    pub fn get_node_type(&self) -> &NodeType {
+        &self.node_type
+    }
+
+    pub fn get_element(&self) -> Option<&ElementData> {
+        match self.node_type {
+            NodeType::Element(ref element) => Some(element),
+            _ => None,
+        }
+    }
+
+    pub fn get_element_mut(&mut self) -> Option<&mut ElementData> {
+        match self.node_type {
+            NodeType::Element(ref mut element) => Some(element),
+            _ => None,
         }
     }
 }
 
 impl ElementData {
-    pub fn new(tag_name: String, attributes: AttributeMap) -> ElementData {
+    pub fn new(tag_name: Atom, attributes: AttributeMap) -> ElementData {
+        ElementData::new_with_namespace(tag_name, None, attributes)
+    }
+
+    pub fn new_with_namespace(
+        tag_name: Atom,
+        namespace: Option<Atom>,
+        attributes: AttributeMap,
+    ) -> ElementData {
+        let id = attributes.get("id").map(|id| Atom::from(&id[..]));
+        let mut classes = Vec::new();
+
+        if let Some(string) = attributes.get("class") {
+            for class in string.split_whitespace().map(Atom::from) {
+                if !classes.contains(&class) {
+                    classes.push(class);
+                }
+            }
+        }
+
         ElementData {
             tag_name,
+            namespace,
             attributes,
+            id,
+            classes,
+            style: OnceLock::new(),
         }
     }
 
-    pub fn get_id(&self) -> Option<&String> {
-        self.attributes.get("id")
+    pub fn get_tag_name(&self) -> &Atom {
+        &self.tag_name
+    }
+
+    pub fn get_namespace(&self) -> Option<&Atom> {
+        self.namespace.as_ref()
+    }
+
+    pub fn get_id(&self) -> Option<&Atom> {
+        self.id.as_ref()
+    }
+
+    pub fn get_classes(&self) -> &[Atom] {
+        &self.classes
     }
 
-    pub fn get_classes(&self) -> HashSet<&str> {
-        match self.attributes.get("class") {
-            Some(string) => string.split(' ').collect(),
-            None => HashSet::new(),
+    pub fn get_attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).map(|value| &value[..])
+    }
+
+    // The inline declarations, with shorthands expanded to their longhands.
+    pub fn get_style(&self) -> &[Declarations] {
+        self.style
+            .get_or_init(|| match self.attributes.get("style") {
+                Some(style) => CssParser::new(style).parse_declaration_list(),
+                None => Vec::new(),
+            })
+    }
+
+    pub fn get_style_property(&self, property: &str) -> Option<&Declarations> {
+        let property = normalize_property(property);
+        self.get_style()
+            .iter()
+            .rev()
+            .find(|declar| declar.property == property)
+    }
+
+    pub fn set_style(&mut self, style: &str) {
+        self.style = OnceLock::new();
+        self.attributes
+            .insert(Atom::from("style"), style.to_string());
+    }
+
+    // Sets a property, or every longhand of a shorthand. Nothing changes when
+    // the value is invalid for the property, and false is returned. The value
+    // cannot carry its own `!important` or go on to other declarations.
+    pub fn set_style_property(&mut self, property: &str, value: &str, important: bool) -> bool {
+        let property = normalize_property(property);
+        let declarations = css_parser::parse_declaration_value(&property, value);
+        if declarations.is_empty() {
+            return false;
+        }
+
+        let style = self.style_mut();
+        for mut declar in declarations {
+            declar.important = important;
+            style.retain(|existing| existing.property != declar.property);
+            style.push(declar);
         }
+
+        self.update_style_attribute();
+        true
+    }
+
+    // Returns whether the property, or any longhand of it, was set.
+    pub fn remove_style_property(&mut self, property: &str) -> bool {
+        let property = normalize_property(property);
+        let style = self.style_mut();
+        let count = style.len();
+
+        style.retain(|declar| !sets_property(&property, &declar.property));
+        if style.len() == count {
+            return false;
+        }
+
+        self.update_style_attribute();
+        true
+    }
+
+    fn style_mut(&mut self) -> &mut Vec<Declarations> {
+        self.get_style();
+        self.style.get_mut().unwrap()
+    }
+
+    fn update_style_attribute(&mut self) {
+        let style = serialize_declaration_block(self.get_style(), Mode::Minify);
+        self.attributes.insert(Atom::from("style"), style);
     }
 }
 
@@ -84,14 +241,30 @@ pub fn pretty_print(node: &Node, indent_size: usize) {
         NodeType::Element(ref element) => println!("{0}{1:?}", indent, element),
         NodeType::Text(ref text) => println!("{0}{1}", indent, text),
         NodeType::Comment(ref comment) => println!("{0}<!--{1}-->", indent, comment),
+        NodeType::ProcessingInstruction(ref target, ref data) => {
+            println!("{0}<?{1} {2}?>", indent, target, data)
+        }
     }
 
     for child in node.children.iter() {
-        pretty_print(&child, indent_size + 2);
+        pretty_print(child, indent_size + 2);
     }
 
-    match node.node_type {
-        NodeType::Element(ref element) => println!("{0}<{1}/>", indent, element.tag_name),
-        _ => {}
+    if let NodeType::Element(ref element) = node.node_type {
+        println!("{0}<{1}/>", indent, element.tag_name);
+    }
+}
+
+// Property names are case-insensitive, except for custom properties.
+fn normalize_property(property: &str) -> String {
+    if custom_properties::is_custom_property(property) {
+        property.to_string()
+    } else {
+        property.to_ascii_lowercase()
     }
 }
+
+fn sets_property(property: &str, name: &str) -> bool {
+    name == property
+        || properties::longhands(property).is_some_and(|longhands| longhands.contains(&name))
+}
@@ -0,0 +1,452 @@
+use crate::css::Value;
+use crate::css_parser::{trim_whitespace, TokenList};
+use crate::css_tokenizer::Token;
+use crate::dom::ElementData;
+use crate::style::{Display, StyledNode};
+
+#[derive(PartialEq, Clone)]
+pub enum Content {
+    Normal,
+    None,
+    Items(Vec<ContentItem>),
+}
+
+#[derive(PartialEq, Clone)]
+pub enum ContentItem {
+    String(String),
+    Attr(String),
+    Counter(String, String),
+    Counters(String, String, String),
+    OpenQuote,
+    CloseQuote,
+    NoOpenQuote,
+    NoCloseQuote,
+}
+
+// `none` is an empty list of pairs.
+#[derive(PartialEq, Clone)]
+pub enum Quotes {
+    Auto,
+    Pairs(Vec<(String, String)>),
+}
+
+struct Counter {
+    name: String,
+    value: i32,
+    depth: usize,
+}
+
+// Counter and quote state, carried through the document in tree order while
+// the style tree is built. A counter lives until the parent of the element
+// that created it is left, so it is visible to that element's descendants and
+// following siblings.
+pub struct GeneratedContent {
+    counters: Vec<Counter>,
+    quotes: Vec<(usize, Quotes)>,
+    quote_depth: usize,
+    depth: usize,
+}
+
+impl GeneratedContent {
+    pub fn new() -> GeneratedContent {
+        GeneratedContent {
+            counters: Vec::new(),
+            quotes: Vec::new(),
+            quote_depth: 0,
+            depth: 0,
+        }
+    }
+
+    // Enters an element or pseudo-element, applying its `quotes` and then
+    // its counter-reset, counter-increment and counter-set, in that order.
+    // List items also increment the `list-item` counter.
+    pub fn enter(&mut self, node: &StyledNode) {
+        self.depth += 1;
+
+        if let Some(Value::Quotes(ref quotes)) = node.value("quotes") {
+            self.quotes.push((self.depth, quotes.clone()));
+        }
+
+        for (name, value) in counter_changes(node, "counter-reset") {
+            let depth = self.depth;
+            match self
+                .counters
+                .iter_mut()
+                .rev()
+                .find(|counter| counter.name == *name)
+            {
+                Some(counter) if counter.depth == depth => counter.value = *value,
+                _ => self.counters.push(Counter {
+                    name: name.clone(),
+                    value: *value,
+                    depth,
+                }),
+            }
+        }
+
+        let increments = counter_changes(node, "counter-increment");
+        for (name, value) in increments {
+            let counter = self.counter(name);
+            counter.value = counter.value.wrapping_add(*value);
+        }
+        if let Display::ListItem = node.get_display() {
+            if !increments.iter().any(|(name, _)| name == "list-item") {
+                let counter = self.counter("list-item");
+                counter.value = counter.value.wrapping_add(1);
+            }
+        }
+
+        for (name, value) in counter_changes(node, "counter-set") {
+            self.counter(name).value = *value;
+        }
+    }
+
+    pub fn leave(&mut self) {
+        let depth = self.depth;
+
+        self.counters.retain(|counter| counter.depth <= depth);
+        while self.quotes.last().is_some_and(|&(d, _)| d == depth) {
+            self.quotes.pop();
+        }
+        self.depth -= 1;
+    }
+
+    // Builds the text of `content` for a box whose originating element is
+    // `element`. Quotes nest across the whole document.
+    pub fn generate(&mut self, items: &[ContentItem], element: &ElementData) -> String {
+        let mut text = String::new();
+
+        for item in items {
+            match *item {
+                ContentItem::String(ref string) => text.push_str(string),
+                ContentItem::Attr(ref name) => {
+                    text.push_str(element.get_attribute(name).unwrap_or(""))
+                }
+                ContentItem::Counter(ref name, ref style) => {
+                    let value = self.counter(name).value;
+                    text.push_str(&format_counter(value, style));
+                }
+                ContentItem::Counters(ref name, ref separator, ref style) => {
+                    self.counter(name);
+                    let values: Vec<String> = self
+                        .counters
+                        .iter()
+                        .filter(|counter| counter.name == *name)
+                        .map(|counter| format_counter(counter.value, style))
+                        .collect();
+                    text.push_str(&values.join(separator));
+                }
+                ContentItem::OpenQuote => {
+                    if let Some((open, _)) = self.quote_pair() {
+                        text.push_str(&open);
+                    }
+                    self.quote_depth += 1;
+                }
+                ContentItem::CloseQuote if self.quote_depth > 0 => {
+                    self.quote_depth -= 1;
+                    if let Some((_, close)) = self.quote_pair() {
+                        text.push_str(&close);
+                    }
+                }
+                ContentItem::NoOpenQuote => self.quote_depth += 1,
+                ContentItem::NoCloseQuote if self.quote_depth > 0 => self.quote_depth -= 1,
+                ContentItem::CloseQuote | ContentItem::NoCloseQuote => {}
+            }
+        }
+
+        text
+    }
+
+    // The innermost counter called `name`, created on the current element
+    // with a value of zero if there is none.
+    fn counter(&mut self, name: &str) -> &mut Counter {
+        match self
+            .counters
+            .iter()
+            .rposition(|counter| counter.name == name)
+        {
+            Some(index) => &mut self.counters[index],
+            None => {
+                self.counters.push(Counter {
+                    name: name.to_string(),
+                    value: 0,
+                    depth: self.depth,
+                });
+                self.counters.last_mut().unwrap()
+            }
+        }
+    }
+
+    // Deeper quotes than the list provides reuse its last pair.
+    fn quote_pair(&self) -> Option<(String, String)> {
+        let quotes = match self.quotes.last() {
+            Some((_, Quotes::Pairs(ref pairs))) => pairs.clone(),
+            Some((_, Quotes::Auto)) | None => default_quotes(),
+        };
+
+        quotes
+            .get(self.quote_depth.min(quotes.len().saturating_sub(1)))
+            .cloned()
+    }
+}
+
+impl Default for GeneratedContent {
+    fn default() -> Self {
+        GeneratedContent::new()
+    }
+}
+
+pub fn content(node: &StyledNode) -> Content {
+    match node.value("content") {
+        Some(Value::Content(ref content)) => content.clone(),
+        _ => Content::Normal,
+    }
+}
+
+// The changes a counter property makes, which are none unless it was set.
+fn counter_changes<'n>(node: &'n StyledNode, name: &str) -> &'n [(String, i32)] {
+    match node.value(name) {
+        Some(Value::CounterChanges(ref changes)) => changes,
+        _ => &[],
+    }
+}
+
+// Used by the parser to translate the properties handled here. Invalid
+// values are None and dropped.
+pub fn parse(property: &str, tokens: &TokenList) -> Option<Value> {
+    match property {
+        "content" => parse_content(tokens).map(Value::Content),
+        "quotes" => parse_quotes(tokens).map(Value::Quotes),
+        "counter-reset" | "counter-set" => {
+            parse_counter_changes(tokens, 0).map(Value::CounterChanges)
+        }
+        "counter-increment" => parse_counter_changes(tokens, 1).map(Value::CounterChanges),
+        _ => None,
+    }
+}
+
+pub fn parse_content(tokens: &TokenList) -> Option<Content> {
+    match *tokens {
+        [(Token::Ident(ref name), _)] if name.eq_ignore_ascii_case("normal") => {
+            return Some(Content::Normal)
+        }
+        [(Token::Ident(ref name), _)] if name.eq_ignore_ascii_case("none") => {
+            return Some(Content::None)
+        }
+        _ => {}
+    }
+
+    let mut items = Vec::new();
+    let mut index = 0;
+
+    while index < tokens.len() {
+        let item = match tokens[index].0 {
+            Token::Whitespace => {
+                index += 1;
+                continue;
+            }
+            Token::QuotedString(ref string) => ContentItem::String(string.to_string()),
+            Token::Ident(ref name) => match &name.to_ascii_lowercase()[..] {
+                "open-quote" => ContentItem::OpenQuote,
+                "close-quote" => ContentItem::CloseQuote,
+                "no-open-quote" => ContentItem::NoOpenQuote,
+                "no-close-quote" => ContentItem::NoCloseQuote,
+                _ => return None,
+            },
+            Token::Function(ref name) => {
+                let close = index
+                    + tokens[index..]
+                        .iter()
+                        .position(|(token, _)| *token == Token::CloseParen)?;
+                let arguments = split_arguments(&tokens[index + 1..close])?;
+                index = close;
+
+                match (&name.to_ascii_lowercase()[..], &arguments[..]) {
+                    ("attr", [Token::Ident(ref name)]) => ContentItem::Attr(name.to_string()),
+                    ("counter", [Token::Ident(ref name)]) => {
+                        ContentItem::Counter(counter_name(name)?, "decimal".to_string())
+                    }
+                    ("counter", [Token::Ident(ref name), Token::Ident(ref style)]) => {
+                        ContentItem::Counter(counter_name(name)?, style.to_ascii_lowercase())
+                    }
+                    ("counters", [Token::Ident(ref name), Token::QuotedString(ref separator)]) => {
+                        ContentItem::Counters(
+                            counter_name(name)?,
+                            separator.to_string(),
+                            "decimal".to_string(),
+                        )
+                    }
+                    (
+                        "counters",
+                        [Token::Ident(ref name), Token::QuotedString(ref separator), Token::Ident(ref style)],
+                    ) => ContentItem::Counters(
+                        counter_name(name)?,
+                        separator.to_string(),
+                        style.to_ascii_lowercase(),
+                    ),
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        };
+
+        items.push(item);
+        index += 1;
+    }
+
+    match items.is_empty() {
+        true => None,
+        false => Some(Content::Items(items)),
+    }
+}
+
+// `none`, or counter names each optionally followed by an integer, which
+// defaults to `default`.
+pub fn parse_counter_changes(tokens: &TokenList, default: i32) -> Option<Vec<(String, i32)>> {
+    let tokens: Vec<&Token> = tokens
+        .iter()
+        .map(|(token, _)| token)
+        .filter(|token| **token != Token::Whitespace)
+        .collect();
+
+    match tokens[..] {
+        [Token::Ident(ref name)] if name.eq_ignore_ascii_case("none") => return Some(Vec::new()),
+        [] => return None,
+        _ => {}
+    }
+
+    let mut changes = Vec::new();
+    let mut index = 0;
+
+    while index < tokens.len() {
+        let name = match *tokens[index] {
+            Token::Ident(ref name) => counter_name(name)?,
+            _ => return None,
+        };
+        let value = match tokens.get(index + 1) {
+            Some(Token::Number(ref n)) if n.is_integer => {
+                index += 1;
+                n.value as i32
+            }
+            _ => default,
+        };
+
+        changes.push((name, value));
+        index += 1;
+    }
+
+    Some(changes)
+}
+
+// `auto`, `none` or pairs of strings.
+pub fn parse_quotes(tokens: &TokenList) -> Option<Quotes> {
+    let strings: Vec<&Token> = tokens
+        .iter()
+        .map(|(token, _)| token)
+        .filter(|token| **token != Token::Whitespace)
+        .collect();
+
+    match strings[..] {
+        [Token::Ident(ref name)] if name.eq_ignore_ascii_case("auto") => Some(Quotes::Auto),
+        [Token::Ident(ref name)] if name.eq_ignore_ascii_case("none") => {
+            Some(Quotes::Pairs(Vec::new()))
+        }
+        [] => None,
+        _ if !strings.len().is_multiple_of(2) => None,
+        _ => {
+            let mut quotes = Vec::new();
+
+            for pair in strings.chunks(2) {
+                match *pair {
+                    [Token::QuotedString(ref open), Token::QuotedString(ref close)] => {
+                        quotes.push((open.to_string(), close.to_string()))
+                    }
+                    _ => return None,
+                }
+            }
+            Some(Quotes::Pairs(quotes))
+        }
+    }
+}
+
+pub fn format_counter(value: i32, style: &str) -> String {
+    match style {
+        "none" => String::new(),
+        "disc" => "\u{2022}".to_string(),
+        "circle" => "\u{25E6}".to_string(),
+        "square" => "\u{25AA}".to_string(),
+        "decimal-leading-zero" if value >= 0 => format!("{:02}", value),
+        "lower-roman" if value > 0 && value < 4000 => roman(value).to_lowercase(),
+        "upper-roman" if value > 0 && value < 4000 => roman(value),
+        "lower-alpha" | "lower-latin" if value > 0 => alphabetic(value, b'a'),
+        "upper-alpha" | "upper-latin" if value > 0 => alphabetic(value, b'A'),
+        _ => value.to_string(),
+    }
+}
+
+fn roman(mut value: i32) -> String {
+    let numerals = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+    let mut text = String::new();
+
+    for &(number, numeral) in numerals.iter() {
+        while value >= number {
+            text.push_str(numeral);
+            value -= number;
+        }
+    }
+    text
+}
+
+// 1 is `a`, 26 is `z`, 27 is `aa`.
+fn alphabetic(mut value: i32, first: u8) -> String {
+    let mut letters = Vec::new();
+
+    while value > 0 {
+        value -= 1;
+        letters.push((first + (value % 26) as u8) as char);
+        value /= 26;
+    }
+    letters.iter().rev().collect()
+}
+
+fn default_quotes() -> Vec<(String, String)> {
+    vec![
+        ("\u{201C}".to_string(), "\u{201D}".to_string()),
+        ("\u{2018}".to_string(), "\u{2019}".to_string()),
+    ]
+}
+
+// CSS-wide keywords and `none` cannot name a counter.
+fn counter_name(name: &str) -> Option<String> {
+    match &name.to_ascii_lowercase()[..] {
+        "none" | "inherit" | "initial" | "unset" | "revert" | "revert-layer" | "default" => None,
+        _ => Some(name.to_string()),
+    }
+}
+
+// Comma separated function arguments, each a single token.
+fn split_arguments<'t, 'a>(tokens: &'t TokenList<'a>) -> Option<Vec<&'t Token<'a>>> {
+    let mut arguments = Vec::new();
+
+    for argument in tokens.split(|(token, _)| *token == Token::Comma) {
+        match *trim_whitespace(argument) {
+            [(ref token, _)] => arguments.push(token),
+            _ => return None,
+        }
+    }
+    Some(arguments)
+}
@@ -1,17 +1,41 @@
+use crate::atom::Atom;
+use crate::css_parser::lowercase;
 use crate::dom::{AttributeMap, ElementData, Node, NodeType};
+use crate::source::LineIndex;
 
-use std::iter::Peekable;
-use std::str::Chars;
+use std::borrow::Cow;
+use std::sync::OnceLock;
+
+pub const XHTML_NAMESPACE: &str = "http://www.w3.org/1999/xhtml";
 
 pub struct HtmlParser<'a> {
-    chars: Peekable<Chars<'a>>,
-    node_q: Vec<String>,
+    input: &'a str,
+    position: usize,
+    lines: LineIndex<'a>,
+    node_q: Vec<Atom>,
+}
+
+// Interned once, so elements can be checked for the HTML namespace by pointer.
+pub fn xhtml_namespace() -> Atom {
+    static NAMESPACE: OnceLock<Atom> = OnceLock::new();
+    *NAMESPACE.get_or_init(|| Atom::from(XHTML_NAMESPACE))
 }
 
 impl<'a> HtmlParser<'a> {
-    pub fn new(full_html: &str) -> HtmlParser {
+    pub fn new(full_html: &'a str) -> HtmlParser<'a> {
+        HtmlParser {
+            input: full_html,
+            position: 0,
+            lines: LineIndex::new(full_html, None),
+            node_q: Vec::new(),
+        }
+    }
+
+    pub fn new_with_file(full_html: &'a str, file: &str) -> HtmlParser<'a> {
         HtmlParser {
-            chars: full_html.chars().peekable(),
+            input: full_html,
+            position: 0,
+            lines: LineIndex::new(full_html, Some(Atom::from(file))),
             node_q: Vec::new(),
         }
     }
@@ -19,202 +43,132 @@ impl<'a> HtmlParser<'a> {
     pub fn parse_nodes(&mut self) -> Vec<Node> {
         let mut nodes = Vec::new();
 
-        while self.chars.peek().is_some() {
+        while self.peek().is_some() {
             self.consume_while(char::is_whitespace);
-            if self.chars.peek().map_or(false, |c| *c == '<') {
-                self.chars.next();
-                if self.chars.peek().map_or(false, |c| *c == '/') {
-                    self.chars.next();
-                    self.consume_while(char::is_whitespace);
-
-                    let close_tag_name = self.consume_while(is_valid_tag_name);
-
-                    self.consume_while(|x| x != '>');
-                    self.chars.next();
-
-                    self.node_q.push(close_tag_name);
-                    break;
-                } else if self.chars.peek().map_or(false, |c| *c == '!') {
-                    self.chars.next();
-                    nodes.push(self.parse_comment_node());
-                } else {
-                    let mut node = self.parse_node();
-                    let insert_index = nodes.len();
-
-                    match node.node_type {
-                        NodeType::Element(ref e) => {
-                            if self.node_q.len() > 0 {
-                                let assumed_tag = self.node_q.remove(0);
-
-                                if e.tag_name != assumed_tag {
-                                    nodes.append(&mut node.children);
-                                    self.node_q.insert(0, assumed_tag);
-                                }
-                            }
+            let start = self.position;
+
+            if self.consume_str("</") {
+                self.consume_while(char::is_whitespace);
+
+                let close_tag_name =
+                    Atom::from(&lowercase(self.consume_while(is_valid_tag_name))[..]);
+
+                self.consume_while(|x| x != '>');
+                self.next_char();
+
+                self.node_q.push(close_tag_name);
+                break;
+            } else if self.consume_str("<!") {
+                let mut node = self.parse_comment_node();
+                node.location = self.lines.location(start, self.position);
+                nodes.push(node);
+            } else if self.consume_str("<") {
+                let mut node = self.parse_node();
+                let insert_index = nodes.len();
+                node.location = self.lines.location(start, self.position);
+
+                if let NodeType::Element(ref e) = node.node_type {
+                    if !self.node_q.is_empty() {
+                        let assumed_tag = self.node_q.remove(0);
+
+                        if e.tag_name != assumed_tag {
+                            nodes.append(&mut node.children);
+                            self.node_q.insert(0, assumed_tag);
                         }
-                        _ => {}
                     }
-
-                    nodes.insert(insert_index, node);
                 }
-            } else {
-                nodes.push(self.parse_text_node());
+
+                nodes.insert(insert_index, node);
+            } else if self.peek().is_some() {
+                let mut node = self.parse_text_node();
+                node.location = self.lines.location(start, self.position);
+                nodes.push(node);
             }
         }
         nodes
     }
 
     fn parse_node(&mut self) -> Node {
-        let tagname = self.consume_while(is_valid_tag_name);
+        let tagname = lowercase(self.consume_while(is_valid_tag_name));
         let attributes = self.parse_attributes();
 
-        let elem = ElementData::new(tagname, attributes);
+        let elem = ElementData::new_with_namespace(
+            Atom::from(&tagname[..]),
+            Some(xhtml_namespace()),
+            attributes,
+        );
         let children = self.parse_nodes();
         Node::new(NodeType::Element(elem), children)
     }
 
     fn parse_text_node(&mut self) -> Node {
-        let mut text_content = String::new();
+        let text_content = collapse_whitespace(self.consume_while(|x| x != '<'));
 
-        while self.chars.peek().map_or(false, |c| *c != '<') {
-            let whitespace = self.consume_while(char::is_whitespace);
-            if whitespace.len() > 0 {
-                text_content.push(' ');
-            }
-            let text_part = self.consume_while(|x| !x.is_whitespace() && x != '<');
-            text_content.push_str(&text_part);
-        }
-        Node::new(NodeType::Text(text_content), Vec::new())
+        Node::new(NodeType::Text(text_content.into_owned()), Vec::new())
     }
 
     fn parse_comment_node(&mut self) -> Node {
-        let mut comment_content = String::new();
-
-        if self.chars.peek().map_or(false, |c| *c == '-') {
-            self.chars.next();
-            if self.chars.peek().map_or(false, |c| *c == '-') {
-                self.chars.next();
-            } else {
-                self.consume_while(|c| c != '>');
-                return Node::new(NodeType::Comment(comment_content), Vec::new());
-            }
-        } else {
+        if !self.consume_str("--") {
             self.consume_while(|c| c != '>');
-            return Node::new(NodeType::Comment(comment_content), Vec::new());
+            self.next_char();
+            return Node::new(NodeType::Comment(String::new()), Vec::new());
         }
 
-        if self.chars.peek().map_or(false, |c| *c == '>') {
-            self.chars.next();
-            return Node::new(NodeType::Comment(comment_content), Vec::new());
+        if self.consume_str(">") || self.consume_str("->") {
+            return Node::new(NodeType::Comment(String::new()), Vec::new());
         }
 
-        if self.chars.peek().map_or(false, |c| *c == '-') {
-            self.chars.next();
-            if self.chars.peek().map_or(false, |c| *c == '>') {
-                self.chars.next();
-                return Node::new(NodeType::Comment(comment_content), Vec::new());
-            } else {
-                comment_content.push('-');
+        let rest = &self.input[self.position..];
+        let comment_content = match rest.find("-->") {
+            Some(end) => {
+                self.position += end + "-->".len();
+                &rest[..end]
             }
-        }
-
-        while self.chars.peek().is_some() {
-            comment_content.push_str(&self.consume_while(|c| c != '<' && c != '-'));
-            if self.chars.peek().map_or(false, |c| *c == '<') {
-                self.chars.next();
-                if self.chars.peek().map_or(false, |c| *c == '!') {
-                    self.chars.next();
-                    if self.chars.peek().map_or(false, |c| *c == '-') {
-                        self.chars.next();
-                        if self.chars.peek().map_or(false, |c| *c == '-') {
-                            self.consume_while(|c| c != '>');
-
-                            return Node::new(NodeType::Comment(String::from("")), Vec::new());
-                        } else {
-                            comment_content.push_str("<!-");
-                        }
-                    } else if self.chars.peek().map_or(false, |c| *c == ' ') {
-                        self.chars.next();
-                        if self.chars.peek().map_or(false, |c| *c == '-') {
-                            self.chars.next();
-                            if self.chars.peek().map_or(false, |c| *c == '-') {
-                                self.chars.next();
-                                if self.chars.peek().map_or(false, |c| *c == '-') {
-                                    self.chars.next();
-                                    if self.chars.peek().map_or(false, |c| *c == '>') {
-                                        self.chars.next();
-                                        return Node::new(
-                                            NodeType::Comment(String::from("")),
-                                            Vec::new(),
-                                        );
-                                    } else {
-                                        comment_content.push_str("<! --");
-                                    }
-                                } else {
-                                    comment_content.push_str("<! -");
-                                }
-                            } else {
-                                comment_content.push_str("<! ");
-                            }
-                        }
-                    } else {
-                        comment_content.push_str("<!");
-                    }
-                } else {
-                    comment_content.push('<');
-                }
-            } else if self.chars.peek().map_or(false, |c| *c == '-') {
-                self.chars.next();
-                if self.chars.peek().map_or(false, |c| *c == '-') {
-                    self.chars.next();
-                    if self.chars.peek().map_or(false, |c| *c == '>') {
-                        self.chars.next();
-                        break;
-                    } else {
-                        comment_content.push_str("--");
-                    }
-                } else {
-                    comment_content.push('-');
-                }
+            None => {
+                self.position = self.input.len();
+                rest
             }
-        }
+        };
 
-        Node::new(NodeType::Comment(comment_content), Vec::new())
+        Node::new(NodeType::Comment(comment_content.to_string()), Vec::new())
     }
 
     fn parse_attributes(&mut self) -> AttributeMap {
         let mut attributes = AttributeMap::new();
 
-        while self.chars.peek().map_or(false, |c| *c != '>') {
-            self.consume_while(char::is_whitespace);
-            let name = self.consume_while(|c| is_valid_attr_name(c)).to_lowercase();
+        while self.peek().is_some_and(|c| c != '>') {
+            self.consume_while(|c| c.is_whitespace() || c == '/');
+            if self.peek().is_none_or(|c| c == '>') {
+                break;
+            }
+
+            let name = lowercase(self.consume_while(is_valid_attr_name));
             self.consume_while(char::is_whitespace);
 
-            let value = if self.chars.peek().map_or(false, |c| *c == '=') {
-                self.chars.next();
+            let value = if self.consume_str("=") {
                 self.consume_while(char::is_whitespace);
                 let s = self.parse_attr_value();
                 self.consume_while(|c| !c.is_whitespace() && c != '>');
                 self.consume_while(char::is_whitespace);
                 s
             } else {
-                "".to_string()
+                ""
             };
-            attributes.insert(name, value);
+            attributes.insert(Atom::from(&name[..]), value.to_string());
         }
-        self.chars.next();
+        self.next_char();
 
         attributes
     }
 
-    fn parse_attr_value(&mut self) -> String {
+    fn parse_attr_value(&mut self) -> &'a str {
         self.consume_while(char::is_whitespace);
 
-        let result = match self.chars.peek() {
-            Some(&c) if c == '"' || c == '\'' => {
-                self.chars.next();
+        let result = match self.peek() {
+            Some(c) if c == '"' || c == '\'' => {
+                self.next_char();
                 let ret = self.consume_while(|x| x != c);
-                self.chars.next();
+                self.next_char();
                 ret
             }
             _ => self.consume_while(is_valid_attr_value),
@@ -223,17 +177,65 @@ impl<'a> HtmlParser<'a> {
         result
     }
 
-    fn consume_while<F>(&mut self, condition: F) -> String
+    fn peek(&self) -> Option<char> {
+        self.input[self.position..].chars().next()
+    }
+
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += c.len_utf8();
+        Some(c)
+    }
+
+    fn consume_str(&mut self, s: &str) -> bool {
+        if self.input[self.position..].starts_with(s) {
+            self.position += s.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn consume_while<F>(&mut self, condition: F) -> &'a str
     where
         F: Fn(char) -> bool,
     {
-        let mut result = String::new();
-        while self.chars.peek().map_or(false, |c| condition(*c)) {
-            result.push(self.chars.next().unwrap());
-        }
+        let start = self.position;
+        let rest = &self.input[start..];
 
-        result
+        self.position += rest
+            .char_indices()
+            .find(|&(_, c)| !condition(c))
+            .map_or(rest.len(), |(i, _)| i);
+
+        &self.input[start..self.position]
+    }
+}
+
+fn collapse_whitespace(text: &str) -> Cow<'_, str> {
+    let mut previous_space = false;
+    let collapsed = text.chars().all(|c| {
+        let ok = c == ' ' && !previous_space || !c.is_whitespace();
+        previous_space = c.is_whitespace();
+        ok
+    });
+
+    if collapsed {
+        return Cow::Borrowed(text);
+    }
+
+    let mut result = String::with_capacity(text.len());
+    for (i, part) in text.split_whitespace().enumerate() {
+        if i > 0 {
+            result.push(' ');
+        }
+        result.push_str(part);
     }
+    if text.ends_with(char::is_whitespace) {
+        result.push(' ');
+    }
+
+    Cow::Owned(result)
 }
 
 fn is_valid_tag_name(ch: char) -> bool {
@@ -245,24 +247,13 @@ fn is_valid_attr_name(c: char) -> bool {
 }
 
 fn is_control(ch: char) -> bool {
-    match ch {
-        '\u{007F}' => true,
-        c if c >= '\u{0000}' && c <= '\u{001F}' => true,
-        c if c >= '\u{0080}' && c <= '\u{009F}' => true,
-        _ => false,
-    }
+    matches!(ch, '\u{0000}'..='\u{001F}' | '\u{007F}'..='\u{009F}')
 }
 
 fn is_excluded_name(c: char) -> bool {
-    match c {
-        ' ' | '"' | '\'' | '>' | '/' | '=' => true,
-        _ => false,
-    }
+    matches!(c, ' ' | '"' | '\'' | '>' | '/' | '=')
 }
 
 fn is_valid_attr_value(c: char) -> bool {
-    match c {
-        ' ' | '"' | '\'' | '=' | '<' | '>' | '`' => false,
-        _ => true,
-    }
+    !matches!(c, ' ' | '"' | '\'' | '=' | '<' | '>' | '`')
 }
@@ -1,6 +1,9 @@
 use std::fmt;
 
-use crate::css::{Unit, Value};
+use crate::css::Value;
+use crate::media::MediaEnvironment;
+use crate::properties::{self, Grammar};
+use crate::source::SourceLocation;
 use crate::style::{Display, StyledNode};
 
 #[derive(Clone)]
@@ -46,52 +49,77 @@ pub enum BoxType {
 impl<'a> LayoutBox<'a> {
     pub fn new(box_type: BoxType, styled_node: &'a StyledNode) -> LayoutBox<'a> {
         LayoutBox {
-            box_type: box_type,
-            styled_node: styled_node,
+            box_type,
+            styled_node,
             dimensions: Default::default(),
             children: Vec::new(),
         }
     }
 
-    fn layout(&mut self, b_box: Dimensions) {
+    pub fn get_location(&self) -> &'a SourceLocation {
+        self.styled_node.get_location()
+    }
+
+    pub fn hit_test(&self, x: f32, y: f32) -> Option<&LayoutBox<'a>> {
+        for child in self.children.iter().rev() {
+            if let Some(hit) = child.hit_test(x, y) {
+                return Some(hit);
+            }
+        }
+
+        if self.dimensions.border_box().contains(x, y) {
+            Some(self)
+        } else {
+            None
+        }
+    }
+
+    // Font relative units in the box's own styles refer to its computed
+    // font size.
+    fn layout(&mut self, b_box: Dimensions, env: &MediaEnvironment) {
+        let env = &MediaEnvironment {
+            font_size: self.styled_node.get_font_size(),
+            ..env.clone()
+        };
+
         match self.box_type {
-            BoxType::Block => self.layout_block(b_box),
-            BoxType::Inline => self.layout_block(b_box),
-            BoxType::InlineBlock => self.layout_inline_block(b_box),
+            BoxType::Block => self.layout_block(b_box, env),
+            BoxType::Inline => self.layout_block(b_box, env),
+            BoxType::InlineBlock => self.layout_inline_block(b_box, env),
             BoxType::Anonymous => {}
         }
     }
 
-    fn layout_inline_block(&mut self, b_box: Dimensions) {
-        self.calculate_inline_width(b_box);
-        self.calculate_inline_position(b_box);
-        self.layout_children();
-        self.calculate_height();
+    fn layout_inline_block(&mut self, b_box: Dimensions, env: &MediaEnvironment) {
+        self.calculate_inline_width(b_box, env);
+        self.calculate_inline_position(b_box, env);
+        self.layout_children(env);
+        self.calculate_height(env);
     }
 
-    fn calculate_inline_width(&mut self, b_box: Dimensions) {
+    fn calculate_inline_width(&mut self, b_box: Dimensions, env: &MediaEnvironment) {
         let s = self.styled_node;
         let d = &mut self.dimensions;
 
-        d.content.width = get_absolute_num(s, b_box, "width").unwrap_or(0.0);
-        d.margin.left = s.num_or("margin-left", 0.0);
-        d.margin.right = s.num_or("margin-right", 0.0);
-        d.padding.left = s.num_or("padding-left", 0.0);
-        d.padding.right = s.num_or("padding-right", 0.0);
-        d.border.left = s.num_or("border-left-width", 0.0);
-        d.border.right = s.num_or("border-right-width", 0.0);
+        d.content.width = get_absolute_num(s, b_box, env, "width").unwrap_or(0.0);
+        d.margin.left = length_or(s, b_box, env, "margin-left", 0.0);
+        d.margin.right = length_or(s, b_box, env, "margin-right", 0.0);
+        d.padding.left = length_or(s, b_box, env, "padding-left", 0.0);
+        d.padding.right = length_or(s, b_box, env, "padding-right", 0.0);
+        d.border.left = length_or(s, b_box, env, "border-left-width", 0.0);
+        d.border.right = length_or(s, b_box, env, "border-right-width", 0.0);
     }
 
-    fn calculate_inline_position(&mut self, b_box: Dimensions) {
+    fn calculate_inline_position(&mut self, b_box: Dimensions, env: &MediaEnvironment) {
         let style = self.styled_node;
         let d = &mut self.dimensions;
 
-        d.margin.top = style.num_or("margin-top", 0.0);
-        d.margin.bottom = style.num_or("margin-bottom", 0.0);
-        d.border.top = style.num_or("border-top-width", 0.0);
-        d.border.bottom = style.num_or("border-bottom-width", 0.0);
-        d.padding.top = style.num_or("padding-top", 0.0);
-        d.padding.bottom = style.num_or("padding-bottom", 0.0);
+        d.margin.top = length_or(style, b_box, env, "margin-top", 0.0);
+        d.margin.bottom = length_or(style, b_box, env, "margin-bottom", 0.0);
+        d.border.top = length_or(style, b_box, env, "border-top-width", 0.0);
+        d.border.bottom = length_or(style, b_box, env, "border-bottom-width", 0.0);
+        d.padding.top = length_or(style, b_box, env, "padding-top", 0.0);
+        d.padding.bottom = length_or(style, b_box, env, "padding-bottom", 0.0);
 
         d.content.x =
             b_box.content.x + b_box.current.x + d.margin.left + d.border.left + d.padding.left;
@@ -99,40 +127,29 @@ impl<'a> LayoutBox<'a> {
             b_box.content.height + b_box.content.y + d.margin.top + d.border.top + d.padding.top;
     }
 
-    fn layout_block(&mut self, b_box: Dimensions) {
-        self.calculate_width(b_box);
-        self.calculate_position(b_box);
-        self.layout_children();
-        self.calculate_height();
+    fn layout_block(&mut self, b_box: Dimensions, env: &MediaEnvironment) {
+        self.calculate_width(b_box, env);
+        self.calculate_position(b_box, env);
+        self.layout_children(env);
+        self.calculate_height(env);
     }
 
-    fn calculate_width(&mut self, b_box: Dimensions) {
+    fn calculate_width(&mut self, b_box: Dimensions, env: &MediaEnvironment) {
         let style = self.styled_node;
         let d = &mut self.dimensions;
 
-        let width = get_absolute_num(style, b_box, "width").unwrap_or(0.0);
-        let margin_l = style.value("margin-left");
-        let margin_r = style.value("margin-right");
+        let width = get_absolute_num(style, b_box, env, "width").unwrap_or(0.0);
+        let is_auto = |value: &&Value| **value == Value::Keyword(String::from("auto"));
+        let margin_l = style.value("margin-left").filter(|value| !is_auto(value));
+        let margin_r = style.value("margin-right").filter(|value| !is_auto(value));
 
-        let margin_l_num = match margin_l {
-            Some(m) => match **m {
-                Value::Other(ref s) => s.parse().unwrap_or(0.0),
-                _ => 0.0,
-            },
-            None => 0.0,
-        };
-        let margin_r_num = match margin_r {
-            Some(m) => match **m {
-                Value::Other(ref s) => s.parse().unwrap_or(0.0),
-                _ => 0.0,
-            },
-            None => 0.0,
-        };
+        let margin_l_num = length_or(style, b_box, env, "margin-left", 0.0);
+        let margin_r_num = length_or(style, b_box, env, "margin-right", 0.0);
 
-        d.border.left = style.num_or("border-left-width", 0.0);
-        d.border.right = style.num_or("border-right-width", 0.0);
-        d.padding.left = style.num_or("padding-left", 0.0);
-        d.padding.right = style.num_or("padding-right", 0.0);
+        d.border.left = length_or(style, b_box, env, "border-left-width", 0.0);
+        d.border.right = length_or(style, b_box, env, "border-right-width", 0.0);
+        d.padding.left = length_or(style, b_box, env, "padding-left", 0.0);
+        d.padding.right = length_or(style, b_box, env, "padding-right", 0.0);
 
         let total = width
             + margin_l_num
@@ -178,48 +195,47 @@ impl<'a> LayoutBox<'a> {
         }
     }
 
-    fn calculate_position(&mut self, b_box: Dimensions) {
+    fn calculate_position(&mut self, b_box: Dimensions, env: &MediaEnvironment) {
         let style = self.styled_node;
         let d = &mut self.dimensions;
 
-        d.margin.top = style.num_or("margin-top", 0.0);
-        d.margin.bottom = style.num_or("margin-bottom", 0.0);
-        d.border.top = style.num_or("border-top-width", 0.0);
-        d.border.bottom = style.num_or("border-bottom-width", 0.0);
-        d.padding.top = style.num_or("padding-top", 0.0);
-        d.padding.bottom = style.num_or("padding-bottom", 0.0);
+        d.margin.top = length_or(style, b_box, env, "margin-top", 0.0);
+        d.margin.bottom = length_or(style, b_box, env, "margin-bottom", 0.0);
+        d.border.top = length_or(style, b_box, env, "border-top-width", 0.0);
+        d.border.bottom = length_or(style, b_box, env, "border-bottom-width", 0.0);
+        d.padding.top = length_or(style, b_box, env, "padding-top", 0.0);
+        d.padding.bottom = length_or(style, b_box, env, "padding-bottom", 0.0);
 
         d.content.x = b_box.content.x + d.margin.left + d.border.left + d.padding.left;
         d.content.y =
             b_box.content.height + b_box.content.y + d.margin.top + d.border.top + d.padding.top;
     }
 
-    fn calculate_height(&mut self) {
-        self.styled_node.value("height").map_or((), |h| match **h {
-            Value::Length(n, _) => self.dimensions.content.height = n,
-            _ => {}
-        })
+    fn calculate_height(&mut self, env: &MediaEnvironment) {
+        if let Some(n) = self
+            .styled_node
+            .value("height")
+            .and_then(|h| resolve_length("height", h, None, env))
+        {
+            self.dimensions.content.height = n;
+        }
     }
 
-    fn layout_children(&mut self) {
+    fn layout_children(&mut self, env: &MediaEnvironment) {
         let d = &mut self.dimensions;
         let mut max_child_height = 0.0;
 
         let mut prev_box_type = BoxType::Block;
 
         for child in &mut self.children {
-            match prev_box_type {
-                BoxType::InlineBlock => match child.box_type {
-                    BoxType::Block => {
-                        d.content.height += max_child_height;
-                        d.current.x = 0.0;
-                    }
-                    _ => {}
-                },
-                _ => {}
+            if matches!(prev_box_type, BoxType::InlineBlock)
+                && matches!(child.box_type, BoxType::Block)
+            {
+                d.content.height += max_child_height;
+                d.current.x = 0.0;
             }
 
-            child.layout(*d);
+            child.layout(*d, env);
             let new_height = child.dimensions.margin_box().height;
 
             if new_height > max_child_height {
@@ -234,7 +250,7 @@ impl<'a> LayoutBox<'a> {
                     if d.current.x > d.content.width {
                         d.content.height += max_child_height;
                         d.current.x = 0.0;
-                        child.layout(*d);
+                        child.layout(*d, env);
                         d.current.x += child.dimensions.margin_box().width;
                     }
                 }
@@ -276,6 +292,10 @@ impl fmt::Debug for Dimensions {
 }
 
 impl Rectangle {
+    fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+
     fn expanded(&self, e: EdgeSizes) -> Rectangle {
         Rectangle {
             x: self.x - e.left,
@@ -318,35 +338,82 @@ impl fmt::Debug for BoxType {
     }
 }
 
-fn get_absolute_num(s_node: &StyledNode, b_box: Dimensions, prop: &str) -> Option<f32> {
-    match s_node.value(prop) {
-        Some(ref v) => match ***v {
-            Value::Length(l, ref u) => match *u {
-                Unit::Px => Some(l),
-                Unit::Pct => Some(l * b_box.content.width / 100.0),
-                _ => panic!("Unimplemented css length unit"),
-            },
-            _ => None,
-        },
-        None => None,
+fn get_absolute_num(
+    s_node: &StyledNode,
+    b_box: Dimensions,
+    env: &MediaEnvironment,
+    prop: &str,
+) -> Option<f32> {
+    s_node
+        .value(prop)
+        .and_then(|value| resolve_length(prop, value, Some(b_box.content.width), env))
+}
+
+// Percentages in margins, borders and padding refer to the containing
+// block's width.
+fn length_or(
+    s_node: &StyledNode,
+    b_box: Dimensions,
+    env: &MediaEnvironment,
+    prop: &str,
+    default: f32,
+) -> f32 {
+    get_absolute_num(s_node, b_box, env, prop).unwrap_or(default)
+}
+
+// The result of a math function is only known here, so it is clamped to
+// what the property accepts, which turns an infinity into the largest or
+// smallest length allowed. NaN makes the declaration invalid, which leaves
+// the property's initial value.
+fn resolve_length(
+    name: &str,
+    value: &Value,
+    percentage_basis: Option<f32>,
+    env: &MediaEnvironment,
+) -> Option<f32> {
+    match *value {
+        Value::Length(length, unit) => Some(unit.to_px(length, env)),
+        Value::Percentage(percentage) => percentage_basis.map(|basis| percentage * basis / 100.0),
+        Value::Calc(ref calc) => {
+            let length = calc.resolve(percentage_basis, env)?;
+            let min = match properties::lookup(name).map(|property| property.grammar) {
+                Some(Grammar::LengthOr(_)) | Some(Grammar::LineWidth) => 0.0,
+                _ => f32::MIN,
+            };
+
+            if length.is_nan() {
+                let initial = properties::initial_value(name)?;
+                resolve_length(name, initial, percentage_basis, env)
+            } else {
+                Some(length.max(min).min(f32::MAX))
+            }
+        }
+        _ => None,
     }
 }
 
+// Viewport units refer to `env`, the environment the style tree was built
+// for.
 pub fn layout_tree<'a>(
     root: &'a StyledNode<'a>,
     mut containing_block: Dimensions,
+    env: &MediaEnvironment,
 ) -> LayoutBox<'a> {
     containing_block.content.height = 0.0;
 
+    let env = &MediaEnvironment {
+        root_font_size: root.get_font_size(),
+        ..env.clone()
+    };
     let mut root_box = build_layout_tree(root);
-    root_box.layout(containing_block);
-    return root_box;
+    root_box.layout(containing_block, env);
+    root_box
 }
 
 fn build_layout_tree<'a>(node: &'a StyledNode) -> LayoutBox<'a> {
     let mut layout_node = LayoutBox::new(
         match node.get_display() {
-            Display::Block => BoxType::Block,
+            Display::Block | Display::ListItem => BoxType::Block,
             Display::Inline => BoxType::Inline,
             Display::InlineBlock => BoxType::InlineBlock,
             Display::None => BoxType::Anonymous,
@@ -357,6 +424,7 @@ fn build_layout_tree<'a>(node: &'a StyledNode) -> LayoutBox<'a> {
     for child in &node.children {
         match child.get_display() {
             Display::Block => layout_node.children.push(build_layout_tree(child)),
+            Display::ListItem => layout_node.children.push(build_layout_tree(child)),
             Display::Inline => layout_node.children.push(build_layout_tree(child)),
             Display::InlineBlock => layout_node.children.push(build_layout_tree(child)),
             Display::None => {}
@@ -365,10 +433,10 @@ fn build_layout_tree<'a>(node: &'a StyledNode) -> LayoutBox<'a> {
     layout_node
 }
 
-pub fn pretty_print<'a>(n: &'a LayoutBox, level: usize) {
+pub fn pretty_print(n: &LayoutBox, level: usize) {
     println!("{}{:?}\n", level, n);
 
     for child in n.children.iter() {
-        pretty_print(&child, level + 1);
+        pretty_print(child, level + 1);
     }
 }
@@ -4,11 +4,25 @@ extern crate gfx_text;
 extern crate gfx_window_glutin;
 extern crate glutin;
 
+pub mod atom;
+pub mod calc;
+pub mod color;
 pub mod command;
 pub mod css;
+pub mod css_loader;
 pub mod css_parser;
+pub mod css_tokenizer;
+pub mod custom_properties;
 pub mod dom;
+pub mod generated;
 pub mod html_parser;
 pub mod layout;
+pub mod media;
+pub mod properties;
 pub mod render;
+pub mod selector;
+pub mod serializer;
+pub mod shorthand;
+pub mod source;
 pub mod style;
+pub mod xml_parser;
@@ -0,0 +1,680 @@
+use std::fmt;
+
+use crate::css_parser::{split_commas, split_top_level, trim_whitespace};
+use crate::css_tokenizer::{serialize_identifier, Token};
+
+#[derive(PartialEq, Clone, Default)]
+pub struct MediaList {
+    pub(crate) queries: Vec<MediaQuery>,
+}
+
+#[derive(PartialEq, Clone)]
+pub struct MediaQuery {
+    pub(crate) qualifier: Option<Qualifier>,
+    pub(crate) media_type: Option<String>,
+    pub(crate) condition: Option<MediaCondition>,
+}
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum Qualifier {
+    Only,
+    Not,
+}
+
+#[derive(PartialEq, Clone)]
+pub enum MediaCondition {
+    Feature(MediaFeature),
+    Not(Box<MediaCondition>),
+    And(Vec<MediaCondition>),
+    Or(Vec<MediaCondition>),
+    Unknown(String),
+}
+
+#[derive(PartialEq, Clone)]
+pub struct MediaFeature {
+    pub(crate) name: String,
+    pub(crate) test: FeatureTest,
+}
+
+// `(name)`, `(name: value)` or `(name op value)`, and `(value op name op
+// value)`; each comparison reads with the feature on its left.
+#[derive(PartialEq, Clone)]
+pub enum FeatureTest {
+    Boolean,
+    Compare(Comparison, MediaValue),
+    Range((Comparison, MediaValue), (Comparison, MediaValue)),
+}
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum Comparison {
+    Equal,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+#[derive(PartialEq, Clone)]
+pub enum MediaValue {
+    Number(f32),
+    Dimension(f32, String),
+    Ratio(f32, f32),
+    Ident(String),
+}
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum ColorScheme {
+    Light,
+    Dark,
+}
+
+#[derive(PartialEq, Clone)]
+pub struct MediaEnvironment {
+    pub media_type: String,
+    pub width: f32,
+    pub height: f32,
+    pub resolution: f32,
+    // What em and rem refer to. Media queries use the initial font size for
+    // both; styling and layout set them to the element's and the root's.
+    pub font_size: f32,
+    pub root_font_size: f32,
+    pub prefers_color_scheme: ColorScheme,
+}
+
+impl Default for MediaEnvironment {
+    fn default() -> Self {
+        MediaEnvironment {
+            media_type: String::from("screen"),
+            width: 1024.0,
+            height: 768.0,
+            resolution: 1.0,
+            font_size: 16.0,
+            root_font_size: 16.0,
+            prefers_color_scheme: ColorScheme::Light,
+        }
+    }
+}
+
+impl MediaEnvironment {
+    pub fn new(width: f32, height: f32) -> MediaEnvironment {
+        MediaEnvironment {
+            width,
+            height,
+            ..MediaEnvironment::default()
+        }
+    }
+}
+
+impl MediaList {
+    pub fn new(queries: Vec<MediaQuery>) -> MediaList {
+        MediaList { queries }
+    }
+
+    pub fn matches(&self, env: &MediaEnvironment) -> bool {
+        self.queries.is_empty() || self.queries.iter().any(|query| query.matches(env))
+    }
+}
+
+impl MediaQuery {
+    // What an invalid query is replaced with.
+    fn not_all() -> MediaQuery {
+        MediaQuery {
+            qualifier: Some(Qualifier::Not),
+            media_type: None,
+            condition: None,
+        }
+    }
+
+    pub fn matches(&self, env: &MediaEnvironment) -> bool {
+        let type_matches = match self.media_type {
+            Some(ref media_type) => media_type.eq_ignore_ascii_case(&env.media_type),
+            None => true,
+        };
+        let condition_matches = match self.condition {
+            Some(ref condition) => condition.evaluate(env),
+            None => Some(true),
+        };
+
+        // `not` leaves an unknown result unknown, and only the query as a
+        // whole treats unknown as false.
+        let result = if type_matches {
+            condition_matches
+        } else {
+            Some(false)
+        };
+        let result = match self.qualifier {
+            Some(Qualifier::Not) => result.map(|result| !result),
+            _ => result,
+        };
+        result.unwrap_or(false)
+    }
+}
+
+// Evaluation uses three-valued logic: None is the spec's "unknown".
+impl MediaCondition {
+    fn evaluate(&self, env: &MediaEnvironment) -> Option<bool> {
+        match *self {
+            MediaCondition::Feature(ref feature) => feature.evaluate(env),
+            MediaCondition::Not(ref condition) => condition.evaluate(env).map(|result| !result),
+            MediaCondition::And(ref conditions) => {
+                let mut result = Some(true);
+                for condition in conditions {
+                    match condition.evaluate(env) {
+                        Some(false) => return Some(false),
+                        None => result = None,
+                        Some(true) => {}
+                    }
+                }
+                result
+            }
+            MediaCondition::Or(ref conditions) => {
+                let mut result = Some(false);
+                for condition in conditions {
+                    match condition.evaluate(env) {
+                        Some(true) => return Some(true),
+                        None => result = None,
+                        Some(false) => {}
+                    }
+                }
+                result
+            }
+            MediaCondition::Unknown(_) => None,
+        }
+    }
+}
+
+impl MediaFeature {
+    fn evaluate(&self, env: &MediaEnvironment) -> Option<bool> {
+        match &self.name[..] {
+            "width" => self.compare_numbers(env.width, |value| value.to_px(env)),
+            "height" => self.compare_numbers(env.height, |value| value.to_px(env)),
+            "resolution" => self.compare_numbers(env.resolution, MediaValue::to_dppx),
+            "orientation" => {
+                let orientation = if env.height >= env.width {
+                    "portrait"
+                } else {
+                    "landscape"
+                };
+                self.compare_keyword(orientation, &["portrait", "landscape"])
+            }
+            "prefers-color-scheme" => {
+                let scheme = match env.prefers_color_scheme {
+                    ColorScheme::Light => "light",
+                    ColorScheme::Dark => "dark",
+                };
+                self.compare_keyword(scheme, &["light", "dark"])
+            }
+            _ => None,
+        }
+    }
+
+    fn compare_numbers<F>(&self, actual: f32, convert: F) -> Option<bool>
+    where
+        F: Fn(&MediaValue) -> Option<f32>,
+    {
+        let compare = |comparison: Comparison, value: &MediaValue| {
+            let expected = convert(value)?;
+            Some(match comparison {
+                Comparison::Equal => actual == expected,
+                Comparison::Less => actual < expected,
+                Comparison::LessOrEqual => actual <= expected,
+                Comparison::Greater => actual > expected,
+                Comparison::GreaterOrEqual => actual >= expected,
+            })
+        };
+
+        match self.test {
+            FeatureTest::Boolean => Some(actual != 0.0),
+            FeatureTest::Compare(comparison, ref value) => compare(comparison, value),
+            FeatureTest::Range((low, ref low_value), (high, ref high_value)) => {
+                let low = compare(low, low_value)?;
+                let high = compare(high, high_value)?;
+                Some(low && high)
+            }
+        }
+    }
+
+    fn compare_keyword(&self, actual: &str, allowed: &[&str]) -> Option<bool> {
+        match self.test {
+            FeatureTest::Boolean => Some(true),
+            FeatureTest::Compare(Comparison::Equal, MediaValue::Ident(ref expected)) => {
+                if allowed.contains(&&expected[..]) {
+                    Some(actual == expected)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+impl MediaValue {
+    fn to_px(&self, env: &MediaEnvironment) -> Option<f32> {
+        match *self {
+            MediaValue::Number(0.0) => Some(0.0),
+            MediaValue::Dimension(n, ref unit) => match &unit[..] {
+                "px" => Some(n),
+                "em" | "rem" => Some(n * env.font_size),
+                "vw" => Some(n * env.width / 100.0),
+                "vh" => Some(n * env.height / 100.0),
+                "vmin" => Some(n * env.width.min(env.height) / 100.0),
+                "vmax" => Some(n * env.width.max(env.height) / 100.0),
+                "in" => Some(n * 96.0),
+                "cm" => Some(n * 96.0 / 2.54),
+                "mm" => Some(n * 96.0 / 25.4),
+                "q" => Some(n * 96.0 / 101.6),
+                "pt" => Some(n * 96.0 / 72.0),
+                "pc" => Some(n * 16.0),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn to_dppx(&self) -> Option<f32> {
+        match *self {
+            MediaValue::Dimension(n, ref unit) => match &unit[..] {
+                "dppx" | "x" => Some(n),
+                "dpi" => Some(n / 96.0),
+                "dpcm" => Some(n * 2.54 / 96.0),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+pub fn parse_media_list(tokens: &[Token]) -> MediaList {
+    let tokens = trim_whitespace(tokens);
+
+    if tokens.is_empty() {
+        return MediaList::default();
+    }
+
+    let queries = split_commas(tokens)
+        .into_iter()
+        .map(|query| parse_media_query(trim_whitespace(query)).unwrap_or_else(MediaQuery::not_all))
+        .collect();
+
+    MediaList::new(queries)
+}
+
+fn parse_media_query(tokens: &[Token]) -> Option<MediaQuery> {
+    let words = split_top_level(tokens, |token| *token == Token::Whitespace);
+    let words: Vec<&[Token]> = words.into_iter().filter(|word| !word.is_empty()).collect();
+
+    let first = ident(words.first()?);
+    let starts_with_type = match first {
+        Some(ref name) if name == "not" => words.get(1).is_some_and(|word| ident(word).is_some()),
+        Some(_) => true,
+        None => false,
+    };
+
+    if !starts_with_type {
+        return Some(MediaQuery {
+            qualifier: None,
+            media_type: None,
+            condition: Some(parse_condition(&words, true)?),
+        });
+    }
+
+    let mut index = 0;
+    let qualifier = match &first.unwrap()[..] {
+        "only" => Some(Qualifier::Only),
+        "not" => Some(Qualifier::Not),
+        _ => None,
+    };
+    if qualifier.is_some() {
+        index += 1;
+    }
+
+    let media_type = ident(words.get(index)?)?;
+    match &media_type[..] {
+        "only" | "not" | "and" | "or" | "layer" => return None,
+        _ => {}
+    }
+    index += 1;
+
+    let condition = match words.get(index) {
+        None => None,
+        Some(word) if ident(word).as_ref().is_some_and(|w| w == "and") => {
+            Some(parse_condition(&words[index + 1..], false)?)
+        }
+        Some(_) => return None,
+    };
+
+    Some(MediaQuery {
+        qualifier,
+        media_type: if media_type == "all" {
+            None
+        } else {
+            Some(media_type)
+        },
+        condition,
+    })
+}
+
+fn parse_condition(words: &[&[Token]], allow_or: bool) -> Option<MediaCondition> {
+    let first = words.first()?;
+
+    if ident(first).as_ref().is_some_and(|w| w == "not") {
+        if words.len() != 2 {
+            return None;
+        }
+        return Some(MediaCondition::Not(Box::new(parse_in_parens(words[1])?)));
+    }
+
+    let mut conditions = vec![parse_in_parens(first)?];
+    let mut operator = None;
+
+    for pair in words[1..].chunks(2) {
+        let keyword = ident(pair[0])?;
+        if pair.len() != 2 || (keyword != "and" && keyword != "or") {
+            return None;
+        }
+        if keyword == "or" && !allow_or {
+            return None;
+        }
+        if operator.get_or_insert(keyword.clone()) != &keyword {
+            return None;
+        }
+        conditions.push(parse_in_parens(pair[1])?);
+    }
+
+    Some(match operator {
+        None => conditions.pop().unwrap(),
+        Some(ref op) if op == "and" => MediaCondition::And(conditions),
+        Some(_) => MediaCondition::Or(conditions),
+    })
+}
+
+fn parse_in_parens(tokens: &[Token]) -> Option<MediaCondition> {
+    let source = tokens.iter().map(|token| token.to_string()).collect();
+
+    match tokens.first()? {
+        Token::Function(_) if tokens.last() == Some(&Token::CloseParen) => {
+            return Some(MediaCondition::Unknown(source))
+        }
+        Token::OpenParen if tokens.last() == Some(&Token::CloseParen) => {}
+        _ => return None,
+    }
+
+    let inner = trim_whitespace(&tokens[1..tokens.len() - 1]);
+    let words: Vec<&[Token]> = split_top_level(inner, |token| *token == Token::Whitespace)
+        .into_iter()
+        .filter(|word| !word.is_empty())
+        .collect();
+
+    if let Some(condition) = parse_condition(&words, true) {
+        return Some(condition);
+    }
+
+    match parse_feature(inner) {
+        Some(feature) => Some(MediaCondition::Feature(feature)),
+        None => Some(MediaCondition::Unknown(source)),
+    }
+}
+
+fn parse_feature(tokens: &[Token]) -> Option<MediaFeature> {
+    let tokens: Vec<&Token> = tokens
+        .iter()
+        .filter(|token| **token != Token::Whitespace)
+        .collect();
+
+    match tokens[..] {
+        [Token::Ident(ref name)] => {
+            return Some(MediaFeature {
+                name: name.to_ascii_lowercase(),
+                test: FeatureTest::Boolean,
+            })
+        }
+        [Token::Ident(ref name), Token::Colon, ..] => {
+            let value = parse_value(&tokens[2..])?;
+            let name = name.to_ascii_lowercase();
+
+            let (name, comparison) = if let Some(name) = name.strip_prefix("min-") {
+                (name.to_string(), Comparison::GreaterOrEqual)
+            } else if let Some(name) = name.strip_prefix("max-") {
+                (name.to_string(), Comparison::LessOrEqual)
+            } else {
+                (name, Comparison::Equal)
+            };
+
+            return Some(MediaFeature {
+                name,
+                test: FeatureTest::Compare(comparison, value),
+            });
+        }
+        _ => {}
+    }
+
+    parse_range(&tokens)
+}
+
+// Handles `name op value`, `value op name` and `value op name op value`.
+fn parse_range(tokens: &[&Token]) -> Option<MediaFeature> {
+    let mut operands = Vec::new();
+    let mut operators = Vec::new();
+    let mut start = 0;
+    let mut index = 0;
+
+    while index < tokens.len() {
+        if let Some((comparison, length)) = parse_comparison(&tokens[index..]) {
+            operands.push(&tokens[start..index]);
+            operators.push(comparison);
+            index += length;
+            start = index;
+        } else {
+            index += 1;
+        }
+    }
+    operands.push(&tokens[start..]);
+
+    let name_of = |operand: &[&Token]| match *operand {
+        [Token::Ident(ref name)] => Some(name.to_ascii_lowercase()),
+        _ => None,
+    };
+
+    match (operands.len(), operators.len()) {
+        (2, 1) => {
+            if let Some(name) = name_of(operands[0]) {
+                Some(MediaFeature {
+                    name,
+                    test: FeatureTest::Compare(operators[0], parse_value(operands[1])?),
+                })
+            } else {
+                Some(MediaFeature {
+                    name: name_of(operands[1])?,
+                    test: FeatureTest::Compare(flip(operators[0]), parse_value(operands[0])?),
+                })
+            }
+        }
+        (3, 2) => {
+            let ascending = is_less(operators[0]) && is_less(operators[1]);
+            let descending = !is_less(operators[0])
+                && !is_less(operators[1])
+                && operators[0] != Comparison::Equal
+                && operators[1] != Comparison::Equal;
+            if !ascending && !descending {
+                return None;
+            }
+
+            Some(MediaFeature {
+                name: name_of(operands[1])?,
+                test: FeatureTest::Range(
+                    (flip(operators[0]), parse_value(operands[0])?),
+                    (operators[1], parse_value(operands[2])?),
+                ),
+            })
+        }
+        _ => None,
+    }
+}
+
+fn parse_comparison(tokens: &[&Token]) -> Option<(Comparison, usize)> {
+    let or_equal = tokens
+        .get(1)
+        .is_some_and(|token| **token == Token::Delim('='));
+
+    match *tokens.first()? {
+        Token::Delim('=') => Some((Comparison::Equal, 1)),
+        Token::Delim('<') if or_equal => Some((Comparison::LessOrEqual, 2)),
+        Token::Delim('<') => Some((Comparison::Less, 1)),
+        Token::Delim('>') if or_equal => Some((Comparison::GreaterOrEqual, 2)),
+        Token::Delim('>') => Some((Comparison::Greater, 1)),
+        _ => None,
+    }
+}
+
+fn parse_value(tokens: &[&Token]) -> Option<MediaValue> {
+    match *tokens {
+        [Token::Number(ref n)] => Some(MediaValue::Number(n.value)),
+        [Token::Dimension(ref n, ref unit)] => {
+            Some(MediaValue::Dimension(n.value, unit.to_ascii_lowercase()))
+        }
+        [Token::Ident(ref name)] => Some(MediaValue::Ident(name.to_ascii_lowercase())),
+        [Token::Number(ref a), Token::Delim('/'), Token::Number(ref b)] => {
+            Some(MediaValue::Ratio(a.value, b.value))
+        }
+        _ => None,
+    }
+}
+
+fn flip(comparison: Comparison) -> Comparison {
+    match comparison {
+        Comparison::Equal => Comparison::Equal,
+        Comparison::Less => Comparison::Greater,
+        Comparison::LessOrEqual => Comparison::GreaterOrEqual,
+        Comparison::Greater => Comparison::Less,
+        Comparison::GreaterOrEqual => Comparison::LessOrEqual,
+    }
+}
+
+fn is_less(comparison: Comparison) -> bool {
+    comparison == Comparison::Less || comparison == Comparison::LessOrEqual
+}
+
+fn ident(tokens: &[Token]) -> Option<String> {
+    match *tokens {
+        [Token::Ident(ref name)] => Some(name.to_ascii_lowercase()),
+        _ => None,
+    }
+}
+
+impl fmt::Debug for MediaList {
+    fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
+        for (i, query) in self.queries.iter().enumerate() {
+            if i > 0 {
+                write!(format, ", ")?;
+            }
+            write!(format, "{0:?}", query)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for MediaQuery {
+    fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
+        match self.qualifier {
+            Some(Qualifier::Only) => write!(format, "only ")?,
+            Some(Qualifier::Not) => write!(format, "not ")?,
+            None => {}
+        }
+
+        match (&self.media_type, &self.condition) {
+            (Some(media_type), Some(condition)) => write!(
+                format,
+                "{0} and {1:?}",
+                serialize_identifier(media_type),
+                condition
+            ),
+            (Some(media_type), None) => write!(format, "{0}", serialize_identifier(media_type)),
+            (None, Some(condition)) if self.qualifier.is_some() => {
+                write!(format, "all and {0:?}", condition)
+            }
+            (None, Some(condition)) => write!(format, "{0:?}", condition),
+            (None, None) => write!(format, "all"),
+        }
+    }
+}
+
+impl fmt::Debug for MediaCondition {
+    fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
+        let join = |format: &mut fmt::Formatter, conditions: &[MediaCondition], op: &str| {
+            for (i, condition) in conditions.iter().enumerate() {
+                if i > 0 {
+                    write!(format, " {0} ", op)?;
+                }
+                match *condition {
+                    MediaCondition::Feature(_) | MediaCondition::Unknown(_) => {
+                        write!(format, "{0:?}", condition)?
+                    }
+                    _ => write!(format, "({0:?})", condition)?,
+                }
+            }
+            Ok(())
+        };
+
+        match *self {
+            MediaCondition::Feature(ref feature) => write!(format, "{0:?}", feature),
+            MediaCondition::Not(ref condition) => match **condition {
+                MediaCondition::Feature(_) | MediaCondition::Unknown(_) => {
+                    write!(format, "not {0:?}", condition)
+                }
+                _ => write!(format, "not ({0:?})", condition),
+            },
+            MediaCondition::And(ref conditions) => join(format, conditions, "and"),
+            MediaCondition::Or(ref conditions) => join(format, conditions, "or"),
+            MediaCondition::Unknown(ref source) => write!(format, "{0}", source),
+        }
+    }
+}
+
+impl fmt::Debug for MediaFeature {
+    fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
+        match self.test {
+            FeatureTest::Boolean => write!(format, "({0})", self.name),
+            FeatureTest::Compare(Comparison::Equal, ref value) => {
+                write!(format, "({0}: {1:?})", self.name, value)
+            }
+            FeatureTest::Compare(comparison, ref value) => {
+                write!(format, "({0} {1:?} {2:?})", self.name, comparison, value)
+            }
+            FeatureTest::Range((low, ref low_value), (high, ref high_value)) => write!(
+                format,
+                "({0:?} {1:?} {2} {3:?} {4:?})",
+                low_value,
+                flip(low),
+                self.name,
+                high,
+                high_value
+            ),
+        }
+    }
+}
+
+impl fmt::Debug for Comparison {
+    fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
+        let comparison = match *self {
+            Comparison::Equal => "=",
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+        };
+
+        write!(format, "{}", comparison)
+    }
+}
+
+impl fmt::Debug for MediaValue {
+    fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MediaValue::Number(n) => write!(format, "{0}", n),
+            MediaValue::Dimension(n, ref unit) => write!(format, "{0}{1}", n, unit),
+            MediaValue::Ratio(a, b) => write!(format, "{0} / {1}", a, b),
+            MediaValue::Ident(ref name) => write!(format, "{0}", name),
+        }
+    }
+}
@@ -0,0 +1,580 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::css::Value;
+use crate::css_parser;
+use crate::css_tokenizer::tokenize;
+
+// How a property's value is parsed. Values that do not match are dropped at
+// parse time.
+#[derive(PartialEq, Clone, Copy)]
+pub enum Grammar {
+    Color,
+    // A non-negative length or percentage, or one of the keywords.
+    LengthOr(&'static [&'static str]),
+    // Like LengthOr, but negative lengths are allowed.
+    SignedLengthOr(&'static [&'static str]),
+    // A non-negative length, but not a percentage, or thin, medium or thick.
+    LineWidth,
+    Keywords(&'static [&'static str]),
+    // The longhands of `background` and `font`, which accept what the
+    // shorthand accepts for them. Only the listed keywords are lowercased;
+    // other identifiers, such as font family names, keep their case.
+    Background(&'static [&'static str]),
+    Font(&'static [&'static str]),
+    GeneratedContent,
+}
+
+pub struct Property {
+    pub name: &'static str,
+    pub grammar: Grammar,
+    pub initial: &'static str,
+    pub inherited: bool,
+    pub animatable: bool,
+    pub shorthands: &'static [&'static str],
+}
+
+// Longhands are listed in the order their shorthands expand to.
+static PROPERTIES: [Property; 50] = [
+    Property {
+        name: "display",
+        grammar: Grammar::Keywords(&DISPLAY),
+        initial: "inline",
+        inherited: false,
+        animatable: false,
+        shorthands: &[],
+    },
+    Property {
+        name: "width",
+        grammar: Grammar::LengthOr(&SIZE),
+        initial: "auto",
+        inherited: false,
+        animatable: true,
+        shorthands: &[],
+    },
+    Property {
+        name: "height",
+        grammar: Grammar::LengthOr(&SIZE),
+        initial: "auto",
+        inherited: false,
+        animatable: true,
+        shorthands: &[],
+    },
+    Property {
+        name: "margin-top",
+        grammar: Grammar::SignedLengthOr(&["auto"]),
+        initial: "0",
+        inherited: false,
+        animatable: true,
+        shorthands: &["margin"],
+    },
+    Property {
+        name: "margin-right",
+        grammar: Grammar::SignedLengthOr(&["auto"]),
+        initial: "0",
+        inherited: false,
+        animatable: true,
+        shorthands: &["margin"],
+    },
+    Property {
+        name: "margin-bottom",
+        grammar: Grammar::SignedLengthOr(&["auto"]),
+        initial: "0",
+        inherited: false,
+        animatable: true,
+        shorthands: &["margin"],
+    },
+    Property {
+        name: "margin-left",
+        grammar: Grammar::SignedLengthOr(&["auto"]),
+        initial: "0",
+        inherited: false,
+        animatable: true,
+        shorthands: &["margin"],
+    },
+    Property {
+        name: "padding-top",
+        grammar: Grammar::LengthOr(&[]),
+        initial: "0",
+        inherited: false,
+        animatable: true,
+        shorthands: &["padding"],
+    },
+    Property {
+        name: "padding-right",
+        grammar: Grammar::LengthOr(&[]),
+        initial: "0",
+        inherited: false,
+        animatable: true,
+        shorthands: &["padding"],
+    },
+    Property {
+        name: "padding-bottom",
+        grammar: Grammar::LengthOr(&[]),
+        initial: "0",
+        inherited: false,
+        animatable: true,
+        shorthands: &["padding"],
+    },
+    Property {
+        name: "padding-left",
+        grammar: Grammar::LengthOr(&[]),
+        initial: "0",
+        inherited: false,
+        animatable: true,
+        shorthands: &["padding"],
+    },
+    Property {
+        name: "top",
+        grammar: Grammar::SignedLengthOr(&["auto"]),
+        initial: "auto",
+        inherited: false,
+        animatable: true,
+        shorthands: &["inset"],
+    },
+    Property {
+        name: "right",
+        grammar: Grammar::SignedLengthOr(&["auto"]),
+        initial: "auto",
+        inherited: false,
+        animatable: true,
+        shorthands: &["inset"],
+    },
+    Property {
+        name: "bottom",
+        grammar: Grammar::SignedLengthOr(&["auto"]),
+        initial: "auto",
+        inherited: false,
+        animatable: true,
+        shorthands: &["inset"],
+    },
+    Property {
+        name: "left",
+        grammar: Grammar::SignedLengthOr(&["auto"]),
+        initial: "auto",
+        inherited: false,
+        animatable: true,
+        shorthands: &["inset"],
+    },
+    Property {
+        name: "border-top-width",
+        grammar: Grammar::LineWidth,
+        initial: "medium",
+        inherited: false,
+        animatable: true,
+        shorthands: &["border-width", "border-top", "border"],
+    },
+    Property {
+        name: "border-right-width",
+        grammar: Grammar::LineWidth,
+        initial: "medium",
+        inherited: false,
+        animatable: true,
+        shorthands: &["border-width", "border-right", "border"],
+    },
+    Property {
+        name: "border-bottom-width",
+        grammar: Grammar::LineWidth,
+        initial: "medium",
+        inherited: false,
+        animatable: true,
+        shorthands: &["border-width", "border-bottom", "border"],
+    },
+    Property {
+        name: "border-left-width",
+        grammar: Grammar::LineWidth,
+        initial: "medium",
+        inherited: false,
+        animatable: true,
+        shorthands: &["border-width", "border-left", "border"],
+    },
+    Property {
+        name: "border-top-style",
+        grammar: Grammar::Keywords(&LINE_STYLE),
+        initial: "none",
+        inherited: false,
+        animatable: false,
+        shorthands: &["border-style", "border-top", "border"],
+    },
+    Property {
+        name: "border-right-style",
+        grammar: Grammar::Keywords(&LINE_STYLE),
+        initial: "none",
+        inherited: false,
+        animatable: false,
+        shorthands: &["border-style", "border-right", "border"],
+    },
+    Property {
+        name: "border-bottom-style",
+        grammar: Grammar::Keywords(&LINE_STYLE),
+        initial: "none",
+        inherited: false,
+        animatable: false,
+        shorthands: &["border-style", "border-bottom", "border"],
+    },
+    Property {
+        name: "border-left-style",
+        grammar: Grammar::Keywords(&LINE_STYLE),
+        initial: "none",
+        inherited: false,
+        animatable: false,
+        shorthands: &["border-style", "border-left", "border"],
+    },
+    Property {
+        name: "border-top-color",
+        grammar: Grammar::Color,
+        initial: "currentcolor",
+        inherited: false,
+        animatable: true,
+        shorthands: &["border-color", "border-top", "border"],
+    },
+    Property {
+        name: "border-right-color",
+        grammar: Grammar::Color,
+        initial: "currentcolor",
+        inherited: false,
+        animatable: true,
+        shorthands: &["border-color", "border-right", "border"],
+    },
+    Property {
+        name: "border-bottom-color",
+        grammar: Grammar::Color,
+        initial: "currentcolor",
+        inherited: false,
+        animatable: true,
+        shorthands: &["border-color", "border-bottom", "border"],
+    },
+    Property {
+        name: "border-left-color",
+        grammar: Grammar::Color,
+        initial: "currentcolor",
+        inherited: false,
+        animatable: true,
+        shorthands: &["border-color", "border-left", "border"],
+    },
+    Property {
+        name: "color",
+        grammar: Grammar::Color,
+        initial: "black",
+        inherited: true,
+        animatable: true,
+        shorthands: &[],
+    },
+    Property {
+        name: "background-image",
+        grammar: Grammar::Background(&["none"]),
+        initial: "none",
+        inherited: false,
+        animatable: false,
+        shorthands: &["background"],
+    },
+    Property {
+        name: "background-position",
+        grammar: Grammar::Background(&["left", "center", "right", "top", "bottom"]),
+        initial: "0% 0%",
+        inherited: false,
+        animatable: true,
+        shorthands: &["background"],
+    },
+    Property {
+        name: "background-size",
+        grammar: Grammar::Background(&["cover", "contain", "auto"]),
+        initial: "auto",
+        inherited: false,
+        animatable: true,
+        shorthands: &["background"],
+    },
+    Property {
+        name: "background-repeat",
+        grammar: Grammar::Background(&[
+            "repeat-x",
+            "repeat-y",
+            "repeat",
+            "space",
+            "round",
+            "no-repeat",
+        ]),
+        initial: "repeat",
+        inherited: false,
+        animatable: false,
+        shorthands: &["background"],
+    },
+    Property {
+        name: "background-attachment",
+        grammar: Grammar::Background(&["scroll", "fixed", "local"]),
+        initial: "scroll",
+        inherited: false,
+        animatable: false,
+        shorthands: &["background"],
+    },
+    Property {
+        name: "background-origin",
+        grammar: Grammar::Background(&BOX),
+        initial: "padding-box",
+        inherited: false,
+        animatable: false,
+        shorthands: &["background"],
+    },
+    Property {
+        name: "background-clip",
+        grammar: Grammar::Background(&BOX),
+        initial: "border-box",
+        inherited: false,
+        animatable: false,
+        shorthands: &["background"],
+    },
+    Property {
+        name: "background-color",
+        grammar: Grammar::Color,
+        initial: "transparent",
+        inherited: false,
+        animatable: true,
+        shorthands: &["background"],
+    },
+    Property {
+        name: "font-style",
+        grammar: Grammar::Keywords(&["normal", "italic", "oblique"]),
+        initial: "normal",
+        inherited: true,
+        animatable: true,
+        shorthands: &["font"],
+    },
+    Property {
+        name: "font-variant",
+        grammar: Grammar::Keywords(&["normal", "small-caps"]),
+        initial: "normal",
+        inherited: true,
+        animatable: false,
+        shorthands: &["font"],
+    },
+    Property {
+        name: "font-weight",
+        grammar: Grammar::Font(&["normal", "bold", "bolder", "lighter"]),
+        initial: "normal",
+        inherited: true,
+        animatable: true,
+        shorthands: &["font"],
+    },
+    Property {
+        name: "font-stretch",
+        grammar: Grammar::Keywords(&FONT_STRETCH),
+        initial: "normal",
+        inherited: true,
+        animatable: true,
+        shorthands: &["font"],
+    },
+    Property {
+        name: "font-size",
+        grammar: Grammar::LengthOr(&FONT_SIZE),
+        initial: "medium",
+        inherited: true,
+        animatable: true,
+        shorthands: &["font"],
+    },
+    Property {
+        name: "line-height",
+        grammar: Grammar::Font(&["normal"]),
+        initial: "normal",
+        inherited: true,
+        animatable: true,
+        shorthands: &["font"],
+    },
+    Property {
+        name: "font-family",
+        grammar: Grammar::Font(&GENERIC_FAMILIES),
+        initial: "serif",
+        inherited: true,
+        animatable: false,
+        shorthands: &["font"],
+    },
+    Property {
+        name: "font-size-adjust",
+        grammar: Grammar::Font(&["none"]),
+        initial: "none",
+        inherited: true,
+        animatable: true,
+        shorthands: &["font"],
+    },
+    Property {
+        name: "font-kerning",
+        grammar: Grammar::Keywords(&["auto", "normal", "none"]),
+        initial: "auto",
+        inherited: true,
+        animatable: false,
+        shorthands: &["font"],
+    },
+    Property {
+        name: "content",
+        grammar: Grammar::GeneratedContent,
+        initial: "normal",
+        inherited: false,
+        animatable: false,
+        shorthands: &[],
+    },
+    Property {
+        name: "quotes",
+        grammar: Grammar::GeneratedContent,
+        initial: "auto",
+        inherited: true,
+        animatable: false,
+        shorthands: &[],
+    },
+    Property {
+        name: "counter-reset",
+        grammar: Grammar::GeneratedContent,
+        initial: "none",
+        inherited: false,
+        animatable: false,
+        shorthands: &[],
+    },
+    Property {
+        name: "counter-increment",
+        grammar: Grammar::GeneratedContent,
+        initial: "none",
+        inherited: false,
+        animatable: false,
+        shorthands: &[],
+    },
+    Property {
+        name: "counter-set",
+        grammar: Grammar::GeneratedContent,
+        initial: "none",
+        inherited: false,
+        animatable: false,
+        shorthands: &[],
+    },
+];
+
+const DISPLAY: [&str; 22] = [
+    "block",
+    "inline",
+    "inline-block",
+    "list-item",
+    "none",
+    "contents",
+    "flow-root",
+    "run-in",
+    "flex",
+    "inline-flex",
+    "grid",
+    "inline-grid",
+    "table",
+    "inline-table",
+    "table-row-group",
+    "table-header-group",
+    "table-footer-group",
+    "table-row",
+    "table-cell",
+    "table-column-group",
+    "table-column",
+    "table-caption",
+];
+
+const SIZE: [&str; 4] = ["auto", "min-content", "max-content", "fit-content"];
+
+pub(crate) const LINE_WIDTH: [&str; 3] = ["thin", "medium", "thick"];
+
+pub(crate) const LINE_STYLE: [&str; 10] = [
+    "none", "hidden", "dotted", "dashed", "solid", "double", "groove", "ridge", "inset", "outset",
+];
+
+pub(crate) const FONT_STRETCH: [&str; 9] = [
+    "normal",
+    "ultra-condensed",
+    "extra-condensed",
+    "condensed",
+    "semi-condensed",
+    "semi-expanded",
+    "expanded",
+    "extra-expanded",
+    "ultra-expanded",
+];
+
+pub(crate) const FONT_SIZE: [&str; 10] = [
+    "xx-small",
+    "x-small",
+    "small",
+    "medium",
+    "large",
+    "x-large",
+    "xx-large",
+    "xxx-large",
+    "larger",
+    "smaller",
+];
+
+const BOX: [&str; 3] = ["border-box", "padding-box", "content-box"];
+
+const GENERIC_FAMILIES: [&str; 13] = [
+    "serif",
+    "sans-serif",
+    "monospace",
+    "cursive",
+    "fantasy",
+    "system-ui",
+    "ui-serif",
+    "ui-sans-serif",
+    "ui-monospace",
+    "ui-rounded",
+    "math",
+    "emoji",
+    "fangsong",
+];
+
+pub(crate) const CSS_WIDE_KEYWORDS: [&str; 5] =
+    ["inherit", "initial", "unset", "revert", "revert-layer"];
+
+// PROPERTIES is in shorthand order, so lookups go through an index sorted
+// by name.
+pub fn lookup(name: &str) -> Option<&'static Property> {
+    static BY_NAME: OnceLock<Vec<&'static Property>> = OnceLock::new();
+    let by_name = BY_NAME.get_or_init(|| {
+        let mut by_name: Vec<&'static Property> = PROPERTIES.iter().collect();
+        by_name.sort_by_key(|property| property.name);
+        by_name
+    });
+
+    by_name
+        .binary_search_by_key(&name, |property| property.name)
+        .ok()
+        .map(|index| by_name[index])
+}
+
+pub fn inherited() -> impl Iterator<Item = &'static Property> {
+    PROPERTIES.iter().filter(|property| property.inherited)
+}
+
+// The longhands a shorthand sets, or None for anything but a shorthand.
+pub fn longhands(shorthand: &str) -> Option<Vec<&'static str>> {
+    let longhands: Vec<&'static str> = PROPERTIES
+        .iter()
+        .filter(|property| property.shorthands.contains(&shorthand))
+        .map(|property| property.name)
+        .collect();
+
+    match longhands.len() {
+        0 => None,
+        _ => Some(longhands),
+    }
+}
+
+// Initial values are parsed once, the first time any is needed.
+pub fn initial_value(name: &str) -> Option<&'static Value> {
+    static INITIAL_VALUES: OnceLock<HashMap<&'static str, Value>> = OnceLock::new();
+    let initial_values = INITIAL_VALUES.get_or_init(|| {
+        PROPERTIES
+            .iter()
+            .filter_map(|property| {
+                let value =
+                    css_parser::parse_value(property.name, None, &tokenize(property.initial))?;
+                Some((property.name, value))
+            })
+            .collect()
+    });
+
+    initial_values.get(name)
+}
+
+pub fn is_css_wide_keyword(value: &str) -> bool {
+    CSS_WIDE_KEYWORDS.contains(&&value.to_ascii_lowercase()[..])
+}
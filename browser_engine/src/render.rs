@@ -49,7 +49,7 @@ fn render_commands(command_list: &[DisplayCommand]) -> (Vec<Vertex>, Vec<u16>) {
     for command in command_list {
         match *command {
             DisplayCommand::SolidRectangle(ref color, ref rectangle) => {
-                let color = [color.red, color.green, color.blue];
+                let color = color.channels;
 
                 let mut vertex = render_rectangle(&color, rectangle);
                 vertices.append(&mut vertex);
@@ -68,13 +68,13 @@ fn render_commands(command_list: &[DisplayCommand]) -> (Vec<Vertex>, Vec<u16>) {
             }
         }
     }
-    return (vertices, index_data);
+    (vertices, index_data)
 }
 
 fn render_rectangle(color: &[f32; 3], rectangle: &layout::Rectangle) -> Vec<Vertex> {
     let (x, y, height, width) = transform_rectangle(rectangle);
 
-    let vertices = vec![
+    vec![
         Vertex {
             pos: [x + width, y],
             color: *color,
@@ -91,9 +91,7 @@ fn render_rectangle(color: &[f32; 3], rectangle: &layout::Rectangle) -> Vec<Vert
             pos: [x + width, y + height],
             color: *color,
         },
-    ];
-
-    vertices
+    ]
 }
 
 fn transform_rectangle(rectangle: &layout::Rectangle) -> (f32, f32, f32, f32) {
@@ -0,0 +1,325 @@
+use crate::css::{
+    LocalName, NamespaceSelector, PseudoClass, PseudoElement, RelativeSelector, Selector,
+    SimpleSelector,
+};
+use crate::dom::{ElementData, Node, NodeType};
+use crate::html_parser::xhtml_namespace;
+
+use std::ptr;
+
+// An element together with the chain of ancestors it was reached through,
+// which is all the context combinators need since nodes have no parent links.
+#[derive(Clone, Copy)]
+pub struct ElementRef<'a, 'p> {
+    node: &'a Node,
+    parent: Option<&'p ElementRef<'a, 'p>>,
+    index: usize,
+}
+
+impl<'a, 'p> ElementRef<'a, 'p> {
+    pub fn new(
+        node: &'a Node,
+        parent: Option<&'p ElementRef<'a, 'p>>,
+        index: usize,
+    ) -> ElementRef<'a, 'p> {
+        ElementRef {
+            node,
+            parent,
+            index,
+        }
+    }
+
+    pub fn get_node(&self) -> &'a Node {
+        self.node
+    }
+
+    pub fn get_element(&self) -> Option<&'a ElementData> {
+        match self.node.node_type {
+            NodeType::Element(ref element) => Some(element),
+            _ => None,
+        }
+    }
+
+    pub fn get_parent(&self) -> Option<ElementRef<'a, 'p>> {
+        self.parent.cloned()
+    }
+
+    // Element siblings before this one, nearest first. They are walked in
+    // place, so callers that only need the nearest stop there.
+    pub fn previous_siblings(&self) -> impl Iterator<Item = ElementRef<'a, 'p>> {
+        let parent = self.parent;
+        let siblings: &'a [Node] = match parent {
+            Some(parent) => &parent.node.children[..self.index],
+            None => &[],
+        };
+
+        siblings
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, node)| node.get_element().is_some())
+            .map(move |(index, node)| ElementRef::new(node, parent, index))
+    }
+
+    // Element siblings after this one, nearest first.
+    pub fn next_siblings(&self) -> impl Iterator<Item = ElementRef<'a, 'p>> {
+        let parent = self.parent;
+        let start = self.index + 1;
+        let siblings: &'a [Node] = match parent {
+            Some(parent) => &parent.node.children[start..],
+            None => &[],
+        };
+
+        siblings
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.get_element().is_some())
+            .map(move |(offset, node)| ElementRef::new(node, parent, start + offset))
+    }
+
+    pub fn is(&self, other: &ElementRef) -> bool {
+        ptr::eq(self.node, other.node)
+    }
+}
+
+pub fn matches(element: &ElementRef, selector: &Selector) -> bool {
+    matches_pseudo_element(element, selector, None)
+}
+
+// Matches selectors ending in `pseudo_element` against its originating
+// element; with `None`, only selectors without a pseudo-element match.
+pub fn matches_pseudo_element(
+    element: &ElementRef,
+    selector: &Selector,
+    pseudo_element: Option<PseudoElement>,
+) -> bool {
+    if selector.pseudo_element() != pseudo_element {
+        return false;
+    }
+
+    match selector.simple.len() {
+        0 => false,
+        len => matches_from(element, selector, len - 1, None),
+    }
+}
+
+// Matches the compound at `index` against `element`, then walks left through
+// the combinators, backtracking over ancestors and siblings as needed. With a
+// scope, the leftmost compound must also stand in the given relation to the
+// scope element, which is how :has() anchors its relative selectors.
+fn matches_from(
+    element: &ElementRef,
+    selector: &Selector,
+    index: usize,
+    scope: Option<(&ElementRef, char)>,
+) -> bool {
+    if !compound_matches(element, &selector.simple[index]) {
+        return false;
+    }
+    if index == 0 {
+        return match scope {
+            Some((anchor, combinator)) => {
+                any_related(element, combinator, &mut |related| related.is(anchor))
+            }
+            None => true,
+        };
+    }
+
+    any_related(element, selector.combinators[index - 1], &mut |related| {
+        matches_from(related, selector, index - 1, scope)
+    })
+}
+
+// Tests the elements that `element` can be reached from through `combinator`.
+fn any_related(
+    element: &ElementRef,
+    combinator: char,
+    test: &mut dyn FnMut(&ElementRef) -> bool,
+) -> bool {
+    match combinator {
+        '>' => element.get_parent().is_some_and(|parent| test(&parent)),
+        '+' => element
+            .previous_siblings()
+            .next()
+            .is_some_and(|sibling| test(&sibling)),
+        '~' => element.previous_siblings().any(|sibling| test(&sibling)),
+        _ => {
+            let mut ancestor = element.get_parent();
+            while let Some(current) = ancestor {
+                if test(&current) {
+                    return true;
+                }
+                ancestor = current.get_parent();
+            }
+            false
+        }
+    }
+}
+
+fn any_descendant(element: &ElementRef, test: &mut dyn FnMut(&ElementRef) -> bool) -> bool {
+    for (index, child) in element.get_node().children.iter().enumerate() {
+        if let NodeType::Element(_) = child.node_type {
+            let child = ElementRef::new(child, Some(element), index);
+            if test(&child) || any_descendant(&child, test) {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+fn pseudo_class_matches(element: &ElementRef, pseudo_class: &PseudoClass) -> bool {
+    let matches_any = |element: &ElementRef, selectors: &[Selector]| {
+        selectors.iter().any(|s| matches(element, s))
+    };
+
+    match *pseudo_class {
+        PseudoClass::Root => element.get_parent().is_none(),
+        PseudoClass::Empty => element
+            .get_node()
+            .children
+            .iter()
+            .all(|child| !matches!(child.node_type, NodeType::Element(_) | NodeType::Text(_))),
+        PseudoClass::FirstChild => element.previous_siblings().next().is_none(),
+        PseudoClass::LastChild => element.next_siblings().next().is_none(),
+        PseudoClass::OnlyChild => {
+            element.previous_siblings().next().is_none() && element.next_siblings().next().is_none()
+        }
+        PseudoClass::FirstOfType => count_of_type(element, element.previous_siblings()) == 0,
+        PseudoClass::LastOfType => count_of_type(element, element.next_siblings()) == 0,
+        PseudoClass::OnlyOfType => {
+            count_of_type(element, element.previous_siblings()) == 0
+                && count_of_type(element, element.next_siblings()) == 0
+        }
+        PseudoClass::NthChild(nth, ref selectors)
+        | PseudoClass::NthLastChild(nth, ref selectors) => {
+            if !selectors.is_empty() && !matches_any(element, selectors) {
+                return false;
+            }
+
+            let counted =
+                |sibling: &ElementRef| selectors.is_empty() || matches_any(sibling, selectors);
+            let count = match *pseudo_class {
+                PseudoClass::NthChild(..) => element.previous_siblings().filter(counted).count(),
+                _ => element.next_siblings().filter(counted).count(),
+            };
+
+            nth.matches(count + 1)
+        }
+        PseudoClass::NthOfType(nth) => {
+            nth.matches(count_of_type(element, element.previous_siblings()) + 1)
+        }
+        PseudoClass::NthLastOfType(nth) => {
+            nth.matches(count_of_type(element, element.next_siblings()) + 1)
+        }
+        PseudoClass::Not(ref selectors) => !matches_any(element, selectors),
+        PseudoClass::Is(ref selectors) | PseudoClass::Where(ref selectors) => {
+            matches_any(element, selectors)
+        }
+        PseudoClass::Has(ref selectors) => selectors
+            .iter()
+            .any(|relative| has_matches(element, relative)),
+    }
+}
+
+// A relative selector can only match descendants of the anchor or, after
+// `+` and `~`, its following siblings and their descendants.
+fn has_matches(anchor: &ElementRef, relative: &RelativeSelector) -> bool {
+    let selector = &relative.selector;
+    if selector.simple.is_empty() {
+        return false;
+    }
+
+    let last = selector.simple.len() - 1;
+    let scope = Some((anchor, relative.combinator));
+    let mut test = |candidate: &ElementRef| matches_from(candidate, selector, last, scope);
+
+    match relative.combinator {
+        '+' | '~' => anchor
+            .next_siblings()
+            .any(|sibling| test(&sibling) || any_descendant(&sibling, &mut test)),
+        _ => any_descendant(anchor, &mut test),
+    }
+}
+
+// Siblings of the same type share both the local name and the namespace.
+fn count_of_type<'a: 'p, 'p, I>(element: &ElementRef, siblings: I) -> usize
+where
+    I: Iterator<Item = ElementRef<'a, 'p>>,
+{
+    let same_type = |sibling: &ElementData, element: &ElementData| {
+        sibling.tag_name == element.tag_name && sibling.namespace == element.namespace
+    };
+    let element = match element.get_element() {
+        Some(element) => element,
+        None => return 0,
+    };
+
+    siblings
+        .filter(|sibling| {
+            sibling
+                .get_element()
+                .is_some_and(|sibling| same_type(sibling, element))
+        })
+        .count()
+}
+
+// Type selectors are written as in the document, except that elements in the
+// HTML namespace, whose names the HTML parser lowercases, match whatever the
+// case.
+fn tag_name_matches(tag_name: &LocalName, element: &ElementData) -> bool {
+    let name = if element.namespace == Some(xhtml_namespace()) {
+        tag_name.lower_name
+    } else {
+        tag_name.name
+    };
+
+    name == element.tag_name
+}
+
+fn compound_matches(element_ref: &ElementRef, simple: &SimpleSelector) -> bool {
+    let element = match element_ref.get_element() {
+        Some(element) => element,
+        None => return false,
+    };
+
+    match simple.namespace {
+        Some(NamespaceSelector::None) if element.get_namespace().is_some() => return false,
+        Some(NamespaceSelector::Prefix(_, ref url)) | Some(NamespaceSelector::Default(ref url))
+            if element.get_namespace() != Some(url) =>
+        {
+            return false
+        }
+        _ => {}
+    }
+
+    match simple.tag_name {
+        Some(ref tag_name) if !tag_name_matches(tag_name, element) => return false,
+        _ => {}
+    }
+
+    match simple.id {
+        Some(ref id) if element.get_id() != Some(id) => return false,
+        _ => {}
+    }
+
+    let classes = element.get_classes();
+    if !simple.classes.iter().all(|class| classes.contains(class)) {
+        return false;
+    }
+
+    let attributes_match =
+        simple
+            .attributes
+            .iter()
+            .all(|attribute| match element.get_attribute(&attribute.name) {
+                Some(value) => attribute.matches(value),
+                None => false,
+            });
+
+    attributes_match
+        && simple
+            .pseudo_classes
+            .iter()
+            .all(|pseudo_class| pseudo_class_matches(element_ref, pseudo_class))
+}
@@ -0,0 +1,465 @@
+use crate::css::{
+    Calc, Color, ColorSpace, CssRule, Declarations, ImportRule, MediaRule, NamespaceRule, Rule,
+    Selector, Separator, StyleSheet, Value,
+};
+use crate::css_tokenizer::{serialize_identifier, serialize_string};
+use crate::generated::{Content, ContentItem, Quotes};
+
+// Pretty output puts every rule and declaration on a line of its own and
+// indents nested rules; minified output leaves out all whitespace the parser
+// does not need.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Mode {
+    Pretty,
+    Minify,
+}
+
+pub fn serialize_stylesheet(stylesheet: &StyleSheet, mode: Mode) -> String {
+    serialize_rules(&stylesheet.rules, mode, 0)
+}
+
+pub fn serialize_rule(rule: &CssRule, mode: Mode) -> String {
+    match *rule {
+        CssRule::Style(ref rule) => serialize_style_rule(rule, mode),
+        CssRule::Media(ref rule) => serialize_media_rule(rule, mode),
+        CssRule::Import(ref rule) => serialize_import_rule(rule),
+        CssRule::Namespace(ref rule) => serialize_namespace_rule(rule),
+    }
+}
+
+pub fn serialize_style_rule(rule: &Rule, mode: Mode) -> String {
+    let selectors = serialize_selectors(&rule.selectors, mode);
+    let declarations = serialize_each(&rule.declarations, mode);
+
+    match mode {
+        _ if declarations.is_empty() => format!("{0}{1}{{}}", selectors, space(mode)),
+        Mode::Pretty => {
+            let mut rule = format!("{0} {{\n", selectors);
+            for declaration in declarations {
+                rule.push_str(&format!("    {0};\n", declaration));
+            }
+            rule.push('}');
+            rule
+        }
+        Mode::Minify => format!("{0}{{{1}}}", selectors, declarations.join(";")),
+    }
+}
+
+pub fn serialize_media_rule(rule: &MediaRule, mode: Mode) -> String {
+    let prelude = if rule.media.queries.is_empty() {
+        String::from("@media")
+    } else {
+        format!("@media {0:?}", rule.media)
+    };
+
+    match mode {
+        _ if rule.rules.is_empty() => format!("{0}{1}{{}}", prelude, space(mode)),
+        Mode::Pretty => format!(
+            "{0} {{\n{1}\n}}",
+            prelude,
+            serialize_rules(&rule.rules, mode, 1)
+        ),
+        Mode::Minify => format!("{0}{{{1}}}", prelude, serialize_rules(&rule.rules, mode, 1)),
+    }
+}
+
+pub fn serialize_import_rule(rule: &ImportRule) -> String {
+    let mut import = format!("@import url({0})", serialize_string(&rule.url));
+
+    match rule.layer {
+        Some(ref layer) if layer.is_empty() => import.push_str(" layer"),
+        Some(ref layer) => {
+            let names: Vec<String> = layer.split('.').map(serialize_identifier).collect();
+            import.push_str(&format!(" layer({0})", names.join(".")));
+        }
+        None => {}
+    }
+
+    if !rule.media.queries.is_empty() {
+        import.push_str(&format!(" {0:?}", rule.media));
+    }
+
+    import.push(';');
+    import
+}
+
+pub fn serialize_namespace_rule(rule: &NamespaceRule) -> String {
+    match rule.prefix {
+        Some(ref prefix) => format!(
+            "@namespace {0} url({1});",
+            serialize_identifier(prefix),
+            serialize_string(&rule.url)
+        ),
+        None => format!("@namespace url({0});", serialize_string(&rule.url)),
+    }
+}
+
+// Combinators other than the descendant one need no whitespace around them.
+pub fn serialize_selector(selector: &Selector, mode: Mode) -> String {
+    let mut serialized = String::new();
+
+    for (index, simple) in selector.simple.iter().enumerate() {
+        if index > 0 {
+            match (selector.combinators[index - 1], mode) {
+                (' ', _) => serialized.push(' '),
+                (combinator, Mode::Pretty) => serialized.push_str(&format!(" {0} ", combinator)),
+                (combinator, Mode::Minify) => serialized.push(combinator),
+            }
+        }
+        serialized.push_str(&format!("{0:?}", simple));
+    }
+
+    serialized
+}
+
+// The contents of a style attribute or a rule's braces, on one line.
+pub fn serialize_declaration_block(declarations: &[Declarations], mode: Mode) -> String {
+    let declarations = serialize_each(declarations, mode);
+
+    match mode {
+        Mode::Pretty => declarations
+            .iter()
+            .map(|declaration| format!("{0};", declaration))
+            .collect::<Vec<_>>()
+            .join(" "),
+        Mode::Minify => declarations.join(";"),
+    }
+}
+
+pub fn serialize_declaration(declaration: &Declarations, mode: Mode) -> String {
+    let property = serialize_identifier(&declaration.property);
+    serialize_property(&property, &declaration.value, declaration.important, mode)
+}
+
+pub fn serialize_value(value: &Value, mode: Mode) -> String {
+    match *value {
+        Value::Keyword(ref keyword) => serialize_identifier(keyword),
+        Value::Number(number) => serialize_number(number, mode),
+        Value::Percentage(percentage) => format!("{0}%", serialize_number(percentage, mode)),
+        Value::Length(length, unit) => format!("{0}{1:?}", serialize_number(length, mode), unit),
+        Value::Color(ref color) => serialize_color(color, mode),
+        Value::CurrentColor => String::from("currentcolor"),
+        Value::String(ref string) => serialize_string(string),
+        Value::Url(ref url) => format!("url({0})", serialize_string(url)),
+        Value::List(ref values, separator) => {
+            let separator = match (separator, mode) {
+                (Separator::Space, _) => " ",
+                (Separator::Comma, Mode::Pretty) => ", ",
+                (Separator::Comma, Mode::Minify) => ",",
+                (Separator::Slash, Mode::Pretty) => " / ",
+                (Separator::Slash, Mode::Minify) => "/",
+            };
+            values
+                .iter()
+                .map(|value| serialize_value(value, mode))
+                .collect::<Vec<_>>()
+                .join(separator)
+        }
+        Value::Function(ref name, ref arguments) => {
+            let arguments: Vec<String> = arguments
+                .iter()
+                .map(|argument| serialize_value(argument, mode))
+                .collect();
+            format!(
+                "{0}({1})",
+                serialize_identifier(name),
+                arguments.join(comma(mode))
+            )
+        }
+        Value::Tokens(ref tokens) | Value::Unresolved(_, ref tokens) => {
+            tokens.iter().map(|token| token.to_string()).collect()
+        }
+        Value::Calc(ref calc @ Calc::Min(_))
+        | Value::Calc(ref calc @ Calc::Max(_))
+        | Value::Calc(ref calc @ Calc::Clamp(..)) => serialize_calc(calc, mode),
+        Value::Calc(ref calc) => format!("calc({0})", serialize_calc(calc, mode)),
+        Value::Content(ref content) => serialize_content(content, mode),
+        Value::Quotes(Quotes::Auto) => String::from("auto"),
+        Value::Quotes(Quotes::Pairs(ref pairs)) if pairs.is_empty() => String::from("none"),
+        Value::Quotes(Quotes::Pairs(ref pairs)) => pairs
+            .iter()
+            .map(|(open, close)| {
+                format!("{0} {1}", serialize_string(open), serialize_string(close))
+            })
+            .collect::<Vec<_>>()
+            .join(" "),
+        Value::CounterChanges(ref changes) if changes.is_empty() => String::from("none"),
+        Value::CounterChanges(ref changes) => changes
+            .iter()
+            .map(|(name, value)| format!("{0} {1}", serialize_identifier(name), value))
+            .collect::<Vec<_>>()
+            .join(" "),
+    }
+}
+
+// Counter styles are left out when they are the default, `decimal`.
+fn serialize_content(content: &Content, mode: Mode) -> String {
+    let items = match *content {
+        Content::Normal => return String::from("normal"),
+        Content::None => return String::from("none"),
+        Content::Items(ref items) => items,
+    };
+    let style = |style: &str| match style {
+        "decimal" => String::new(),
+        _ => format!("{0}{1}", comma(mode), serialize_identifier(style)),
+    };
+
+    items
+        .iter()
+        .map(|item| match *item {
+            ContentItem::String(ref string) => serialize_string(string),
+            ContentItem::Attr(ref name) => format!("attr({0})", serialize_identifier(name)),
+            ContentItem::Counter(ref name, ref counter_style) => format!(
+                "counter({0}{1})",
+                serialize_identifier(name),
+                style(counter_style)
+            ),
+            ContentItem::Counters(ref name, ref separator, ref counter_style) => format!(
+                "counters({0}{1}{2}{3})",
+                serialize_identifier(name),
+                comma(mode),
+                serialize_string(separator),
+                style(counter_style)
+            ),
+            ContentItem::OpenQuote => String::from("open-quote"),
+            ContentItem::CloseQuote => String::from("close-quote"),
+            ContentItem::NoOpenQuote => String::from("no-open-quote"),
+            ContentItem::NoCloseQuote => String::from("no-close-quote"),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// The expression inside a math function. `+` and `-` keep their whitespace
+// because the parser requires it.
+pub fn serialize_calc(calc: &Calc, mode: Mode) -> String {
+    let list = |name: &str, calcs: &[&Calc]| {
+        let calcs: Vec<String> = calcs
+            .iter()
+            .map(|calc| serialize_calc(calc, mode))
+            .collect();
+        format!("{0}({1})", name, calcs.join(comma(mode)))
+    };
+
+    match *calc {
+        Calc::Number(number) => serialize_number(number, mode),
+        Calc::Percentage(percentage) => format!("{0}%", serialize_number(percentage, mode)),
+        Calc::Length(length, unit) => format!("{0}{1:?}", serialize_number(length, mode), unit),
+        Calc::Operation(operator, ref left, ref right) => {
+            // The right operand of `-` and `/` needs parentheses even at the
+            // same precedence.
+            let precedence = calc.precedence();
+            let right_precedence = match operator {
+                '-' | '/' => precedence + 1,
+                _ => precedence,
+            };
+            let operand = |operand: &Calc, precedence: u8| {
+                if operand.precedence() < precedence {
+                    format!("({0})", serialize_calc(operand, mode))
+                } else {
+                    serialize_calc(operand, mode)
+                }
+            };
+            let operator = match (operator, mode) {
+                ('*', Mode::Minify) | ('/', Mode::Minify) => operator.to_string(),
+                _ => format!(" {0} ", operator),
+            };
+
+            format!(
+                "{0}{1}{2}",
+                operand(left, precedence),
+                operator,
+                operand(right, right_precedence)
+            )
+        }
+        Calc::Min(ref calcs) => list("min", &calcs.iter().collect::<Vec<_>>()),
+        Calc::Max(ref calcs) => list("max", &calcs.iter().collect::<Vec<_>>()),
+        Calc::Clamp(ref min, ref value, ref max) => list("clamp", &[min, value, max]),
+    }
+}
+
+// sRGB colors use the legacy rgb() and rgba() forms, or the shortest hex
+// form that keeps them exact when minifying; other colors keep their space.
+pub fn serialize_color(color: &Color, mode: Mode) -> String {
+    let [c0, c1, c2] = color.channels;
+
+    let function = match color.space {
+        ColorSpace::Srgb => return serialize_srgb(color, mode),
+        ColorSpace::SrgbLinear => "color(srgb-linear ",
+        ColorSpace::DisplayP3 => "color(display-p3 ",
+        ColorSpace::Lab => "lab(",
+        ColorSpace::Lch => "lch(",
+        ColorSpace::Oklab => "oklab(",
+        ColorSpace::Oklch => "oklch(",
+    };
+
+    let channels: Vec<String> = [c0, c1, c2]
+        .iter()
+        .map(|&channel| serialize_number(channel, mode))
+        .collect();
+    let alpha = match (color.alpha, mode) {
+        (1.0, _) => String::new(),
+        (alpha, Mode::Pretty) => format!(" / {0}", serialize_number(alpha, mode)),
+        (alpha, Mode::Minify) => format!("/{0}", serialize_number(alpha, mode)),
+    };
+
+    format!("{0}{1}{2})", function, channels.join(" "), alpha)
+}
+
+fn serialize_srgb(color: &Color, mode: Mode) -> String {
+    let [red, green, blue] = color.channels;
+
+    if mode == Mode::Minify {
+        let bytes = [red, green, blue, color.alpha]
+            .iter()
+            .map(|&channel| to_byte(channel))
+            .collect::<Option<Vec<u8>>>();
+
+        if let Some(mut bytes) = bytes {
+            if bytes[3] == 255 {
+                bytes.pop();
+            }
+            // #aabbcc shortens to #abc.
+            let short = bytes.iter().all(|byte| byte >> 4 == byte & 0xf);
+            let hex: String = bytes
+                .iter()
+                .map(|byte| {
+                    if short {
+                        format!("{0:x}", byte & 0xf)
+                    } else {
+                        format!("{0:02x}", byte)
+                    }
+                })
+                .collect();
+            return format!("#{0}", hex);
+        }
+    }
+
+    let channels: Vec<String> = [red, green, blue]
+        .iter()
+        .map(|&channel| match to_byte(channel) {
+            Some(byte) => byte.to_string(),
+            None => serialize_number(channel * 255.0, mode),
+        })
+        .collect();
+
+    match color.alpha {
+        1.0 => format!("rgb({0})", channels.join(comma(mode))),
+        alpha => format!(
+            "rgba({0}{1}{2})",
+            channels.join(comma(mode)),
+            comma(mode),
+            serialize_number(alpha, mode)
+        ),
+    }
+}
+
+// Minified numbers drop the leading zero of a fraction.
+pub fn serialize_number(number: f32, mode: Mode) -> String {
+    let number = number.to_string();
+
+    match mode {
+        Mode::Minify if number.starts_with("0.") => number[1..].to_string(),
+        Mode::Minify if number.starts_with("-0.") => format!("-{0}", &number[2..]),
+        _ => number,
+    }
+}
+
+fn serialize_rules(rules: &[CssRule], mode: Mode, depth: usize) -> String {
+    let rules = rules.iter().map(|rule| serialize_rule(rule, mode));
+
+    match mode {
+        Mode::Pretty => {
+            let indent = "    ".repeat(depth);
+            rules
+                .map(|rule| {
+                    rule.lines()
+                        .map(|line| {
+                            if line.is_empty() {
+                                String::new()
+                            } else {
+                                format!("{0}{1}", indent, line)
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                })
+                .collect::<Vec<_>>()
+                .join("\n\n")
+        }
+        Mode::Minify => rules.collect(),
+    }
+}
+
+fn serialize_selectors(selectors: &[Selector], mode: Mode) -> String {
+    selectors
+        .iter()
+        .map(|selector| serialize_selector(selector, mode))
+        .collect::<Vec<_>>()
+        .join(comma(mode))
+}
+
+// Longhands whose shorthand held a var() reference are written back as that
+// shorthand, once, since none of them has a value of its own yet.
+fn serialize_each(declarations: &[Declarations], mode: Mode) -> Vec<String> {
+    let mut serialized = Vec::new();
+    let mut shorthands: Vec<(&str, &Value)> = Vec::new();
+
+    for declaration in declarations {
+        match declaration.value {
+            Value::Unresolved(Some(ref shorthand), _) => {
+                let written = shorthands
+                    .iter()
+                    .any(|&(name, value)| name == shorthand && *value == declaration.value);
+                if written {
+                    continue;
+                }
+                shorthands.push((shorthand, &declaration.value));
+                serialized.push(serialize_property(
+                    shorthand,
+                    &declaration.value,
+                    declaration.important,
+                    mode,
+                ));
+            }
+            _ => serialized.push(serialize_declaration(declaration, mode)),
+        }
+    }
+
+    serialized
+}
+
+fn serialize_property(property: &str, value: &Value, important: bool, mode: Mode) -> String {
+    let value = serialize_value(value, mode);
+
+    match (important, mode) {
+        (false, Mode::Pretty) => format!("{0}: {1}", property, value),
+        (false, Mode::Minify) => format!("{0}:{1}", property, value),
+        (true, Mode::Pretty) => format!("{0}: {1} !important", property, value),
+        (true, Mode::Minify) => format!("{0}:{1}!important", property, value),
+    }
+}
+
+fn to_byte(channel: f32) -> Option<u8> {
+    let scaled = channel * 255.0;
+
+    if (0.0..=255.0).contains(&scaled) && (scaled - scaled.round()).abs() < 0.001 {
+        Some(scaled.round() as u8)
+    } else {
+        None
+    }
+}
+
+fn space(mode: Mode) -> &'static str {
+    match mode {
+        Mode::Pretty => " ",
+        Mode::Minify => "",
+    }
+}
+
+fn comma(mode: Mode) -> &'static str {
+    match mode {
+        Mode::Pretty => ", ",
+        Mode::Minify => ",",
+    }
+}
@@ -0,0 +1,610 @@
+use crate::color::parse_color;
+use crate::css_parser::{split_commas, TokenList};
+use crate::css_tokenizer::{Numeric, Token};
+use crate::properties;
+use crate::source::Span;
+
+use std::borrow::Cow;
+use std::ops::Range;
+
+// Each longhand gets the tokens of its part of the shorthand's value, so
+// they are translated without being serialized and tokenized again.
+pub type Longhands<'a> = Vec<(&'static str, Vec<(Token<'a>, Span)>)>;
+
+// Returns None when the value does not match the shorthand's grammar, in
+// which case the whole declaration is dropped.
+pub fn expand<'a>(property: &str, tokens: &TokenList<'a>) -> Option<Longhands<'a>> {
+    let names = &properties::longhands(property)?;
+
+    match *tokens {
+        [(Token::Ident(ref keyword), _)] if properties::is_css_wide_keyword(keyword) => {
+            return Some(names.iter().map(|name| (*name, tokens.to_vec())).collect());
+        }
+        _ => {}
+    }
+
+    let parts = components(tokens);
+    let parts: Vec<&TokenList> = parts.iter().map(|range| &tokens[range.clone()]).collect();
+
+    match property {
+        "margin" | "inset" => expand_box(names, &parts, |part| {
+            is_length_percentage(part) || is_keyword(part, &["auto"])
+        }),
+        "padding" => expand_box(names, &parts, is_non_negative_length_percentage),
+        "border-width" => expand_box(names, &parts, is_line_width),
+        "border-style" => expand_box(names, &parts, is_line_style),
+        "border-color" => expand_box(names, &parts, is_color),
+        "border" => {
+            let side = expand_border_side(&parts)?;
+            Some(
+                BORDER_SIDES
+                    .iter()
+                    .flat_map(|(_, longhands)| longhands.iter().cloned().zip(side.clone()))
+                    .collect(),
+            )
+        }
+        "border-top" | "border-right" | "border-bottom" | "border-left" => {
+            let side = expand_border_side(&parts)?;
+            let (_, longhands) = BORDER_SIDES.iter().find(|(name, _)| *name == property)?;
+            Some(longhands.iter().cloned().zip(side).collect())
+        }
+        "background" => expand_background(names, tokens),
+        "font" => expand_font(names, tokens),
+        _ => None,
+    }
+}
+
+// Whether a value is valid for a longhand of `background` or `font` on its
+// own. Background longhands take one value per comma-separated layer.
+pub fn is_valid(property: &str, tokens: &TokenList) -> bool {
+    match property {
+        "font-weight" => is_keyword(tokens, &["normal"]) || is_font_weight(tokens),
+        "line-height" => is_line_height(tokens),
+        "font-family" => is_font_family(tokens),
+        "font-size-adjust" => is_keyword(tokens, &["none"]) || is_non_negative_number(tokens),
+        _ => split_commas(tokens).into_iter().all(|layer| {
+            let ranges = components(layer);
+            let parts: Vec<&TokenList> = ranges.iter().map(|range| &layer[range.clone()]).collect();
+            is_valid_layer(property, &parts)
+        }),
+    }
+}
+
+fn is_valid_layer(property: &str, parts: &[&TokenList]) -> bool {
+    match (property, parts) {
+        ("background-image", [part]) => is_image(part),
+        ("background-position", _) => {
+            !parts.is_empty() && parts.len() <= 4 && parts.iter().all(|p| is_position_part(p))
+        }
+        ("background-size", [part]) if is_keyword(part, &["cover", "contain"]) => true,
+        ("background-size", _) => {
+            !parts.is_empty()
+                && parts.len() <= 2
+                && parts.iter().all(|p| is_background_size_part(p))
+        }
+        ("background-repeat", [part]) => {
+            is_keyword(part, &["repeat-x", "repeat-y"]) || is_repeat_keyword(part)
+        }
+        ("background-repeat", [first, second]) => {
+            is_repeat_keyword(first) && is_repeat_keyword(second)
+        }
+        ("background-attachment", [part]) => is_keyword(part, &ATTACHMENT),
+        ("background-origin", [part]) | ("background-clip", [part]) => is_keyword(part, &BOX),
+        _ => false,
+    }
+}
+
+fn expand_box<'a>(
+    names: &[&'static str],
+    parts: &[&TokenList<'a>],
+    is_valid: fn(&TokenList) -> bool,
+) -> Option<Longhands<'a>> {
+    if parts.is_empty() || parts.len() > 4 || !parts.iter().all(|part| is_valid(part)) {
+        return None;
+    }
+
+    let sides = match parts.len() {
+        1 => [0, 0, 0, 0],
+        2 => [0, 1, 0, 1],
+        3 => [0, 1, 2, 1],
+        _ => [0, 1, 2, 3],
+    };
+
+    Some(
+        names
+            .iter()
+            .zip(sides.iter())
+            .map(|(name, &side)| (*name, parts[side].to_vec()))
+            .collect(),
+    )
+}
+
+// `<line-width> || <line-style> || <color>`, returned as width, style and
+// color with omitted parts reset to their initial values.
+fn expand_border_side<'a>(parts: &[&TokenList<'a>]) -> Option<Vec<Vec<(Token<'a>, Span)>>> {
+    let mut width = None;
+    let mut style = None;
+    let mut color = None;
+
+    if parts.is_empty() {
+        return None;
+    }
+
+    for part in parts {
+        let slot = if is_line_width(part) {
+            &mut width
+        } else if is_line_style(part) {
+            &mut style
+        } else if is_color(part) {
+            &mut color
+        } else {
+            return None;
+        };
+
+        if slot.is_some() {
+            return None;
+        }
+        *slot = Some(part.to_vec());
+    }
+
+    Some(vec![
+        width.unwrap_or_else(|| keyword("medium")),
+        style.unwrap_or_else(|| keyword("none")),
+        color.unwrap_or_else(|| keyword("currentcolor")),
+    ])
+}
+
+// Each comma-separated layer sets one entry of every list-valued longhand;
+// only the final layer may carry a color.
+fn expand_background<'a>(names: &[&'static str], tokens: &TokenList<'a>) -> Option<Longhands<'a>> {
+    let layers = split_commas(tokens);
+    let mut values: Vec<Vec<(Token<'a>, Span)>> = vec![Vec::new(); names.len()];
+
+    for (i, layer) in layers.iter().enumerate() {
+        let layer = parse_background_layer(layer, i == layers.len() - 1)?;
+        for (j, value) in layer.into_iter().enumerate() {
+            if !values[j].is_empty() {
+                values[j].push((Token::Comma, Span::default()));
+                values[j].push((Token::Whitespace, Span::default()));
+            }
+            values[j].extend(value);
+        }
+    }
+
+    Some(names.iter().cloned().zip(values).collect())
+}
+
+fn parse_background_layer<'a>(
+    tokens: &TokenList<'a>,
+    is_final: bool,
+) -> Option<Vec<Vec<(Token<'a>, Span)>>> {
+    let ranges = components(tokens);
+    let parts: Vec<&TokenList> = ranges.iter().map(|range| &tokens[range.clone()]).collect();
+
+    let mut image = None;
+    let mut position = None;
+    let mut size = None;
+    let mut repeat = None;
+    let mut attachment = None;
+    let mut boxes = Vec::new();
+    let mut color = None;
+
+    if parts.is_empty() {
+        return None;
+    }
+
+    let mut i = 0;
+    while i < parts.len() {
+        let part = parts[i];
+
+        if image.is_none() && is_image(part) {
+            image = Some(part.to_vec());
+            i += 1;
+        } else if repeat.is_none() && is_keyword(part, &["repeat-x", "repeat-y"]) {
+            repeat = Some(part.to_vec());
+            i += 1;
+        } else if repeat.is_none() && is_repeat_keyword(part) {
+            let count = if parts.get(i + 1).is_some_and(|p| is_repeat_keyword(p)) {
+                2
+            } else {
+                1
+            };
+            repeat = Some(join(tokens, &ranges[i..i + count]));
+            i += count;
+        } else if attachment.is_none() && is_keyword(part, &ATTACHMENT) {
+            attachment = Some(part.to_vec());
+            i += 1;
+        } else if boxes.len() < 2 && is_keyword(part, &BOX) {
+            boxes.push(part.to_vec());
+            i += 1;
+        } else if position.is_none() && is_position_part(part) {
+            let count = parts[i..]
+                .iter()
+                .take(4)
+                .take_while(|p| is_position_part(p))
+                .count();
+            position = Some(join(tokens, &ranges[i..i + count]));
+            i += count;
+
+            if parts.get(i).is_some_and(|p| is_delim(p, '/')) {
+                i += 1;
+                let count = if parts
+                    .get(i)
+                    .is_some_and(|p| is_keyword(p, &["cover", "contain"]))
+                {
+                    1
+                } else {
+                    parts[i..]
+                        .iter()
+                        .take(2)
+                        .take_while(|p| is_background_size_part(p))
+                        .count()
+                };
+                if count == 0 {
+                    return None;
+                }
+                size = Some(join(tokens, &ranges[i..i + count]));
+                i += count;
+            }
+        } else if is_final && color.is_none() && is_color(part) {
+            color = Some(part.to_vec());
+            i += 1;
+        } else {
+            return None;
+        }
+    }
+
+    let origin = boxes
+        .first()
+        .cloned()
+        .unwrap_or_else(|| keyword("padding-box"));
+    let clip = boxes
+        .get(1)
+        .or_else(|| boxes.first())
+        .cloned()
+        .unwrap_or_else(|| keyword("border-box"));
+
+    let mut layer = vec![
+        image.unwrap_or_else(|| keyword("none")),
+        position.unwrap_or_else(initial_position),
+        size.unwrap_or_else(|| keyword("auto")),
+        repeat.unwrap_or_else(|| keyword("repeat")),
+        attachment.unwrap_or_else(|| keyword("scroll")),
+        origin,
+        clip,
+    ];
+    if is_final {
+        layer.push(color.unwrap_or_else(|| keyword("transparent")));
+    }
+
+    Some(layer)
+}
+
+// `[ <font-style> || <font-variant> || <font-weight> || <font-stretch> ]?
+// <font-size> [ / <line-height> ]? <font-family>`
+fn expand_font<'a>(names: &[&'static str], tokens: &TokenList<'a>) -> Option<Longhands<'a>> {
+    let ranges = components(tokens);
+    let parts: Vec<&TokenList> = ranges.iter().map(|range| &tokens[range.clone()]).collect();
+
+    let mut style = None;
+    let mut variant = None;
+    let mut weight = None;
+    let mut stretch = None;
+
+    let mut i = 0;
+    while i < parts.len() && i < 4 {
+        let part = parts[i];
+
+        let slot = if is_keyword(part, &["normal"]) {
+            i += 1;
+            continue;
+        } else if is_keyword(part, &["italic", "oblique"]) {
+            &mut style
+        } else if is_keyword(part, &["small-caps"]) {
+            &mut variant
+        } else if is_font_weight(part) {
+            &mut weight
+        } else if is_keyword(part, &properties::FONT_STRETCH) {
+            &mut stretch
+        } else {
+            break;
+        };
+
+        if slot.is_some() {
+            return None;
+        }
+        *slot = Some(part.to_vec());
+        i += 1;
+    }
+
+    let size = parts.get(i)?;
+    if !is_non_negative_length_percentage(size) && !is_keyword(size, &properties::FONT_SIZE) {
+        return None;
+    }
+    let size = size.to_vec();
+    i += 1;
+
+    let mut line_height = None;
+    if parts.get(i).is_some_and(|p| is_delim(p, '/')) {
+        let part = parts.get(i + 1)?;
+        if !is_line_height(part) {
+            return None;
+        }
+        line_height = Some(part.to_vec());
+        i += 2;
+    }
+
+    let family_start = ranges.get(i)?.start;
+    let family = &tokens[family_start..];
+    if !is_font_family(family) {
+        return None;
+    }
+
+    let normal = || keyword("normal");
+    let values = vec![
+        style.unwrap_or_else(normal),
+        variant.unwrap_or_else(normal),
+        weight.unwrap_or_else(normal),
+        stretch.unwrap_or_else(normal),
+        size,
+        line_height.unwrap_or_else(normal),
+        family.to_vec(),
+        keyword("none"),
+        keyword("auto"),
+    ];
+
+    Some(names.iter().cloned().zip(values).collect())
+}
+
+const BORDER_SIDES: [(&str, [&str; 3]); 4] = [
+    (
+        "border-top",
+        ["border-top-width", "border-top-style", "border-top-color"],
+    ),
+    (
+        "border-right",
+        [
+            "border-right-width",
+            "border-right-style",
+            "border-right-color",
+        ],
+    ),
+    (
+        "border-bottom",
+        [
+            "border-bottom-width",
+            "border-bottom-style",
+            "border-bottom-color",
+        ],
+    ),
+    (
+        "border-left",
+        [
+            "border-left-width",
+            "border-left-style",
+            "border-left-color",
+        ],
+    ),
+];
+
+const ATTACHMENT: [&str; 3] = ["scroll", "fixed", "local"];
+
+const BOX: [&str; 3] = ["border-box", "padding-box", "content-box"];
+
+const LENGTH_UNITS: [&str; 15] = [
+    "em", "ex", "ch", "rem", "vh", "vw", "vmin", "vmax", "px", "mm", "q", "cm", "in", "pt", "pc",
+];
+
+const MATH_FUNCTIONS: [&str; 4] = ["calc", "min", "max", "clamp"];
+
+const IMAGE_FUNCTIONS: [&str; 11] = [
+    "url",
+    "image",
+    "image-set",
+    "cross-fade",
+    "element",
+    "linear-gradient",
+    "radial-gradient",
+    "conic-gradient",
+    "repeating-linear-gradient",
+    "repeating-radial-gradient",
+    "repeating-conic-gradient",
+];
+
+fn is_length_percentage(part: &TokenList) -> bool {
+    match *part {
+        [(Token::Dimension(_, ref unit), _)] => {
+            LENGTH_UNITS.contains(&&unit.to_ascii_lowercase()[..])
+        }
+        [(Token::Percentage(_), _)] => true,
+        [(Token::Number(ref n), _)] => n.value == 0.0,
+        _ => is_function(part, &MATH_FUNCTIONS),
+    }
+}
+
+// Math functions are accepted whatever their sign, since it is only known
+// once they are resolved.
+fn is_non_negative_length_percentage(part: &TokenList) -> bool {
+    is_length_percentage(part) && !is_negative(part)
+}
+
+fn is_non_negative_number(part: &TokenList) -> bool {
+    match *part {
+        [(Token::Number(ref n), _)] => n.value >= 0.0,
+        _ => false,
+    }
+}
+
+fn is_negative(part: &TokenList) -> bool {
+    match *part {
+        [(Token::Dimension(ref n, _), _)]
+        | [(Token::Percentage(ref n), _)]
+        | [(Token::Number(ref n), _)] => n.value < 0.0,
+        _ => false,
+    }
+}
+
+fn is_line_width(part: &TokenList) -> bool {
+    match *part {
+        [(Token::Percentage(_), _)] => false,
+        _ => is_non_negative_length_percentage(part) || is_keyword(part, &properties::LINE_WIDTH),
+    }
+}
+
+fn is_line_style(part: &TokenList) -> bool {
+    is_keyword(part, &properties::LINE_STYLE)
+}
+
+fn is_color(part: &TokenList) -> bool {
+    parse_color(part).is_some()
+}
+
+fn is_image(part: &TokenList) -> bool {
+    match *part {
+        [(Token::Url(_), _)] => true,
+        _ => is_keyword(part, &["none"]) || is_function(part, &IMAGE_FUNCTIONS),
+    }
+}
+
+fn is_background_size_part(part: &TokenList) -> bool {
+    is_non_negative_length_percentage(part) || is_keyword(part, &["auto"])
+}
+
+fn is_repeat_keyword(part: &TokenList) -> bool {
+    is_keyword(part, &["repeat", "space", "round", "no-repeat"])
+}
+
+fn is_position_part(part: &TokenList) -> bool {
+    is_length_percentage(part) || is_keyword(part, &["left", "center", "right", "top", "bottom"])
+}
+
+fn is_line_height(part: &TokenList) -> bool {
+    is_non_negative_length_percentage(part)
+        || is_non_negative_number(part)
+        || is_keyword(part, &["normal"])
+}
+
+fn is_font_weight(part: &TokenList) -> bool {
+    match *part {
+        [(Token::Number(ref n), _)] => n.value >= 1.0 && n.value <= 1000.0,
+        _ => is_keyword(part, &["bold", "bolder", "lighter"]),
+    }
+}
+
+// A comma-separated list of strings or sequences of identifiers.
+fn is_font_family(tokens: &TokenList) -> bool {
+    split_commas(tokens).into_iter().all(|family| {
+        let family: Vec<&Token> = family
+            .iter()
+            .map(|(token, _)| token)
+            .filter(|token| **token != Token::Whitespace)
+            .collect();
+
+        match family[..] {
+            [Token::QuotedString(_)] => true,
+            [] => false,
+            _ => family
+                .iter()
+                .all(|token| matches!(**token, Token::Ident(_))),
+        }
+    })
+}
+
+fn is_keyword(part: &TokenList, keywords: &[&str]) -> bool {
+    match *part {
+        [(Token::Ident(ref name), _)] => keywords
+            .iter()
+            .any(|keyword| name.eq_ignore_ascii_case(keyword)),
+        _ => false,
+    }
+}
+
+fn is_function(part: &TokenList, names: &[&str]) -> bool {
+    match part.first() {
+        Some((Token::Function(ref name), _)) => names
+            .iter()
+            .any(|function| name.eq_ignore_ascii_case(function)),
+        _ => false,
+    }
+}
+
+fn is_delim(part: &TokenList, delim: char) -> bool {
+    match *part {
+        [(Token::Delim(c), _)] => c == delim,
+        _ => false,
+    }
+}
+
+// The tokens from the first of `ranges` to the end of the last.
+fn join<'a>(tokens: &TokenList<'a>, ranges: &[Range<usize>]) -> Vec<(Token<'a>, Span)> {
+    match (ranges.first(), ranges.last()) {
+        (Some(first), Some(last)) => tokens[first.start..last.end].to_vec(),
+        _ => Vec::new(),
+    }
+}
+
+fn keyword<'a>(name: &'static str) -> Vec<(Token<'a>, Span)> {
+    vec![(Token::Ident(Cow::Borrowed(name)), Span::default())]
+}
+
+// `0% 0%`
+fn initial_position<'a>() -> Vec<(Token<'a>, Span)> {
+    let zero = Numeric {
+        value: 0.0,
+        is_integer: true,
+        has_sign: false,
+    };
+    vec![
+        (Token::Percentage(zero), Span::default()),
+        (Token::Whitespace, Span::default()),
+        (Token::Percentage(zero), Span::default()),
+    ]
+}
+
+// Splits a value into whitespace-separated components. Blocks and function
+// calls stay whole, and `/` and `,` become components of their own.
+pub(crate) fn components(tokens: &TokenList) -> Vec<Range<usize>> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = None;
+
+    for (i, (token, _)) in tokens.iter().enumerate() {
+        if depth > 0 {
+            match *token {
+                Token::OpenParen | Token::OpenSquare | Token::OpenCurly | Token::Function(_) => {
+                    depth += 1
+                }
+                Token::CloseParen | Token::CloseSquare | Token::CloseCurly => depth -= 1,
+                _ => {}
+            }
+            continue;
+        }
+
+        match *token {
+            Token::Whitespace => {
+                if let Some(s) = start.take() {
+                    parts.push(s..i);
+                }
+            }
+            Token::Comma | Token::Delim('/') => {
+                if let Some(s) = start.take() {
+                    parts.push(s..i);
+                }
+                parts.push(i..i + 1);
+            }
+            Token::OpenParen | Token::OpenSquare | Token::OpenCurly | Token::Function(_) => {
+                start.get_or_insert(i);
+                depth += 1;
+            }
+            _ => {
+                start.get_or_insert(i);
+            }
+        }
+    }
+
+    if let Some(s) = start {
+        parts.push(s..tokens.len());
+    }
+
+    parts
+}
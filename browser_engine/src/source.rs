@@ -0,0 +1,97 @@
+use std::fmt;
+
+use crate::atom::Atom;
+
+#[derive(PartialEq, Eq, Clone, Copy, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Default)]
+pub struct LineColumn {
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Default)]
+pub struct SourceLocation {
+    pub file: Option<Atom>,
+    pub span: Span,
+    pub start: LineColumn,
+    pub end: LineColumn,
+}
+
+pub struct LineIndex<'a> {
+    input: &'a str,
+    file: Option<Atom>,
+    line_starts: Vec<usize>,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Span {
+        Span { start, end }
+    }
+}
+
+impl fmt::Debug for Span {
+    fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
+        write!(format, "{0}..{1}", self.start, self.end)
+    }
+}
+
+impl fmt::Debug for LineColumn {
+    fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
+        write!(format, "{0}:{1}", self.line, self.column)
+    }
+}
+
+impl fmt::Debug for SourceLocation {
+    fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
+        match self.file {
+            Some(ref file) => write!(format, "{0}:{1:?}-{2:?}", file, self.start, self.end),
+            None => write!(format, "{0:?}-{1:?}", self.start, self.end),
+        }
+    }
+}
+
+impl<'a> LineIndex<'a> {
+    // A line ends at "\n", "\r\n" or a "\r" on its own.
+    pub fn new(input: &'a str, file: Option<Atom>) -> LineIndex<'a> {
+        let bytes = input.as_bytes();
+        let mut line_starts = vec![0];
+        line_starts.extend((0..bytes.len()).filter_map(|i| match bytes[i] {
+            b'\n' => Some(i + 1),
+            b'\r' if bytes.get(i + 1) != Some(&b'\n') => Some(i + 1),
+            _ => None,
+        }));
+
+        LineIndex {
+            input,
+            file,
+            line_starts,
+        }
+    }
+
+    pub fn line_column(&self, offset: usize) -> LineColumn {
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next_line) => next_line - 1,
+        };
+        let line_start = self.line_starts[line];
+
+        LineColumn {
+            line: line + 1,
+            column: self.input[line_start..offset].chars().count() + 1,
+        }
+    }
+
+    pub fn location(&self, start: usize, end: usize) -> SourceLocation {
+        SourceLocation {
+            file: self.file,
+            span: Span::new(start, end),
+            start: self.line_column(start),
+            end: self.line_column(end),
+        }
+    }
+}
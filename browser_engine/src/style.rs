@@ -1,137 +1,520 @@
-﻿use std::collections::HashMap;
+use std::collections::HashMap;
 use std::{fmt, str};
 
-use crate::css::{Selector, StyleSheet, Value};
-use crate::dom::{ElementData, Node, NodeType};
+use crate::css::{Declarations, Origin, PseudoElement, Rule, Specificity, StyleSheet, Unit, Value};
+use crate::custom_properties::{self, CustomProperties};
+use crate::dom::{Node, NodeType};
+use crate::generated::{self, Content, ContentItem, GeneratedContent};
+use crate::media::MediaEnvironment;
+use crate::properties;
+use crate::selector::{self, ElementRef};
+use crate::source::SourceLocation;
 
-type PropertyMap<'a> = HashMap<&'a str, &'a Value>;
+type PropertyMap<'a> = HashMap<&'a str, &'a Declarations>;
 
 pub struct StyledNode<'a> {
     node: &'a Node,
     styles: PropertyMap<'a>,
+    computed: HashMap<&'a str, Option<Value>>,
+    custom_properties: CustomProperties,
+    rules: Vec<&'a Rule>,
+    pseudo_element: Option<PseudoElement>,
+    content: Option<String>,
+    font_size: f32,
     pub children: Vec<StyledNode<'a>>,
 }
 
+#[derive(Clone, Copy)]
+struct MatchedRule<'a> {
+    origin: Origin,
+    specificity: Specificity,
+    rule: &'a Rule,
+}
+
 pub enum Display {
     Block,
     Inline,
     InlineBlock,
+    ListItem,
     None,
 }
 
 impl<'a> StyledNode<'a> {
     pub fn new(node: &'a Node, stylesheet: &'a StyleSheet) -> StyledNode<'a> {
-        let mut style_children = Vec::new();
+        StyledNode::new_with_media(node, stylesheet, &MediaEnvironment::default())
+    }
 
-        for child in &node.children {
-            match child.node_type {
-                NodeType::Element(_) => style_children.push(StyledNode::new(&child, stylesheet)),
-                _ => {}
+    pub fn new_with_media(
+        node: &'a Node,
+        stylesheet: &'a StyleSheet,
+        env: &MediaEnvironment,
+    ) -> StyledNode<'a> {
+        StyledNode::new_with_cascade(node, &[stylesheet], env)
+    }
+
+    // Stylesheets may come from any origin; their order only matters within
+    // the same origin.
+    pub fn new_with_cascade(
+        node: &'a Node,
+        stylesheets: &[&'a StyleSheet],
+        env: &MediaEnvironment,
+    ) -> StyledNode<'a> {
+        let mut rules = Vec::new();
+
+        for stylesheet in stylesheets {
+            for rule in stylesheet.style_rules(env) {
+                rules.push((stylesheet.origin, rule));
+            }
+        }
+
+        StyledNode::build(
+            &ElementRef::new(node, None, 0),
+            &rules,
+            None,
+            &mut GeneratedContent::new(),
+            env,
+        )
+    }
+
+    // Elements are styled in tree order so counters and quotes see the
+    // boxes generated before them. ::marker, ::before and ::after become the
+    // first and last children of their element.
+    fn build(
+        element: &ElementRef<'a, '_>,
+        stylesheet_rules: &[(Origin, &'a Rule)],
+        parent: Option<&StyledNode<'a>>,
+        generated: &mut GeneratedContent,
+        env: &MediaEnvironment,
+    ) -> StyledNode<'a> {
+        let node = element.get_node();
+
+        let matched = match node.node_type {
+            NodeType::Element(_) => StyledNode::get_rules(element, stylesheet_rules),
+            _ => Vec::new(),
+        };
+        let rules = rules_for(&matched, None);
+        let mut styled_node = StyledNode::new_with_rules(node, rules, None, parent, env);
+
+        // rem refers to the root element's font size everywhere below it.
+        let root_env;
+        let env = match parent {
+            Some(_) => env,
+            None => {
+                root_env = MediaEnvironment {
+                    root_font_size: styled_node.font_size,
+                    ..env.clone()
+                };
+                &root_env
+            }
+        };
+
+        match node.node_type {
+            NodeType::Element(_) => generated.enter(&styled_node),
+            _ => return styled_node,
+        }
+
+        let mut children = Vec::new();
+        if let Display::ListItem = styled_node.get_display() {
+            children.extend(StyledNode::build_pseudo_element(
+                element,
+                &matched,
+                PseudoElement::Marker,
+                &styled_node,
+                generated,
+                env,
+            ));
+        }
+        children.extend(StyledNode::build_pseudo_element(
+            element,
+            &matched,
+            PseudoElement::Before,
+            &styled_node,
+            generated,
+            env,
+        ));
+
+        for (index, child) in node.children.iter().enumerate() {
+            if let NodeType::Element(_) = child.node_type {
+                children.push(StyledNode::build(
+                    &ElementRef::new(child, Some(element), index),
+                    stylesheet_rules,
+                    Some(&styled_node),
+                    generated,
+                    env,
+                ));
+            }
+        }
+
+        children.extend(StyledNode::build_pseudo_element(
+            element,
+            &matched,
+            PseudoElement::After,
+            &styled_node,
+            generated,
+            env,
+        ));
+
+        generated.leave();
+        styled_node.children = children;
+        styled_node
+    }
+
+    // A pseudo-element only generates a box when it has content; a marker
+    // defaults to the list item number.
+    fn build_pseudo_element(
+        element: &ElementRef<'a, '_>,
+        matched: &[(Option<PseudoElement>, MatchedRule<'a>)],
+        pseudo_element: PseudoElement,
+        parent: &StyledNode<'a>,
+        generated: &mut GeneratedContent,
+        env: &MediaEnvironment,
+    ) -> Option<StyledNode<'a>> {
+        let element_data = element.get_element()?;
+        let rules = rules_for(matched, Some(pseudo_element));
+        let mut styled_node = StyledNode::new_with_rules(
+            element.get_node(),
+            rules,
+            Some(pseudo_element),
+            Some(parent),
+            env,
+        );
+
+        let items = match (generated::content(&styled_node), pseudo_element) {
+            (Content::Items(items), _) => items,
+            (Content::Normal, PseudoElement::Marker) => vec![
+                ContentItem::Counter("list-item".to_string(), "decimal".to_string()),
+                ContentItem::String(". ".to_string()),
+            ],
+            _ => return None,
+        };
+
+        generated.enter(&styled_node);
+        styled_node.content = Some(generated.generate(&items, element_data));
+        generated.leave();
+
+        Some(styled_node)
+    }
+
+    // Custom properties are computed first, since var() references depend on
+    // them. Values other than the declared ones, from substitution, CSS-wide
+    // keywords or inheritance, are kept in `computed`, where None means the
+    // property has no value at all.
+    fn new_with_rules(
+        node: &'a Node,
+        rules: Vec<MatchedRule<'a>>,
+        pseudo_element: Option<PseudoElement>,
+        parent: Option<&StyledNode<'a>>,
+        env: &MediaEnvironment,
+    ) -> StyledNode<'a> {
+        let inline = match (&node.node_type, pseudo_element) {
+            (NodeType::Element(ref element), None) => element.get_style(),
+            _ => &[],
+        };
+        let styles = StyledNode::get_styles(&rules, inline);
+        let no_custom_properties = CustomProperties::new();
+        let inherited = parent.map_or(&no_custom_properties, |parent| &parent.custom_properties);
+        let custom_properties = custom_properties::compute(inherited, styles.values().cloned());
+
+        let mut computed = HashMap::new();
+        for (&name, declar) in &styles {
+            // A value that is invalid at computed-value time acts as `unset`.
+            let value = match declar.value {
+                Value::Unresolved(ref shorthand, ref tokens) => {
+                    let shorthand = shorthand.as_ref().map(|shorthand| &shorthand[..]);
+                    custom_properties::resolve_value(name, shorthand, tokens, &custom_properties)
+                        .unwrap_or_else(|| Value::Keyword(String::from("unset")))
+                }
+                Value::Keyword(ref keyword) if properties::is_css_wide_keyword(keyword) => {
+                    Value::Keyword(keyword.clone())
+                }
+                _ => continue,
+            };
+
+            let value = match value {
+                Value::Keyword(ref keyword) if properties::is_css_wide_keyword(keyword) => {
+                    cascade_keyword(name, keyword, parent)
+                }
+                value => Some(value),
+            };
+            computed.insert(name, value);
+        }
+
+        if let Some(parent) = parent {
+            for property in properties::inherited() {
+                if styles.contains_key(property.name) {
+                    continue;
+                }
+                if let Some(value) = parent.value(property.name) {
+                    computed.insert(property.name, Some(value.clone()));
+                }
             }
         }
 
+        let font_size = compute_font_size(
+            match computed.get("font-size") {
+                Some(value) => value.as_ref(),
+                None => styles.get("font-size").map(|declar| &declar.value),
+            },
+            parent.map_or(env.font_size, |parent| parent.font_size),
+            env,
+        );
+        computed.insert("font-size", Some(Value::Length(font_size, Unit::Px)));
+
         StyledNode {
             node,
-            styles: match node.node_type {
-                NodeType::Element(ref e) => StyledNode::get_styles(e, stylesheet),
-                _ => PropertyMap::new(),
-            },
-            children: style_children,
+            styles,
+            computed,
+            custom_properties,
+            rules: rules.into_iter().map(|matched| matched.rule).collect(),
+            pseudo_element,
+            content: None,
+            font_size,
+            children: Vec::new(),
         }
     }
 
-    fn get_styles(element: &'a ElementData, stylesheet: &'a StyleSheet) -> PropertyMap<'a> {
-        let mut styles = PropertyMap::new();
+    // Every selector is matched once per element. A rule can apply to the
+    // element and to its pseudo-elements through different selectors; for
+    // each, it counts with its most specific matching selector.
+    fn get_rules(
+        element: &ElementRef,
+        stylesheet_rules: &[(Origin, &'a Rule)],
+    ) -> Vec<(Option<PseudoElement>, MatchedRule<'a>)> {
+        let mut matched: Vec<(Option<PseudoElement>, MatchedRule<'a>)> = Vec::new();
+
+        for &(origin, rule) in stylesheet_rules {
+            let start = matched.len();
 
-        for rule in &stylesheet.rules {
             for selector in &rule.selectors {
-                if selector_matches(element, &selector) {
-                    for declar in &rule.declarations {
-                        styles.insert(&declar.property, &declar.value);
-                    }
-                    break;
+                let pseudo_element = selector.pseudo_element();
+                if !selector::matches_pseudo_element(element, selector, pseudo_element) {
+                    continue;
                 }
+
+                let specificity = selector.specificity();
+                match matched[start..]
+                    .iter_mut()
+                    .find(|(target, _)| *target == pseudo_element)
+                {
+                    Some((_, rule)) => rule.specificity = rule.specificity.max(specificity),
+                    None => matched.push((
+                        pseudo_element,
+                        MatchedRule {
+                            origin,
+                            specificity,
+                            rule,
+                        },
+                    )),
+                }
+            }
+        }
+
+        matched
+    }
+
+    // Declarations are ordered by cascade level, then by specificity and
+    // source order within a level, and the last one for each property wins.
+    // The style attribute is more specific than any selector in the author
+    // origin. A winning `revert` rolls the property back to what the origins
+    // below its own declare.
+    fn get_styles(rules: &[MatchedRule<'a>], inline: &'a [Declarations]) -> PropertyMap<'a> {
+        let mut declarations = Vec::new();
+
+        for (order, matched) in rules.iter().enumerate() {
+            for declar in &matched.rule.declarations {
+                let level = cascade_level(matched.origin, declar.important);
+                let key = (level, false, matched.specificity, order);
+                declarations.push((key, matched.origin, declar));
+            }
+        }
+        for declar in inline {
+            let level = cascade_level(Origin::Author, declar.important);
+            let key = (level, true, Specificity::default(), rules.len());
+            declarations.push((key, Origin::Author, declar));
+        }
+        declarations.sort_by_key(|&(key, _, _)| key);
+
+        let mut styles = PropertyMap::new();
+        let mut reverted: HashMap<&str, Origin> = HashMap::new();
+        for (_, origin, declar) in declarations.into_iter().rev() {
+            let name = &declar.property[..];
+            if styles.contains_key(name) {
+                continue;
+            }
+            match reverted.get(name) {
+                Some(&reverted) if origin >= reverted => continue,
+                _ => {}
+            }
+
+            if is_revert(&declar.value) && origin != Origin::UserAgent {
+                reverted.insert(name, origin);
+            } else {
+                styles.insert(name, declar);
             }
         }
         styles
     }
 
-    pub fn value(&self, name: &str) -> Option<&&Value> {
-        self.styles.get(name)
+    pub fn value(&self, name: &str) -> Option<&Value> {
+        match self.computed.get(name) {
+            Some(value) => value.as_ref(),
+            None => self.styles.get(name).map(|declar| &declar.value),
+        }
+    }
+
+    // The declaration that won the cascade, as it was written.
+    pub fn declaration(&self, name: &str) -> Option<&'a Declarations> {
+        self.styles.get(name).copied()
+    }
+
+    pub fn get_custom_properties(&self) -> &CustomProperties {
+        &self.custom_properties
+    }
+
+    pub fn matched_rules(&self) -> &[&'a Rule] {
+        &self.rules
+    }
+
+    pub fn get_location(&self) -> &'a SourceLocation {
+        self.node.get_location()
+    }
+
+    pub fn get_pseudo_element(&self) -> Option<PseudoElement> {
+        self.pseudo_element
+    }
+
+    // The text generated by the `content` property of a pseudo-element.
+    pub fn get_content(&self) -> Option<&str> {
+        self.content.as_ref().map(|content| &content[..])
+    }
+
+    // The computed font size in pixels, which em units refer to.
+    pub fn get_font_size(&self) -> f32 {
+        self.font_size
     }
 
     pub fn get_display(&self) -> Display {
         match self.value("display") {
-            Some(s) => match **s {
-                Value::Other(ref v) => match v.as_ref() {
-                    "block" => Display::Block,
-                    "none" => Display::None,
-                    "inline-block" => Display::InlineBlock,
-                    _ => Display::Inline,
-                },
+            Some(Value::Keyword(keyword)) => match keyword.as_ref() {
+                "block" => Display::Block,
+                "none" => Display::None,
+                "inline-block" => Display::InlineBlock,
+                "list-item" => Display::ListItem,
                 _ => Display::Inline,
             },
-            None => Display::Inline,
+            _ => Display::Inline,
         }
     }
 
     pub fn num_or(&self, name: &str, default: f32) -> f32 {
         match self.value(name) {
-            Some(v) => match **v {
-                Value::Length(n, _) => n,
-                _ => default,
-            },
-            None => default,
+            Some(&Value::Length(n, _)) => n,
+            _ => default,
         }
     }
 }
 
 impl<'a> fmt::Debug for StyledNode<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}: {:?}", self.node, self.styles)
+        let styles: HashMap<_, _> = self
+            .styles
+            .keys()
+            .chain(self.computed.keys())
+            .filter_map(|name| self.value(name).map(|value| (name, value)))
+            .collect();
+
+        match (self.pseudo_element, &self.content) {
+            (Some(pseudo_element), Some(content)) => write!(
+                f,
+                "{:?}{:?} {:?}: {:?}",
+                self.node, pseudo_element, content, styles
+            ),
+            _ => write!(f, "{:?}: {:?}", self.node, styles),
+        }
     }
 }
 
-fn selector_matches(element: &ElementData, selector: &Selector) -> bool {
-    for simple in &selector.simple {
-        let mut selector_match = true;
+fn cascade_level(origin: Origin, important: bool) -> u8 {
+    match (origin, important) {
+        (Origin::UserAgent, false) => 0,
+        (Origin::User, false) => 1,
+        (Origin::Author, false) => 2,
+        (Origin::Author, true) => 3,
+        (Origin::User, true) => 4,
+        (Origin::UserAgent, true) => 5,
+    }
+}
 
-        match simple.tag_name {
-            Some(ref t) => {
-                if *t != element.tag_name {
-                    continue;
-                }
-            }
-            None => {}
-        };
+// A `revert` left at this point comes from the user agent origin, which has
+// nothing below it, or from var() substitution after the cascade. Either way
+// it inherits or resets depending on the property, like `unset`.
+fn cascade_keyword(name: &str, keyword: &str, parent: Option<&StyledNode>) -> Option<Value> {
+    let inherit = match &keyword.to_ascii_lowercase()[..] {
+        "inherit" => true,
+        "initial" => false,
+        _ => properties::lookup(name).is_some_and(|property| property.inherited),
+    };
 
-        match element.get_id() {
-            Some(i) => match simple.id {
-                Some(ref id) => {
-                    if *i != *id {
-                        continue;
-                    }
-                }
-                None => {}
-            },
-            None => match simple.id {
-                Some(_) => {
-                    continue;
-                }
-                _ => {}
-            },
-        }
-        let element_classes = element.get_classes();
+    match parent.and_then(|parent| parent.value(name)) {
+        Some(value) if inherit => Some(value.clone()),
+        _ => properties::initial_value(name).cloned(),
+    }
+}
 
-        for class in &simple.classes {
-            selector_match &= element_classes.contains::<str>(class);
-        }
+// The rules matched for the element itself, or for one of its
+// pseudo-elements.
+fn rules_for<'a>(
+    matched: &[(Option<PseudoElement>, MatchedRule<'a>)],
+    pseudo_element: Option<PseudoElement>,
+) -> Vec<MatchedRule<'a>> {
+    matched
+        .iter()
+        .filter(|(target, _)| *target == pseudo_element)
+        .map(|&(_, rule)| rule)
+        .collect()
+}
 
-        if selector_match {
-            return true;
-        }
+// font-size computes to an absolute length. Keywords scale the initial font
+// size, while relative lengths and percentages refer to the parent's. A math
+// function that gives NaN is invalid, so the parent's size is inherited.
+fn compute_font_size(value: Option<&Value>, parent_size: f32, env: &MediaEnvironment) -> f32 {
+    let parent_env = || MediaEnvironment {
+        font_size: parent_size,
+        ..env.clone()
+    };
+
+    let size = match value {
+        Some(Value::Length(length, Unit::Px)) => *length,
+        Some(Value::Length(length, unit)) => unit.to_px(*length, &parent_env()),
+        Some(Value::Percentage(percentage)) => parent_size * percentage / 100.0,
+        Some(Value::Calc(calc)) => match calc.resolve(Some(parent_size), &parent_env()) {
+            Some(size) if !size.is_nan() => size,
+            _ => parent_size,
+        },
+        Some(Value::Keyword(keyword)) => match &keyword[..] {
+            "xx-small" => env.font_size * 3.0 / 5.0,
+            "x-small" => env.font_size * 3.0 / 4.0,
+            "small" => env.font_size * 8.0 / 9.0,
+            "large" => env.font_size * 6.0 / 5.0,
+            "x-large" => env.font_size * 3.0 / 2.0,
+            "xx-large" => env.font_size * 2.0,
+            "xxx-large" => env.font_size * 3.0,
+            "larger" => parent_size * 1.2,
+            "smaller" => parent_size / 1.2,
+            "inherit" | "unset" => parent_size,
+            _ => env.font_size,
+        },
+        _ => parent_size,
+    };
+
+    size.clamp(0.0, f32::MAX)
+}
+
+fn is_revert(value: &Value) -> bool {
+    match *value {
+        Value::Keyword(ref keyword) => keyword.eq_ignore_ascii_case("revert"),
+        _ => false,
     }
-    false
 }
 
 pub fn pretty_print(node: &StyledNode, indent_size: usize) {
@@ -139,6 +522,6 @@ pub fn pretty_print(node: &StyledNode, indent_size: usize) {
     println!("{}{:?}", indent, node);
 
     for child in node.children.iter() {
-        pretty_print(&child, indent_size + 2);
+        pretty_print(child, indent_size + 2);
     }
 }
@@ -0,0 +1,548 @@
+use crate::atom::Atom;
+use crate::dom::{AttributeMap, ElementData, Node, NodeType};
+use crate::source::{LineIndex, SourceLocation};
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+pub const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+pub struct XmlParser<'a> {
+    input: &'a str,
+    position: usize,
+    lines: LineIndex<'a>,
+    namespaces: Vec<HashMap<String, String>>,
+}
+
+#[derive(PartialEq, Clone)]
+pub struct XmlError {
+    pub(crate) message: String,
+    pub(crate) location: SourceLocation,
+}
+
+impl<'a> XmlParser<'a> {
+    pub fn new(full_xml: &'a str) -> XmlParser<'a> {
+        XmlParser::new_with_lines(full_xml, LineIndex::new(full_xml, None))
+    }
+
+    pub fn new_with_file(full_xml: &'a str, file: &str) -> XmlParser<'a> {
+        XmlParser::new_with_lines(full_xml, LineIndex::new(full_xml, Some(Atom::from(file))))
+    }
+
+    fn new_with_lines(full_xml: &'a str, lines: LineIndex<'a>) -> XmlParser<'a> {
+        let mut scope = HashMap::new();
+        scope.insert(String::from("xml"), String::from(XML_NAMESPACE));
+
+        XmlParser {
+            input: full_xml,
+            position: 0,
+            lines,
+            namespaces: vec![scope],
+        }
+    }
+
+    pub fn parse_nodes(&mut self) -> Result<Vec<Node>, XmlError> {
+        let mut nodes = Vec::new();
+        let mut seen_root = false;
+
+        if self.starts_with("<?xml") && self.rest().chars().nth(5).is_some_and(is_xml_whitespace) {
+            self.parse_xml_declaration()?;
+        }
+
+        loop {
+            self.consume_while(is_xml_whitespace);
+            let start = self.position;
+
+            if self.peek().is_none() {
+                break;
+            } else if self.consume_str("<!--") {
+                let node = self.parse_comment_node()?;
+                nodes.push(self.located(node, start));
+            } else if self.consume_str("<!DOCTYPE") {
+                if seen_root {
+                    return Err(self.error("doctype after the root element"));
+                }
+                self.skip_doctype()?;
+            } else if self.consume_str("<?") {
+                let node = self.parse_processing_instruction()?;
+                nodes.push(self.located(node, start));
+            } else if self.consume_str("<") {
+                if seen_root {
+                    return Err(self.error("more than one root element"));
+                }
+                let node = self.parse_node()?;
+                nodes.push(self.located(node, start));
+                seen_root = true;
+            } else {
+                return Err(self.error("text outside of the root element"));
+            }
+        }
+
+        if !seen_root {
+            return Err(self.error("missing root element"));
+        }
+
+        Ok(nodes)
+    }
+
+    fn parse_xml_declaration(&mut self) -> Result<(), XmlError> {
+        self.consume_str("<?xml");
+
+        let declaration = self.consume_until("?>")?;
+        if !declaration.contains("version") {
+            return Err(self.error("malformed xml declaration"));
+        }
+
+        Ok(())
+    }
+
+    fn skip_doctype(&mut self) -> Result<(), XmlError> {
+        let mut in_subset = false;
+
+        loop {
+            match self.next_char() {
+                Some(c) if c == '"' || c == '\'' => {
+                    self.consume_while(|x| x != c);
+                    self.next_char();
+                }
+                Some('[') => in_subset = true,
+                Some(']') => in_subset = false,
+                Some('>') if !in_subset => return Ok(()),
+                Some(_) => {}
+                None => return Err(self.error("unterminated doctype")),
+            }
+        }
+    }
+
+    fn parse_node(&mut self) -> Result<Node, XmlError> {
+        let qualified_name = self.parse_name()?;
+        let attributes = self.parse_attributes()?;
+
+        self.push_namespace_scope(&attributes)?;
+
+        let (namespace, tag_name) = self.resolve_element_name(&qualified_name)?;
+        for name in attributes.keys() {
+            self.check_attribute_name(name)?;
+        }
+
+        let children = if self.consume_str("/>") {
+            Vec::new()
+        } else if self.consume_str(">") {
+            let children = self.parse_content()?;
+
+            self.consume_str("</");
+            let close_tag_name = self.parse_name()?;
+            if close_tag_name != qualified_name {
+                return Err(self.error(&format!(
+                    "expected </{0}> but found </{1}>",
+                    qualified_name, close_tag_name
+                )));
+            }
+
+            self.consume_while(is_xml_whitespace);
+            if !self.consume_str(">") {
+                return Err(self.error("expected '>' to close end tag"));
+            }
+            children
+        } else {
+            return Err(self.error("expected '>' or '/>' in start tag"));
+        };
+
+        self.namespaces.pop();
+
+        let elem = ElementData::new_with_namespace(tag_name, namespace, attributes);
+        Ok(Node::new(NodeType::Element(elem), children))
+    }
+
+    fn parse_content(&mut self) -> Result<Vec<Node>, XmlError> {
+        let mut nodes = Vec::new();
+        let mut text_content = String::new();
+        let mut text_start = self.position;
+
+        loop {
+            let start = self.position;
+
+            if self.peek().is_none() {
+                return Err(self.error("unexpected end of input inside element"));
+            } else if self.starts_with("</") {
+                break;
+            } else if self.consume_str("<![CDATA[") {
+                text_content.push_str(&self.consume_until("]]>")?);
+            } else if self.starts_with("<") {
+                self.push_text_node(&mut nodes, &mut text_content, text_start);
+
+                let node = if self.consume_str("<!--") {
+                    self.parse_comment_node()?
+                } else if self.consume_str("<?") {
+                    self.parse_processing_instruction()?
+                } else if self.starts_with("<!") {
+                    return Err(self.error("markup declaration inside element"));
+                } else {
+                    self.next_char();
+                    self.parse_node()?
+                };
+                nodes.push(self.located(node, start));
+                text_start = self.position;
+            } else if self.starts_with("&") {
+                text_content.push(self.parse_reference()?);
+            } else if self.starts_with("]]>") {
+                return Err(self.error("']]>' is not allowed in text content"));
+            } else {
+                let text = self.consume_while(|c| c != '<' && c != '&' && c != ']');
+                text_content.push_str(&normalize_line_ends(text));
+                if self.starts_with("]") && !self.starts_with("]]>") {
+                    self.next_char();
+                    text_content.push(']');
+                }
+            }
+        }
+
+        self.push_text_node(&mut nodes, &mut text_content, text_start);
+        Ok(nodes)
+    }
+
+    fn parse_comment_node(&mut self) -> Result<Node, XmlError> {
+        let comment_content = self.consume_until("--")?;
+
+        if !self.consume_str(">") {
+            return Err(self.error("'--' is not allowed inside a comment"));
+        }
+
+        Ok(Node::new(NodeType::Comment(comment_content), Vec::new()))
+    }
+
+    fn parse_processing_instruction(&mut self) -> Result<Node, XmlError> {
+        let target = self.parse_name()?;
+
+        if target.eq_ignore_ascii_case("xml") {
+            return Err(self.error("the xml declaration must be at the start of the document"));
+        }
+        if target.contains(':') {
+            return Err(self.error("processing instruction targets may not contain ':'"));
+        }
+
+        let data = if self.consume_str("?>") {
+            String::new()
+        } else if self.peek().is_some_and(is_xml_whitespace) {
+            self.consume_while(is_xml_whitespace);
+            self.consume_until("?>")?
+        } else {
+            return Err(self.error("expected whitespace after processing instruction target"));
+        };
+
+        Ok(Node::new(
+            NodeType::ProcessingInstruction(target, data),
+            Vec::new(),
+        ))
+    }
+
+    fn parse_attributes(&mut self) -> Result<AttributeMap, XmlError> {
+        let mut attributes = AttributeMap::new();
+
+        loop {
+            let whitespace = self.consume_while(is_xml_whitespace);
+
+            match self.peek() {
+                Some('>') | Some('/') => break,
+                None => return Err(self.error("unexpected end of input inside start tag")),
+                _ => {}
+            }
+
+            if whitespace.is_empty() {
+                return Err(self.error("expected whitespace between attributes"));
+            }
+
+            let name = self.parse_name()?;
+            self.consume_while(is_xml_whitespace);
+            if !self.consume_str("=") {
+                return Err(self.error(&format!("attribute {} has no value", name)));
+            }
+            self.consume_while(is_xml_whitespace);
+
+            let value = self.parse_attr_value()?;
+            if attributes.insert(Atom::from(&name[..]), value).is_some() {
+                return Err(self.error(&format!("duplicate attribute {}", name)));
+            }
+        }
+
+        Ok(attributes)
+    }
+
+    fn parse_attr_value(&mut self) -> Result<String, XmlError> {
+        let quote = match self.next_char() {
+            Some(c) if c == '"' || c == '\'' => c,
+            _ => return Err(self.error("attribute values must be quoted")),
+        };
+
+        let mut value = String::new();
+        loop {
+            match self.peek() {
+                Some(c) if c == quote => {
+                    self.next_char();
+                    return Ok(value);
+                }
+                Some('<') => return Err(self.error("'<' is not allowed in attribute values")),
+                Some('&') => value.push(self.parse_reference()?),
+                Some(c) if is_xml_whitespace(c) => {
+                    self.next_char();
+                    value.push(' ');
+                }
+                Some(_) => value.push(self.next_char().unwrap()),
+                None => return Err(self.error("unterminated attribute value")),
+            }
+        }
+    }
+
+    fn parse_reference(&mut self) -> Result<char, XmlError> {
+        self.consume_str("&");
+        let reference = self.consume_while(|c| c != ';' && c != '<' && !is_xml_whitespace(c));
+
+        if !self.consume_str(";") {
+            return Err(self.error("unterminated entity reference"));
+        }
+
+        let code_point = if let Some(hex) = reference.strip_prefix("#x") {
+            u32::from_str_radix(hex, 16).ok()
+        } else if let Some(decimal) = reference.strip_prefix('#') {
+            decimal.parse::<u32>().ok()
+        } else {
+            return match reference {
+                "lt" => Ok('<'),
+                "gt" => Ok('>'),
+                "amp" => Ok('&'),
+                "quot" => Ok('"'),
+                "apos" => Ok('\''),
+                _ => Err(self.error(&format!("undefined entity &{};", reference))),
+            };
+        };
+
+        match code_point.and_then(std::char::from_u32) {
+            Some(c) if is_valid_xml_char(c) => Ok(c),
+            _ => Err(self.error(&format!("invalid character reference &{};", reference))),
+        }
+    }
+
+    fn parse_name(&mut self) -> Result<String, XmlError> {
+        if !self.peek().is_some_and(is_name_start_char) {
+            return Err(self.error("expected a name"));
+        }
+
+        Ok(self.consume_while(is_name_char).to_string())
+    }
+
+    fn push_namespace_scope(&mut self, attributes: &AttributeMap) -> Result<(), XmlError> {
+        let mut scope = HashMap::new();
+
+        for (name, value) in attributes.iter() {
+            if name == "xmlns" {
+                scope.insert(String::new(), value.clone());
+            } else if let Some(prefix) = name.strip_prefix("xmlns:") {
+                if value.is_empty() {
+                    return Err(self.error(&format!("namespace prefix {} bound to ''", prefix)));
+                }
+                if prefix == "xmlns" || (prefix == "xml") != (value == XML_NAMESPACE) {
+                    return Err(self.error(&format!("cannot rebind reserved prefix {}", prefix)));
+                }
+                scope.insert(prefix.to_string(), value.clone());
+            }
+        }
+
+        self.namespaces.push(scope);
+        Ok(())
+    }
+
+    fn lookup_namespace(&self, prefix: &str) -> Option<&String> {
+        self.namespaces
+            .iter()
+            .rev()
+            .filter_map(|scope| scope.get(prefix))
+            .next()
+    }
+
+    fn resolve_element_name(&self, qualified_name: &str) -> Result<(Option<Atom>, Atom), XmlError> {
+        let (prefix, local_name) = split_qualified_name(qualified_name)
+            .ok_or_else(|| self.error(&format!("malformed name {}", qualified_name)))?;
+
+        let namespace = match self.lookup_namespace(prefix.unwrap_or("")) {
+            Some(ns) if ns.is_empty() => None,
+            Some(ns) => Some(Atom::from(&ns[..])),
+            None if prefix.is_none() => None,
+            None => {
+                return Err(self.error(&format!("undeclared namespace prefix {}", prefix.unwrap())))
+            }
+        };
+
+        Ok((namespace, Atom::from(local_name)))
+    }
+
+    fn check_attribute_name(&self, qualified_name: &str) -> Result<(), XmlError> {
+        match split_qualified_name(qualified_name) {
+            Some((Some("xmlns"), _)) | Some((None, _)) => Ok(()),
+            Some((Some(prefix), _)) => match self.lookup_namespace(prefix) {
+                Some(_) => Ok(()),
+                None => Err(self.error(&format!("undeclared namespace prefix {}", prefix))),
+            },
+            None => Err(self.error(&format!("malformed name {}", qualified_name))),
+        }
+    }
+
+    // Whitespace-only runs between elements are dropped, the same as in HtmlParser.
+    fn push_text_node(&self, nodes: &mut Vec<Node>, text_content: &mut String, start: usize) {
+        if !text_content.chars().all(is_xml_whitespace) {
+            let node = Node::new(NodeType::Text(text_content.clone()), Vec::new());
+            nodes.push(self.located(node, start));
+        }
+        text_content.clear();
+    }
+
+    fn located(&self, mut node: Node, start: usize) -> Node {
+        node.location = self.lines.location(start, self.position);
+        node
+    }
+
+    fn error(&self, message: &str) -> XmlError {
+        XmlError {
+            message: message.to_string(),
+            location: self.lines.location(self.position, self.position),
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.position..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    // Line ends are read as a single '\n', whether written as "\r\n", "\r"
+    // or "\n".
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += c.len_utf8();
+
+        if c == '\r' {
+            self.consume_str("\n");
+            return Some('\n');
+        }
+        Some(c)
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        self.rest().starts_with(s)
+    }
+
+    fn consume_str(&mut self, s: &str) -> bool {
+        if self.starts_with(s) {
+            self.position += s.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn consume_until(&mut self, terminator: &str) -> Result<String, XmlError> {
+        let mut result = String::new();
+
+        while !self.consume_str(terminator) {
+            match self.next_char() {
+                Some(c) => result.push(c),
+                None => return Err(self.error(&format!("expected '{}'", terminator))),
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn consume_while<F>(&mut self, condition: F) -> &'a str
+    where
+        F: Fn(char) -> bool,
+    {
+        let start = self.position;
+        let rest = self.rest();
+
+        self.position += rest
+            .char_indices()
+            .find(|&(_, c)| !condition(c))
+            .map_or(rest.len(), |(i, _)| i);
+
+        &self.input[start..self.position]
+    }
+}
+
+impl fmt::Debug for XmlError {
+    fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
+        write!(format, "{0}", self)
+    }
+}
+
+impl fmt::Display for XmlError {
+    fn fmt(&self, format: &mut fmt::Formatter) -> fmt::Result {
+        let start = self.location.start;
+
+        match self.location.file {
+            Some(ref file) => write!(
+                format,
+                "{0}:{1}:{2}: {3}",
+                file, start.line, start.column, self.message
+            ),
+            None => write!(
+                format,
+                "{0}:{1}: {2}",
+                start.line, start.column, self.message
+            ),
+        }
+    }
+}
+
+impl Error for XmlError {}
+
+fn split_qualified_name(name: &str) -> Option<(Option<&str>, &str)> {
+    let mut parts = name.splitn(2, ':');
+    let first = parts.next()?;
+
+    match parts.next() {
+        Some(local) if !first.is_empty() && !local.is_empty() && !local.contains(':') => {
+            Some((Some(first), local))
+        }
+        Some(_) => None,
+        None => Some((None, first)),
+    }
+}
+
+fn normalize_line_ends(text: &str) -> Cow<'_, str> {
+    if text.contains('\r') {
+        Cow::Owned(text.replace("\r\n", "\n").replace('\r', "\n"))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+// Only these four count as whitespace in XML; other Unicode spaces are
+// ordinary characters.
+fn is_xml_whitespace(c: char) -> bool {
+    matches!(c, '\u{20}' | '\u{9}' | '\u{D}' | '\u{A}')
+}
+
+fn is_name_start_char(c: char) -> bool {
+    matches!(c,
+        ':' | 'A'..='Z' | '_' | 'a'..='z'
+        | '\u{C0}'..='\u{D6}' | '\u{D8}'..='\u{F6}' | '\u{F8}'..='\u{2FF}'
+        | '\u{370}'..='\u{37D}' | '\u{37F}'..='\u{1FFF}' | '\u{200C}'..='\u{200D}'
+        | '\u{2070}'..='\u{218F}' | '\u{2C00}'..='\u{2FEF}' | '\u{3001}'..='\u{D7FF}'
+        | '\u{F900}'..='\u{FDCF}' | '\u{FDF0}'..='\u{FFFD}' | '\u{10000}'..='\u{EFFFF}')
+}
+
+fn is_name_char(c: char) -> bool {
+    is_name_start_char(c)
+        || matches!(c,
+            '-' | '.' | '0'..='9' | '\u{B7}' | '\u{300}'..='\u{36F}' | '\u{203F}'..='\u{2040}')
+}
+
+fn is_valid_xml_char(c: char) -> bool {
+    matches!(c,
+        '\u{9}' | '\u{A}' | '\u{D}'
+        | '\u{20}'..='\u{D7FF}' | '\u{E000}'..='\u{FFFD}' | '\u{10000}'..='\u{10FFFF}')
+}
@@ -0,0 +1,80 @@
+extern crate browser_engine;
+use browser_engine::calc::parse_length_percentage;
+use browser_engine::css::Calc;
+use browser_engine::css_tokenizer::{tokenize, Token};
+use browser_engine::media::MediaEnvironment;
+
+fn parse(css: &str) -> Option<Calc> {
+    let tokens: Vec<Token> = tokenize(css).into_iter().map(|(token, _)| token).collect();
+    parse_length_percentage(&tokens)
+}
+
+// Resolved against a 200px percentage basis and 16px fonts.
+fn resolve(css: &str) -> f32 {
+    let calc = parse(css).unwrap_or_else(|| panic!("{0} does not parse", css));
+    calc.resolve(Some(200.0), &MediaEnvironment::default())
+        .unwrap()
+}
+
+#[test]
+fn arithmetic() {
+    assert_eq!(resolve("calc(10px + 5px)"), 15.0);
+    assert_eq!(resolve("calc(10px - 2 * 3px)"), 4.0);
+    assert_eq!(resolve("calc((10px - 2px) * 3)"), 24.0);
+    assert_eq!(resolve("calc(2 * 1em / 4)"), 8.0);
+    assert_eq!(resolve("calc(50% - 1in)"), 4.0);
+    assert_eq!(resolve("CALC(1px)"), 1.0);
+}
+
+#[test]
+fn type_checking() {
+    assert!(parse("calc(1px + 1%)").is_some());
+    assert!(parse("calc(1px * 2)").is_some());
+    assert!(parse("calc(2 * 1px)").is_some());
+    assert!(parse("calc(1px / 2)").is_some());
+    assert!(parse("calc(1px + 1)").is_none());
+    assert!(parse("calc(1px * 1px)").is_none());
+    assert!(parse("calc(2 / 1px)").is_none());
+    assert!(parse("calc(1px / 1px)").is_none());
+    assert!(parse("calc(1 + 2)").is_none());
+    assert!(parse("calc(1deg)").is_none());
+    assert!(parse("min(1px, 2)").is_none());
+}
+
+#[test]
+fn sums_need_whitespace_around_the_operator() {
+    assert!(parse("calc(1px + 2px)").is_some());
+    assert!(parse("calc(1px - 2px)").is_some());
+    assert!(parse("calc(1px+2px)").is_none());
+    assert!(parse("calc(1px -2px)").is_none());
+    assert!(parse("calc(1px- 2px)").is_none());
+    assert!(parse("calc(1px +2px)").is_none());
+    assert!(parse("calc(2px*3)").is_some());
+    assert!(parse("calc(2px/ 3)").is_some());
+}
+
+#[test]
+fn comparison_functions() {
+    assert_eq!(resolve("min(10px, 5px, 20%)"), 5.0);
+    assert_eq!(resolve("max(10px, 5px, 20%)"), 40.0);
+    assert_eq!(resolve("clamp(10px, 5px, 20px)"), 10.0);
+    assert_eq!(resolve("clamp(10px, 50%, 20px)"), 20.0);
+    assert_eq!(resolve("clamp(10px, 15px, 20px)"), 15.0);
+    assert_eq!(resolve("calc(min(1px, 2px) + max(3px, 4px))"), 5.0);
+    assert!(parse("min()").is_none());
+    assert!(parse("clamp(1px, 2px)").is_none());
+    assert!(parse("calc(1px, 2px)").is_none());
+}
+
+#[test]
+fn division_by_zero() {
+    assert_eq!(resolve("calc(1px / 0)"), f32::INFINITY);
+    assert_eq!(resolve("calc(-1px / (1 - 1))"), f32::NEG_INFINITY);
+    assert!(resolve("calc(0px / 0)").is_nan());
+}
+
+#[test]
+fn percentages_need_a_basis() {
+    let calc = parse("calc(50% + 1px)").unwrap();
+    assert_eq!(calc.resolve(None, &MediaEnvironment::default()), None);
+}
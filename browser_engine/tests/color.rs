@@ -0,0 +1,158 @@
+extern crate browser_engine;
+use browser_engine::color::parse_color;
+use browser_engine::css::{Color, ColorSpace, Value};
+use browser_engine::css_tokenizer::tokenize;
+
+fn parse(css: &str) -> Option<Value> {
+    parse_color(&tokenize(css))
+}
+
+fn rgba(red: f32, green: f32, blue: f32, alpha: f32) -> Option<Value> {
+    Some(Value::Color(Color::new(
+        red / 255.0,
+        green / 255.0,
+        blue / 255.0,
+        alpha,
+    )))
+}
+
+fn color(space: ColorSpace, channels: [f32; 3], alpha: f32) -> Option<Value> {
+    Some(Value::Color(Color::new_with_space(space, channels, alpha)))
+}
+
+// The color as drawn, after conversion and gamut mapping to sRGB, rounded to
+// whole channels.
+fn srgb(css: &str) -> [u8; 3] {
+    let color = match parse(css) {
+        Some(Value::Color(color)) => format!("{0:?}", color.to_srgb()),
+        _ => panic!("{0} is not a color", css),
+    };
+    let channels: Vec<u8> = color
+        .split(|c: char| !c.is_ascii_digit() && c != '.' && c != '-' && c != 'e')
+        .filter_map(|number| number.parse::<f32>().ok())
+        .map(|channel| (channel * 255.0).round() as u8)
+        .collect();
+    [channels[0], channels[1], channels[2]]
+}
+
+#[test]
+fn hex_colors() {
+    assert_eq!(parse("#f00"), rgba(255.0, 0.0, 0.0, 1.0));
+    assert_eq!(parse("#ff000080"), rgba(255.0, 0.0, 0.0, 128.0 / 255.0));
+    assert_eq!(parse("#FF00"), rgba(255.0, 255.0, 0.0, 0.0));
+    assert_eq!(parse("#fffff"), None);
+    assert_eq!(parse("#ggg"), None);
+}
+
+#[test]
+fn keywords() {
+    assert_eq!(parse("RED"), rgba(255.0, 0.0, 0.0, 1.0));
+    assert_eq!(parse("transparent"), rgba(0.0, 0.0, 0.0, 0.0));
+    assert_eq!(parse("currentColor"), Some(Value::CurrentColor));
+    assert_eq!(parse("LightSlateGrey"), rgba(119.0, 136.0, 153.0, 1.0));
+    assert_eq!(parse("rebeccapurple"), rgba(102.0, 51.0, 153.0, 1.0));
+    assert_eq!(parse("nope"), None);
+}
+
+#[test]
+fn rgb_legacy_and_modern_syntax() {
+    assert_eq!(parse("rgb(255, 0, 0)"), rgba(255.0, 0.0, 0.0, 1.0));
+    assert_eq!(parse("rgb(100%, 0%, 0%)"), rgba(255.0, 0.0, 0.0, 1.0));
+    assert_eq!(parse("rgba(255, 0, 0, 0.5)"), rgba(255.0, 0.0, 0.0, 0.5));
+    assert_eq!(parse("rgb(255 0 0 / 50%)"), rgba(255.0, 0.0, 0.0, 0.5));
+    assert_eq!(parse("rgb(none 0 0)"), rgba(0.0, 0.0, 0.0, 1.0));
+}
+
+#[test]
+fn rgb_channels_and_alpha_are_clamped() {
+    assert_eq!(parse("rgb(300 -10 0)"), rgba(255.0, 0.0, 0.0, 1.0));
+    assert_eq!(parse("rgb(255 0 0 / 2)"), rgba(255.0, 0.0, 0.0, 1.0));
+}
+
+#[test]
+fn invalid_rgb_is_rejected() {
+    // Legacy syntax can't mix numbers and percentages, use `none` or `/`.
+    assert_eq!(parse("rgb(255, 0%, 0)"), None);
+    assert_eq!(parse("rgb(none, 0, 0)"), None);
+    assert_eq!(parse("rgb(255, 0, 0 / 1)"), None);
+    assert_eq!(parse("rgb(255 0, 0)"), None);
+    assert_eq!(parse("rgb(1px 0 0)"), None);
+    assert_eq!(parse("rgb(255 0)"), None);
+    assert_eq!(parse("rgb(255 0 0 0)"), None);
+}
+
+#[test]
+fn hsl_and_hwb() {
+    assert_eq!(parse("hsl(120, 100%, 50%)"), rgba(0.0, 255.0, 0.0, 1.0));
+    assert_eq!(parse("hsl(120 100 50)"), rgba(0.0, 255.0, 0.0, 1.0));
+    assert_eq!(
+        parse("hsl(120deg 100% 50% / 0.25)"),
+        rgba(0.0, 255.0, 0.0, 0.25)
+    );
+    assert_eq!(
+        parse("hsla(0.5turn, 100%, 25%, 1)"),
+        rgba(0.0, 127.5, 127.5, 1.0)
+    );
+    assert_eq!(parse("hsl(120, 100, 50)"), None);
+
+    assert_eq!(parse("hwb(0 0% 0%)"), rgba(255.0, 0.0, 0.0, 1.0));
+    // Whiteness and blackness adding up to more than 100% make a gray.
+    assert_eq!(parse("hwb(0 60% 60%)"), rgba(127.5, 127.5, 127.5, 1.0));
+    assert_eq!(parse("hwb(0, 0%, 0%)"), None);
+}
+
+#[test]
+fn modern_color_spaces_keep_their_space() {
+    assert_eq!(
+        parse("lab(50% 40 59.5)"),
+        color(ColorSpace::Lab, [50.0, 40.0, 59.5], 1.0)
+    );
+    assert_eq!(
+        parse("lch(50% 70 60 / 0.5)"),
+        color(ColorSpace::Lch, [50.0, 70.0, 60.0], 0.5)
+    );
+    assert_eq!(
+        parse("oklab(0.5 0.1 -0.1)"),
+        color(ColorSpace::Oklab, [0.5, 0.1, -0.1], 1.0)
+    );
+    assert_eq!(
+        parse("oklch(70% 0.4 none)"),
+        color(ColorSpace::Oklch, [0.7, 0.4, 0.0], 1.0)
+    );
+    assert_eq!(
+        parse("color(display-p3 1 0 0)"),
+        color(ColorSpace::DisplayP3, [1.0, 0.0, 0.0], 1.0)
+    );
+    assert_eq!(parse("color(srgb 1 0 0 / 0.5)"), rgba(255.0, 0.0, 0.0, 0.5));
+    assert_eq!(parse("color(rec2020 1 0 0)"), None);
+    assert_eq!(parse("color(display-p3, 1, 0, 0)"), None);
+}
+
+#[test]
+fn conversion_to_srgb() {
+    assert_eq!(srgb("oklch(62.8% 0.2577 29.23)"), [255, 0, 0]);
+    assert_eq!(srgb("lab(50% 40 59.5)"), [191, 87, 0]);
+    assert_eq!(srgb("color(srgb-linear 0.5 0.5 0.5)"), [188, 188, 188]);
+}
+
+#[test]
+fn out_of_gamut_colors_are_mapped_into_srgb() {
+    assert_eq!(srgb("color(display-p3 1 0 0)"), [255, 11, 12]);
+    assert_eq!(srgb("oklch(70% 0.4 150)"), [0, 194, 72]);
+    assert_eq!(srgb("lch(100% 50 0)"), [255, 255, 255]);
+}
+
+#[test]
+fn color_mix() {
+    assert_eq!(
+        parse("color-mix(in srgb, red, blue)"),
+        rgba(127.5, 0.0, 127.5, 1.0)
+    );
+    assert_eq!(
+        parse("color-mix(in srgb, red 25%, blue)"),
+        rgba(63.75, 0.0, 191.25, 1.0)
+    );
+    // Hue methods only apply to polar spaces.
+    assert_eq!(parse("color-mix(in srgb longer hue, red, blue)"), None);
+    assert_eq!(parse("color-mix(in srgb, red 0%, blue 0%)"), None);
+}
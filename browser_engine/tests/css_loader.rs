@@ -0,0 +1,70 @@
+extern crate browser_engine;
+use browser_engine::css_loader::resolve_url;
+
+#[test]
+fn relative_to_a_file() {
+    assert_eq!(
+        resolve_url("http://x.com/css/main.css", "a.css"),
+        "http://x.com/css/a.css"
+    );
+    assert_eq!(resolve_url("css/main.css", "a.css"), "css/a.css");
+    assert_eq!(resolve_url("main.css", "a.css"), "a.css");
+    assert_eq!(resolve_url("/css/main.css", "../a.css"), "/a.css");
+}
+
+#[test]
+fn relative_to_a_directory() {
+    assert_eq!(
+        resolve_url("http://x.com/css/", "a.css"),
+        "http://x.com/css/a.css"
+    );
+    assert_eq!(resolve_url("css/", "a.css"), "css/a.css");
+    assert_eq!(resolve_url("http://x.com/", "a.css"), "http://x.com/a.css");
+    assert_eq!(resolve_url("http://x.com", "a.css"), "http://x.com/a.css");
+}
+
+#[test]
+fn dot_segments() {
+    assert_eq!(
+        resolve_url("http://x.com/a/b/c.css", "./../d.css"),
+        "http://x.com/a/d.css"
+    );
+    assert_eq!(
+        resolve_url("http://x.com/a.css", "../../b.css"),
+        "http://x.com/b.css"
+    );
+    assert_eq!(resolve_url("css/main.css", "../../a.css"), "../a.css");
+}
+
+#[test]
+fn absolute_paths() {
+    assert_eq!(
+        resolve_url("http://x.com/css/main.css", "/a.css"),
+        "http://x.com/a.css"
+    );
+    assert_eq!(resolve_url("css/main.css", "/a.css"), "/a.css");
+}
+
+#[test]
+fn protocol_relative() {
+    assert_eq!(
+        resolve_url("https://x.com/css/main.css", "//cdn.com/a.css"),
+        "https://cdn.com/a.css"
+    );
+    assert_eq!(
+        resolve_url("css/main.css", "//cdn.com/a.css"),
+        "//cdn.com/a.css"
+    );
+}
+
+#[test]
+fn absolute_urls() {
+    assert_eq!(
+        resolve_url("css/main.css", "http://y.com/a.css"),
+        "http://y.com/a.css"
+    );
+    assert_eq!(
+        resolve_url("http://x.com/a.css", "file:///b.css"),
+        "file:///b.css"
+    );
+}
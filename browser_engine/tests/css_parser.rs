@@ -0,0 +1,209 @@
+extern crate browser_engine;
+use browser_engine::css::{Separator, Unit, Value};
+use browser_engine::css_parser::CssParser;
+use browser_engine::serializer::{serialize_stylesheet, serialize_value, Mode};
+
+fn parse(css: &str) -> String {
+    serialize_stylesheet(&CssParser::new(css).parse_stylesheet(), Mode::Minify)
+}
+
+#[test]
+fn unknown_at_rule_stops_at_the_end_of_a_media_block() {
+    assert_eq!(
+        parse("@media print { @foo } b {color:red} c {color:blue}"),
+        "@media print{}b{color:#f00}c{color:#00f}"
+    );
+}
+
+#[test]
+fn unfinished_rule_stops_at_the_end_of_a_media_block() {
+    // The stray `}` at the top level becomes part of the next prelude, which
+    // drops `c`.
+    assert_eq!(
+        parse("@media print { a } b {color:red} } c {color:blue}"),
+        "@media print{}b{color:#f00}"
+    );
+}
+
+#[test]
+fn unknown_at_rule_in_a_declaration_block() {
+    assert_eq!(parse("a { @foo } b {color:red}"), "a{}b{color:#f00}");
+}
+
+#[test]
+fn values_outside_a_property_grammar_are_dropped() {
+    let css = "a { width: red; height: bogus; display: 12px; border-top-style: 5px; \
+               margin-top: foo(1px); padding-left: auto }";
+    assert_eq!(parse(css), "a{}");
+}
+
+#[test]
+fn line_widths_do_not_take_percentages() {
+    let css = "a { border-top-width: 10%; border-left-width: calc(1px + 5%); \
+               border-right-width: THICK; border-bottom-width: calc(1em + 2px) }";
+    assert_eq!(
+        parse(css),
+        "a{border-right-width:thick;border-bottom-width:calc(1em + 2px)}"
+    );
+    assert_eq!(parse("a { border: 10% solid }"), "a{}");
+}
+
+#[test]
+fn values_inside_a_property_grammar_are_kept() {
+    let css = "a { width: AUTO; display: Inline-Block; border-top-style: dashed; \
+               font-weight: 700; background-repeat: repeat-x, no-repeat round }";
+    assert_eq!(
+        parse(css),
+        "a{width:auto;display:inline-block;border-top-style:dashed;font-weight:700;\
+         background-repeat:repeat-x,no-repeat round}"
+    );
+}
+
+// The value of a declaration, and the value it parses to once serialized.
+fn round_trip(declaration: &str) -> (Value, Value) {
+    let parse = |css: &str| {
+        CssParser::new(css).parse_declaration_list()[0]
+            .get_value()
+            .clone()
+    };
+    let value = parse(declaration);
+    let reparsed = parse(&format!(
+        "{0}: {1}",
+        &declaration[..declaration.find(':').unwrap()],
+        serialize_value(&value, Mode::Minify)
+    ));
+    (value, reparsed)
+}
+
+#[test]
+fn keywords_round_trip() {
+    let (value, reparsed) = round_trip("background-repeat: REPEAT-X");
+    assert_eq!(value, Value::Keyword(String::from("repeat-x")));
+    assert_eq!(value, reparsed);
+}
+
+#[test]
+fn identifiers_that_are_not_keywords_keep_their_case() {
+    let (value, reparsed) = round_trip("font-family: Fira Sans, SERIF");
+    assert_eq!(
+        value,
+        Value::List(
+            vec![
+                Value::List(
+                    vec![
+                        Value::Keyword(String::from("Fira")),
+                        Value::Keyword(String::from("Sans")),
+                    ],
+                    Separator::Space
+                ),
+                Value::Keyword(String::from("serif")),
+            ],
+            Separator::Comma
+        )
+    );
+    assert_eq!(value, reparsed);
+}
+
+#[test]
+fn strings_round_trip() {
+    let (value, reparsed) = round_trip("font-family: \"Say \\\"hi\\\"\\A\"");
+    assert_eq!(value, Value::String(String::from("Say \"hi\"\n")));
+    assert_eq!(value, reparsed);
+}
+
+#[test]
+fn urls_round_trip() {
+    let (value, reparsed) = round_trip("background-image: url(a\\ b.png)");
+    assert_eq!(value, Value::Url(String::from("a b.png")));
+    assert_eq!(value, reparsed);
+
+    let (value, reparsed) = round_trip("background-image: url(\"c.png\")");
+    assert_eq!(value, Value::Url(String::from("c.png")));
+    assert_eq!(value, reparsed);
+}
+
+#[test]
+fn lists_round_trip() {
+    let (value, reparsed) = round_trip("background-position: 10px 20%, left top");
+    assert_eq!(
+        value,
+        Value::List(
+            vec![
+                Value::List(
+                    vec![Value::Length(10.0, Unit::Px), Value::Percentage(20.0)],
+                    Separator::Space
+                ),
+                Value::List(
+                    vec![
+                        Value::Keyword(String::from("left")),
+                        Value::Keyword(String::from("top")),
+                    ],
+                    Separator::Space
+                ),
+            ],
+            Separator::Comma
+        )
+    );
+    assert_eq!(value, reparsed);
+}
+
+#[test]
+fn functions_round_trip() {
+    let (value, reparsed) = round_trip("background-image: Linear-Gradient(to right, 1.5em, 2)");
+    assert_eq!(
+        value,
+        Value::Function(
+            String::from("linear-gradient"),
+            vec![
+                Value::List(
+                    vec![
+                        Value::Keyword(String::from("to")),
+                        Value::Keyword(String::from("right")),
+                    ],
+                    Separator::Space
+                ),
+                Value::Length(1.5, Unit::Em),
+                Value::Number(2.0),
+            ]
+        )
+    );
+    assert_eq!(value, reparsed);
+}
+
+// The value a single declaration parses to, if it is valid.
+fn value(declaration: &str) -> Option<Value> {
+    CssParser::new(declaration)
+        .parse_declaration_list()
+        .first()
+        .map(|declaration| declaration.get_value().clone())
+}
+
+#[test]
+fn numbers_keep_their_sign_fraction_and_exponent() {
+    assert_eq!(
+        value("margin-left: -5px"),
+        Some(Value::Length(-5.0, Unit::Px))
+    );
+    assert_eq!(value("width: 1.5em"), Some(Value::Length(1.5, Unit::Em)));
+    assert_eq!(value("width: +.5e1px"), Some(Value::Length(5.0, Unit::Px)));
+    assert_eq!(value("width: 25E-1in"), Some(Value::Length(2.5, Unit::In)));
+    assert_eq!(value("width: 12.5%"), Some(Value::Percentage(12.5)));
+    assert_eq!(value("line-height: 1.25"), Some(Value::Number(1.25)));
+    assert_eq!(value("margin-left: 0"), Some(Value::Length(0.0, Unit::Px)));
+}
+
+#[test]
+fn units_match_case_insensitively() {
+    assert_eq!(value("width: 2REM"), Some(Value::Length(2.0, Unit::Rem)));
+    assert_eq!(value("width: 3q"), Some(Value::Length(3.0, Unit::Q)));
+    assert_eq!(value("width: 1VMax"), Some(Value::Length(1.0, Unit::Vmax)));
+}
+
+#[test]
+fn unknown_units_are_rejected() {
+    assert_eq!(value("width: 10pxx"), None);
+    assert_eq!(value("width: 10furlongs"), None);
+    assert_eq!(value("width: 10 px"), None);
+    assert_eq!(value("width: 10"), None);
+    assert_eq!(value("width: 1.5.5px"), None);
+}
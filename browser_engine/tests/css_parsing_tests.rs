@@ -0,0 +1,328 @@
+// Runs the JSON fixtures in tests/css-parsing-tests, which use the format of
+// https://github.com/SimonSapin/css-parsing-tests: an array alternating
+// between an input and its expected result.
+extern crate browser_engine;
+extern crate serde_json;
+
+use std::fs;
+use std::path::Path;
+
+use browser_engine::css_parser::CssParser;
+use browser_engine::css_tokenizer::{serialize_identifier, serialize_string, tokenize, Token};
+use browser_engine::serializer::{serialize_declaration, serialize_stylesheet, Mode};
+use browser_engine::source::Span;
+use serde_json::{json, Value as Json};
+
+#[test]
+fn component_value_list() {
+    run(
+        "component_value_list.json",
+        |input| {
+            let tokens = tokenize(input);
+            Json::Array(component_values(input, &tokens, &mut 0, None))
+        },
+        Json::clone,
+    );
+}
+
+// Our parser drops at-rules and invalid declarations instead of returning
+// them, so the expected declarations are compared by parsing them again on
+// their own.
+#[test]
+fn declaration_list() {
+    run("declaration_list.json", declarations, reparse_declarations);
+}
+
+// Likewise, the expected rules are compared by parsing the stylesheet they
+// make up.
+#[test]
+fn rule_list() {
+    run("rule_list.json", stylesheet, reparse_rules);
+}
+
+fn run(file: &str, parse: impl Fn(&str) -> Json, expected: impl Fn(&Json) -> Json) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("css-parsing-tests")
+        .join(file);
+    let fixtures: Json = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+    let fixtures = fixtures.as_array().unwrap();
+
+    let mut failures = Vec::new();
+    for case in fixtures.chunks(2) {
+        let input = case[0].as_str().unwrap();
+        let expected = expected(&case[1]);
+        let actual = parse(input);
+
+        if !matches(&actual, &expected) {
+            failures.push(format!(
+                "input:    {0:?}\nexpected: {1}\nactual:   {2}",
+                input, expected, actual
+            ));
+        }
+    }
+
+    assert!(failures.is_empty(), "\n{0}\n", failures.join("\n\n"));
+}
+
+// Numbers only have to agree to single precision.
+fn matches(actual: &Json, expected: &Json) -> bool {
+    match (actual, expected) {
+        (Json::Number(actual), Json::Number(expected)) => {
+            let (actual, expected) = (actual.as_f64().unwrap(), expected.as_f64().unwrap());
+            (actual - expected).abs() <= 1e-6 * expected.abs().max(1.0)
+        }
+        (Json::Array(actual), Json::Array(expected)) => {
+            actual.len() == expected.len()
+                && actual
+                    .iter()
+                    .zip(expected)
+                    .all(|(actual, expected)| matches(actual, expected))
+        }
+        _ => actual == expected,
+    }
+}
+
+fn component_values(
+    input: &str,
+    tokens: &[(Token, Span)],
+    index: &mut usize,
+    closer: Option<&Token>,
+) -> Vec<Json> {
+    let mut values = Vec::new();
+
+    while let Some((token, span)) = tokens.get(*index) {
+        *index += 1;
+
+        let value = match *token {
+            _ if Some(token) == closer => return values,
+            Token::Ident(ref name) => json!(["ident", name]),
+            Token::Function(ref name) => {
+                let mut function = vec![json!("function"), json!(name)];
+                function.extend(component_values(
+                    input,
+                    tokens,
+                    index,
+                    Some(&Token::CloseParen),
+                ));
+                Json::Array(function)
+            }
+            Token::AtKeyword(ref name) => json!(["at-keyword", name]),
+            Token::Hash(ref name) => json!(["hash", name, "unrestricted"]),
+            Token::IdHash(ref name) => json!(["hash", name, "id"]),
+            Token::QuotedString(ref string) => json!(["string", string]),
+            Token::BadString => json!(["error", "bad-string"]),
+            Token::Url(ref url) => json!(["url", url]),
+            Token::BadUrl => json!(["error", "bad-url"]),
+            Token::Delim(c) => json!(c.to_string()),
+            Token::Number(ref n) => {
+                json!([
+                    "number",
+                    repr(&input[span.start..span.end]),
+                    n.value,
+                    kind(n.is_integer)
+                ])
+            }
+            Token::Percentage(ref n) => json!([
+                "percentage",
+                repr(&input[span.start..span.end]),
+                n.value,
+                kind(n.is_integer)
+            ]),
+            Token::Dimension(ref n, ref unit) => json!([
+                "dimension",
+                repr(&input[span.start..span.end]),
+                n.value,
+                kind(n.is_integer),
+                unit
+            ]),
+            Token::Whitespace => json!(" "),
+            Token::Cdo => json!("<!--"),
+            Token::Cdc => json!("-->"),
+            Token::Colon => json!(":"),
+            Token::Semicolon => json!(";"),
+            Token::Comma => json!(","),
+            Token::OpenCurly | Token::OpenSquare | Token::OpenParen => {
+                let (name, closer) = match *token {
+                    Token::OpenCurly => ("{}", Token::CloseCurly),
+                    Token::OpenSquare => ("[]", Token::CloseSquare),
+                    _ => ("()", Token::CloseParen),
+                };
+                let mut block = vec![json!(name)];
+                block.extend(component_values(input, tokens, index, Some(&closer)));
+                Json::Array(block)
+            }
+            Token::CloseCurly => json!(["error", "}"]),
+            Token::CloseSquare => json!(["error", "]"]),
+            Token::CloseParen => json!(["error", ")"]),
+        };
+        values.push(value);
+    }
+
+    values
+}
+
+// The source text of the number at the start of a numeric token.
+fn repr(source: &str) -> String {
+    let chars: Vec<char> = source.chars().collect();
+    let digits = |mut i: usize| {
+        while chars.get(i).is_some_and(|c| c.is_ascii_digit()) {
+            i += 1;
+        }
+        i
+    };
+
+    let mut end = match chars[0] {
+        '+' | '-' => 1,
+        _ => 0,
+    };
+    end = digits(end);
+    if chars.get(end) == Some(&'.') && chars.get(end + 1).is_some_and(|c| c.is_ascii_digit()) {
+        end = digits(end + 1);
+    }
+    if let Some('e') | Some('E') = chars.get(end) {
+        let sign = match chars.get(end + 1) {
+            Some('+') | Some('-') => 1,
+            _ => 0,
+        };
+        if chars
+            .get(end + 1 + sign)
+            .is_some_and(|c| c.is_ascii_digit())
+        {
+            end = digits(end + 1 + sign);
+        }
+    }
+
+    chars[..end].iter().collect()
+}
+
+fn kind(is_integer: bool) -> &'static str {
+    if is_integer {
+        "integer"
+    } else {
+        "number"
+    }
+}
+
+fn declarations(css: &str) -> Json {
+    CssParser::new(css)
+        .parse_declaration_list()
+        .iter()
+        .map(|declaration| json!(serialize_declaration(declaration, Mode::Minify)))
+        .collect()
+}
+
+fn stylesheet(css: &str) -> Json {
+    json!(serialize_stylesheet(
+        &CssParser::new(css).parse_stylesheet(),
+        Mode::Minify
+    ))
+}
+
+fn reparse_declarations(expected: &Json) -> Json {
+    let mut css = String::new();
+
+    for item in expected.as_array().unwrap() {
+        if item[0].as_str().unwrap() == "declaration" {
+            css.push_str(&serialize_identifier(item[1].as_str().unwrap()));
+            css.push(':');
+            css.push_str(&to_css(item[2].as_array().unwrap()));
+            if item[3].as_bool().unwrap() {
+                css.push_str("!important");
+            }
+            css.push(';');
+        }
+    }
+
+    declarations(&css)
+}
+
+fn reparse_rules(expected: &Json) -> Json {
+    let mut css = String::new();
+
+    for item in expected.as_array().unwrap() {
+        match item[0].as_str().unwrap() {
+            "qualified rule" => {
+                css.push_str(&to_css(item[1].as_array().unwrap()));
+                css.push('{');
+                css.push_str(&to_css(item[2].as_array().unwrap()));
+                css.push('}');
+            }
+            "at-rule" => {
+                css.push('@');
+                css.push_str(&serialize_identifier(item[1].as_str().unwrap()));
+                css.push(' ');
+                css.push_str(&to_css(item[2].as_array().unwrap()));
+                match item[3].as_array() {
+                    Some(block) => css.push_str(&format!("{{{0}}}", to_css(block))),
+                    None => css.push(';'),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    stylesheet(&css)
+}
+
+// Writes component values back as CSS. An empty comment between each keeps
+// neighbouring tokens from running together.
+fn to_css(values: &[Json]) -> String {
+    let values: Vec<String> = values.iter().map(component_value_to_css).collect();
+    values.join("/**/")
+}
+
+fn component_value_to_css(value: &Json) -> String {
+    let value = match value {
+        Json::String(punctuation) => return punctuation.clone(),
+        Json::Array(value) => value,
+        _ => panic!("unexpected component value {0}", value),
+    };
+    let string = |i: usize| value[i].as_str().unwrap();
+
+    match string(0) {
+        "ident" => serialize_identifier(string(1)),
+        "at-keyword" => format!("@{0}", serialize_identifier(string(1))),
+        "hash" => format!("#{0}", serialize_hash(string(1))),
+        "string" => serialize_string(string(1)),
+        "url" => format!("url({0})", serialize_url(string(1))),
+        "number" => string(1).to_string(),
+        "percentage" => format!("{0}%", string(1)),
+        "dimension" => format!("{0}{1}", string(1), serialize_identifier(string(4))),
+        "function" => format!(
+            "{0}({1})",
+            serialize_identifier(string(1)),
+            to_css(&value[2..])
+        ),
+        "{}" => format!("{{{0}}}", to_css(&value[1..])),
+        "[]" => format!("[{0}]", to_css(&value[1..])),
+        "()" => format!("({0})", to_css(&value[1..])),
+        "error" => match string(1) {
+            "bad-url" => String::from("url(bad url)"),
+            "bad-string" => String::from("\"\n"),
+            closer => closer.to_string(),
+        },
+        other => panic!("unexpected component value {0}", other),
+    }
+}
+
+fn serialize_hash(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c >= '\u{80}' {
+                c.to_string()
+            } else {
+                format!("\\{0}", c)
+            }
+        })
+        .collect()
+}
+
+fn serialize_url(url: &str) -> String {
+    url.chars()
+        .map(|c| match c {
+            ' ' | '\t' | '\n' | '"' | '\'' | '(' | ')' | '\\' => format!("\\{0:x} ", c as u32),
+            c => c.to_string(),
+        })
+        .collect()
+}
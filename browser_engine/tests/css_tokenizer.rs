@@ -0,0 +1,39 @@
+extern crate browser_engine;
+use browser_engine::css_tokenizer::{tokenize, Token};
+
+use std::borrow::Cow;
+
+#[test]
+fn tokens_borrow_the_input() {
+    let tokens = tokenize("a-b f( @media #x 'str' url(a.png) 10px");
+    let names: Vec<(&Cow<str>, bool)> = tokens
+        .iter()
+        .filter_map(|(token, _)| match *token {
+            Token::Ident(ref name)
+            | Token::Function(ref name)
+            | Token::AtKeyword(ref name)
+            | Token::IdHash(ref name)
+            | Token::QuotedString(ref name)
+            | Token::Url(ref name)
+            | Token::Dimension(_, ref name) => Some((name, matches!(name, Cow::Borrowed(_)))),
+            _ => None,
+        })
+        .collect();
+
+    assert_eq!(names.len(), 7);
+    assert!(names.iter().all(|&(_, borrowed)| borrowed));
+}
+
+#[test]
+fn escapes_are_unescaped_into_owned_strings() {
+    match tokenize("\\61 b 'a\\\nb'")[..] {
+        [(Token::Ident(ref name), _), (Token::Whitespace, _), (Token::QuotedString(ref string), _)] =>
+        {
+            assert_eq!(&name[..], "ab");
+            assert!(!matches!(name, Cow::Borrowed(_)));
+            assert_eq!(&string[..], "ab");
+            assert!(!matches!(string, Cow::Borrowed(_)));
+        }
+        ref other => panic!("unexpected tokens {0:?}", other),
+    }
+}
@@ -0,0 +1,88 @@
+extern crate browser_engine;
+use browser_engine::css_parser::CssParser;
+use browser_engine::html_parser::HtmlParser;
+use browser_engine::serializer::{serialize_value, Mode};
+use browser_engine::style::StyledNode;
+
+// The computed value of `property` on the root element and on its first
+// child, where a missing value is empty.
+fn values(css: &str, property: &str) -> (String, String) {
+    let nodes = HtmlParser::new("<div><p></p></div>").parse_nodes();
+    let stylesheet = CssParser::new(css).parse_stylesheet();
+    let root = StyledNode::new(&nodes[0], &stylesheet);
+
+    let value = |node: &StyledNode| {
+        if property.starts_with("--") {
+            let tokens = node.get_custom_properties().get(property);
+            tokens.map_or(String::new(), |tokens| {
+                tokens.iter().map(|token| token.to_string()).collect()
+            })
+        } else {
+            node.value(property)
+                .map_or(String::new(), |value| serialize_value(value, Mode::Minify))
+        }
+    };
+    (value(&root), value(&root.children[0]))
+}
+
+#[test]
+fn var_substitutes_the_computed_value() {
+    let (div, _) = values(
+        "div { --w: 10px; --h: var(--w); height: var(--h) }",
+        "height",
+    );
+    assert_eq!(div, "10px");
+}
+
+#[test]
+fn substituted_tokens_never_merge() {
+    // `10` and `px` stay a number and an identifier, which calc() rejects,
+    // so width falls back to its initial value.
+    let (div, _) = values("div { --w: 10; width: calc(var(--w)px) }", "width");
+    assert_eq!(div, "auto");
+    let (div, _) = values("div { --w: 10; width: calc(var(--w) * 1px) }", "width");
+    assert_eq!(div, "calc(10*1px)");
+}
+
+#[test]
+fn fallbacks_apply_only_to_missing_properties() {
+    let (div, _) = values("div { width: var(--missing, 5px) }", "width");
+    assert_eq!(div, "5px");
+    let (div, _) = values("div { --w: 1px; width: var(--w, 5px) }", "width");
+    assert_eq!(div, "1px");
+    let (div, _) = values("div { width: var(--a, var(--b, 7px)) }", "width");
+    assert_eq!(div, "7px");
+}
+
+#[test]
+fn custom_properties_inherit() {
+    let (div, p) = values("div { --color: red } p { color: var(--color) }", "color");
+    assert_eq!((div.as_str(), p.as_str()), ("", "#f00"));
+
+    let (div, p) = values("div { --x: a } p { --x: b }", "--x");
+    assert_eq!((div.as_str(), p.as_str()), ("a", "b"));
+}
+
+#[test]
+fn every_property_in_a_cycle_is_invalid() {
+    // --d only refers to the cycle, so it takes its fallback.
+    let css = "div { --a: var(--b); --b: var(--c); --c: var(--a); --d: var(--a, 1px); --e: 2px }";
+    assert_eq!(values(css, "--a").0, "");
+    assert_eq!(values(css, "--b").0, "");
+    assert_eq!(values(css, "--c").0, "");
+    assert_eq!(values(css, "--d").0.trim(), "1px");
+    assert_eq!(values(css, "--e").0, "2px");
+}
+
+#[test]
+fn invalid_at_computed_value_time_acts_as_unset() {
+    // width is not inherited, so it becomes its initial value; color is, so
+    // the paragraph takes its parent's.
+    let (div, _) = values("div { --w: red; width: var(--w) }", "width");
+    assert_eq!(div, "auto");
+    let (_, p) = values(
+        "div { color: blue } p { --c: 1px; color: var(--c) }",
+        "color",
+    );
+    assert_eq!(p, "#00f");
+}
@@ -0,0 +1,109 @@
+extern crate browser_engine;
+use browser_engine::dom::Node;
+use browser_engine::html_parser::HtmlParser;
+
+fn element(style: &str) -> Node {
+    let mut nodes = HtmlParser::new(&format!("<div style=\"{0}\"></div>", style)).parse_nodes();
+    nodes.remove(0)
+}
+
+fn style(node: &Node) -> &str {
+    node.get_element().unwrap().get_attribute("style").unwrap()
+}
+
+#[test]
+fn style_attribute_is_parsed() {
+    let node = element("color: red; margin: 1px 2px");
+    let element = node.get_element().unwrap();
+
+    assert_eq!(element.get_style().len(), 5);
+    assert!(element.get_style_property("MARGIN-LEFT").is_some());
+    assert!(element.get_style_property("width").is_none());
+}
+
+#[test]
+fn stray_braces_do_not_end_the_style_attribute() {
+    let node = element("color: red; } width: 1px; height: 2px");
+    let element = node.get_element().unwrap();
+
+    assert!(element.get_style_property("color").is_some());
+    assert!(element.get_style_property("width").is_none());
+    assert!(element.get_style_property("height").is_some());
+}
+
+#[test]
+fn set_style_property() {
+    let mut node = element("color: red");
+
+    assert!(node
+        .get_element_mut()
+        .unwrap()
+        .set_style_property("color", "blue", false));
+    assert!(node
+        .get_element_mut()
+        .unwrap()
+        .set_style_property("Width", "10px", true));
+    assert_eq!(style(&node), "color:#00f;width:10px!important");
+}
+
+#[test]
+fn set_style_replaces_the_declarations() {
+    let mut node = element("color: red");
+    assert!(node
+        .get_element()
+        .unwrap()
+        .get_style_property("color")
+        .is_some());
+
+    node.get_element_mut().unwrap().set_style("width: 10px");
+    assert!(node
+        .get_element()
+        .unwrap()
+        .get_style_property("color")
+        .is_none());
+    assert!(node
+        .get_element()
+        .unwrap()
+        .get_style_property("width")
+        .is_some());
+    assert_eq!(style(&node), "width: 10px");
+}
+
+#[test]
+fn set_style_property_rejects_invalid_values() {
+    let mut node = element("color: red");
+    let mut set = |property: &str, value: &str| {
+        node.get_element_mut()
+            .unwrap()
+            .set_style_property(property, value, false)
+    };
+
+    assert!(!set("width", "red"));
+    assert!(!set("color", "blue !important"));
+    assert!(!set("color", "blue; width: 10px"));
+    assert!(!set("color", "blue; color: green"));
+    assert!(!set("color", "; color: green"));
+    assert!(!set("color", "blue }"));
+    assert!(!set("--x", "a; b"));
+    assert_eq!(style(&node), "color: red");
+}
+
+#[test]
+fn set_and_remove_shorthands() {
+    let mut node = element("");
+
+    assert!(node
+        .get_element_mut()
+        .unwrap()
+        .set_style_property("margin", "1px", false));
+    assert_eq!(node.get_element().unwrap().get_style().len(), 4);
+    assert!(node
+        .get_element_mut()
+        .unwrap()
+        .remove_style_property("margin"));
+    assert!(!node
+        .get_element_mut()
+        .unwrap()
+        .remove_style_property("margin"));
+    assert_eq!(style(&node), "");
+}
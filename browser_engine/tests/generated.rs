@@ -0,0 +1,113 @@
+extern crate browser_engine;
+use browser_engine::css_parser::CssParser;
+use browser_engine::html_parser::HtmlParser;
+use browser_engine::style::StyledNode;
+
+// The generated boxes of the document, in tree order, as `pseudo:text`.
+fn generate(html: &str, css: &str) -> Vec<String> {
+    let nodes = HtmlParser::new(html).parse_nodes();
+    let stylesheet = CssParser::new(css).parse_stylesheet();
+    let root = StyledNode::new(&nodes[0], &stylesheet);
+
+    let mut boxes = Vec::new();
+    collect(&root, &mut boxes);
+    boxes
+}
+
+fn collect(node: &StyledNode, boxes: &mut Vec<String>) {
+    if let Some(pseudo_element) = node.get_pseudo_element() {
+        let content = node.get_content().unwrap_or("");
+        boxes.push(format!("{0:?}{1}", pseudo_element, content));
+    }
+    for child in &node.children {
+        collect(child, boxes);
+    }
+}
+
+#[test]
+fn before_and_after_need_content() {
+    let boxes = generate(
+        "<div><p></p><p class=\"x\"></p></div>",
+        "p::before { content: \"<\" } p.x::after { content: \">\" } p::after { color: red }",
+    );
+    assert_eq!(boxes, ["::before<", "::before<", "::after>"]);
+}
+
+#[test]
+fn none_and_normal_generate_no_box() {
+    let boxes = generate(
+        "<div><p></p></div>",
+        "p::before { content: none } p::after { content: normal }",
+    );
+    assert!(boxes.is_empty());
+}
+
+#[test]
+fn attr_reads_the_originating_element() {
+    let boxes = generate(
+        "<div><a href=\"/x\" title=\"X\"></a></div>",
+        "a::after { content: \" (\" attr(href) \")\" attr(missing) }",
+    );
+    assert_eq!(boxes, ["::after (/x)"]);
+}
+
+#[test]
+fn counters_reset_increment_and_set() {
+    let boxes = generate(
+        "<div><h2></h2><h2></h2><h2 class=\"x\"></h2><h2></h2></div>",
+        "div { counter-reset: Section 4 } h2 { counter-increment: Section } \
+         h2.x { counter-set: Section 10 } h2::before { content: counter(Section) }",
+    );
+    assert_eq!(
+        boxes,
+        ["::before5", "::before6", "::before10", "::before11"]
+    );
+}
+
+#[test]
+fn counter_names_keep_their_case() {
+    let boxes = generate(
+        "<div><p></p></div>",
+        "p { counter-increment: item 2 } p::before { content: counter(ITEM) }",
+    );
+    assert_eq!(boxes, ["::before0"]);
+}
+
+#[test]
+fn nested_counters_join_every_level() {
+    let boxes = generate(
+        "<ol><li><ol><li></li><li></li></ol></li></ol>",
+        "ol { counter-reset: n } li { display: block; counter-increment: n } \
+         li::before { content: counters(n, \".\", upper-roman) \" \" }",
+    );
+    assert_eq!(boxes, ["::beforeI ", "::beforeI.I ", "::beforeI.II "]);
+}
+
+#[test]
+fn quotes_nest_and_use_the_innermost_pairs() {
+    let boxes = generate(
+        "<div><q><q></q></q></div>",
+        "q::before { content: open-quote } q::after { content: close-quote } \
+         div { quotes: \"<\" \">\" \"{\" \"}\" }",
+    );
+    assert_eq!(boxes, ["::before<", "::before{", "::after}", "::after>"]);
+}
+
+#[test]
+fn default_quotes_are_curly() {
+    let boxes = generate(
+        "<div><q></q></div>",
+        "q::before { content: open-quote } q::after { content: no-close-quote close-quote }",
+    );
+    assert_eq!(boxes, ["::before\u{201C}", "::after"]);
+}
+
+#[test]
+fn list_items_increment_list_item_and_get_markers() {
+    let boxes = generate(
+        "<ul><li></li><li class=\"x\"></li><li></li></ul>",
+        "li { display: list-item } li.x { counter-increment: list-item 5 } \
+         li.x::marker { content: \"-\" counter(list-item) }",
+    );
+    assert_eq!(boxes, ["::marker1. ", "::marker-6", "::marker7. "]);
+}
@@ -0,0 +1,83 @@
+extern crate browser_engine;
+use browser_engine::css_parser::CssParser;
+use browser_engine::html_parser::HtmlParser;
+use browser_engine::layout::{layout_tree, Dimensions};
+use browser_engine::media::MediaEnvironment;
+use browser_engine::style::StyledNode;
+
+// The border box of a block laid out in an 800px wide containing block.
+fn border_box(style: &str) -> (f32, f32, f32) {
+    let nodes = HtmlParser::new(&format!("<div style=\"{0}\"></div>", style)).parse_nodes();
+    let stylesheet = CssParser::new("div { display: block }").parse_stylesheet();
+    let env = MediaEnvironment::new(800.0, 600.0);
+    let styled_node = StyledNode::new_with_media(&nodes[0], &stylesheet, &env);
+
+    let mut containing_block = Dimensions::default();
+    containing_block.content.width = 800.0;
+    let layout_box = layout_tree(&styled_node, containing_block, &env);
+
+    let border_box = layout_box.dimensions.border_box();
+    (
+        border_box.x,
+        border_box.width,
+        layout_box.dimensions.border.left,
+    )
+}
+
+#[test]
+fn calc_is_clamped_for_non_negative_properties() {
+    let (_, width, border) = border_box(
+        "width: 100px; padding-left: calc(10px - 30px); border-left-width: calc(0px - 5px)",
+    );
+    assert_eq!(width, 100.0);
+    assert_eq!(border, 0.0);
+}
+
+#[test]
+fn calc_may_be_negative_for_margins() {
+    let (x, _, _) = border_box("width: 100px; margin-left: calc(0px - 5px)");
+    assert_eq!(x, -5.0);
+}
+
+#[test]
+fn calc_divided_by_zero() {
+    let (_, width, _) = border_box("width: 100px; padding-left: calc(0px / 0)");
+    assert_eq!(width, 100.0);
+
+    let (_, width, _) = border_box("width: calc(0px / 0)");
+    assert_eq!(width, 800.0);
+
+    let (_, _, border) = border_box("width: 100px; border-left-width: calc(1px / 0)");
+    assert_eq!(border, f32::MAX);
+}
+
+#[test]
+fn em_refers_to_the_computed_font_size() {
+    let (_, width, _) = border_box("font-size: 20px; width: 2em; padding-left: 1em");
+    assert_eq!(width, 60.0);
+
+    let (_, width, _) = border_box("font-size: 150%; width: 10em");
+    assert_eq!(width, 240.0);
+}
+
+#[test]
+fn viewport_units_refer_to_the_media_environment() {
+    let (_, width, _) = border_box("width: 50vw; padding-left: 10vh");
+    assert_eq!(width, 460.0);
+}
+
+#[test]
+fn rem_refers_to_the_root_font_size() {
+    let html =
+        "<html style=\"font-size: 20px\"><div style=\"font-size: 10px; width: 2rem\"></div></html>";
+    let nodes = HtmlParser::new(html).parse_nodes();
+    let stylesheet = CssParser::new("html, div { display: block }").parse_stylesheet();
+    let env = MediaEnvironment::new(800.0, 600.0);
+    let styled_node = StyledNode::new_with_media(&nodes[0], &stylesheet, &env);
+
+    let mut containing_block = Dimensions::default();
+    containing_block.content.width = 800.0;
+    let layout_box = layout_tree(&styled_node, containing_block, &env);
+
+    assert_eq!(layout_box.children[0].dimensions.content.width, 40.0);
+}
@@ -0,0 +1,67 @@
+extern crate browser_engine;
+use browser_engine::css_tokenizer::{tokenize, Token};
+use browser_engine::media::{parse_media_list, MediaEnvironment};
+
+// Matched against a 1024x768 screen.
+fn matches(media: &str) -> bool {
+    let tokens: Vec<Token> = tokenize(media)
+        .into_iter()
+        .map(|(token, _)| token)
+        .collect();
+    parse_media_list(&tokens).matches(&MediaEnvironment::default())
+}
+
+#[test]
+fn media_types() {
+    assert!(matches(""));
+    assert!(matches("all"));
+    assert!(matches("screen, print"));
+    assert!(matches("only screen"));
+    assert!(!matches("print"));
+    assert!(matches("not print"));
+    assert!(!matches("not screen"));
+}
+
+#[test]
+fn features() {
+    assert!(matches("(min-width: 600px)"));
+    assert!(!matches("(max-width: 600px)"));
+    assert!(matches("screen and (orientation: landscape)"));
+    assert!(matches("(400px < width <= 1024px)"));
+    assert!(!matches("(width > 64em)"));
+    assert!(matches("(width) and (height)"));
+    assert!(matches(
+        "(max-width: 600px) or (prefers-color-scheme: light)"
+    ));
+    assert!(matches("not ((max-width: 600px) and (color-gamut: p3))"));
+}
+
+#[test]
+fn unknown_features_never_match() {
+    assert!(!matches("(unknown-feature)"));
+    assert!(!matches("not (unknown-feature)"));
+    assert!(!matches("not all and (unknown-feature)"));
+    assert!(!matches("not screen and (unknown-feature)"));
+    assert!(!matches("(orientation: sideways)"));
+    assert!(!matches("not (orientation: sideways)"));
+    assert!(!matches("(width > 10foo)"));
+    assert!(!matches("not (width > 10foo)"));
+    assert!(!matches("not (min-width: 600px) and (unknown-feature)"));
+    assert!(!matches("(unknown-feature) and (min-width: 600px)"));
+    assert!(matches("(unknown-feature) or (min-width: 600px)"));
+}
+
+#[test]
+fn unknown_parts_do_not_matter_when_the_result_is_known() {
+    assert!(!matches("(unknown-feature) and (max-width: 600px)"));
+    assert!(matches("not ((unknown-feature) and (max-width: 600px))"));
+    assert!(matches("not print and (unknown-feature)"));
+}
+
+#[test]
+fn invalid_queries_become_not_all() {
+    assert!(!matches("screen and"));
+    assert!(!matches("not"));
+    assert!(!matches("(min-width: 600px) and or (width)"));
+    assert!(matches("screen and, print, screen"));
+}
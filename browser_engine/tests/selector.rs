@@ -0,0 +1,286 @@
+extern crate browser_engine;
+use browser_engine::css::{Selector, Specificity};
+use browser_engine::css_parser::CssParser;
+use browser_engine::dom::Node;
+use browser_engine::html_parser::HtmlParser;
+use browser_engine::media::MediaEnvironment;
+use browser_engine::selector::{self, ElementRef};
+use browser_engine::xml_parser::XmlParser;
+
+// The ids of the elements `selectors` matches, in tree order.
+fn select(nodes: &[Node], selectors: &str) -> Vec<String> {
+    let stylesheet = CssParser::new(&format!("{0} {{}}", selectors)).parse_stylesheet();
+    let rules = stylesheet.style_rules(&MediaEnvironment::default());
+    assert_eq!(rules.len(), 1, "{0} is not a valid selector", selectors);
+
+    let mut ids = Vec::new();
+    collect(
+        &ElementRef::new(&nodes[0], None, 0),
+        rules[0].get_selectors(),
+        &mut ids,
+    );
+    ids
+}
+
+fn collect(element: &ElementRef, selectors: &[Selector], ids: &mut Vec<String>) {
+    let node = element.get_node();
+    if selectors.iter().any(|s| selector::matches(element, s)) {
+        let id = node.get_element().and_then(|element| element.get_id());
+        ids.push(id.map_or(String::new(), |id| id.to_string()));
+    }
+
+    for (index, child) in node.get_children().iter().enumerate() {
+        if child.get_element().is_some() {
+            collect(
+                &ElementRef::new(child, Some(element), index),
+                selectors,
+                ids,
+            );
+        }
+    }
+}
+
+fn html(source: &str) -> Vec<Node> {
+    HtmlParser::new(source).parse_nodes()
+}
+
+fn xml(source: &str) -> Vec<Node> {
+    XmlParser::new(source).parse_nodes().unwrap()
+}
+
+#[test]
+fn type_selectors_ignore_case_for_html_elements() {
+    let nodes = html("<div id=\"a\"><P id=\"b\"></P></div>");
+    assert_eq!(select(&nodes, "DIV"), ["a"]);
+    assert_eq!(select(&nodes, "p"), ["b"]);
+    assert_eq!(select(&nodes, "Div > P"), ["b"]);
+}
+
+#[test]
+fn type_selectors_keep_case_for_other_elements() {
+    let nodes = xml("<svg xmlns=\"http://www.w3.org/2000/svg\" id=\"a\">\
+                     <foreignObject id=\"b\"/><foreignobject id=\"c\"/></svg>");
+    assert_eq!(select(&nodes, "foreignObject"), ["b"]);
+    assert_eq!(select(&nodes, "foreignobject"), ["c"]);
+    assert!(select(&nodes, "SVG").is_empty());
+}
+
+#[test]
+fn xhtml_elements_ignore_case() {
+    let nodes =
+        xml("<html xmlns=\"http://www.w3.org/1999/xhtml\" id=\"a\"><body id=\"b\"/></html>");
+    assert_eq!(select(&nodes, "HTML, Body"), ["a", "b"]);
+}
+
+#[test]
+fn of_type_counts_only_siblings_in_the_same_namespace() {
+    let nodes = xml("<html xmlns=\"http://www.w3.org/1999/xhtml\" id=\"r\">\
+                     <a id=\"a\"/><svg:a xmlns:svg=\"http://www.w3.org/2000/svg\" id=\"b\"/>\
+                     <a id=\"c\"/></html>");
+    assert_eq!(select(&nodes, "a:first-of-type"), ["a", "b"]);
+    assert_eq!(select(&nodes, "a:nth-of-type(2)"), ["c"]);
+    assert_eq!(select(&nodes, "a:only-of-type"), ["b"]);
+}
+
+#[test]
+fn html_tag_names_are_lowercased() {
+    let nodes = html("<DIV id=\"a\"><Span id=\"b\"></SPAN></DIV>");
+    assert_eq!(&nodes[0].get_element().unwrap().get_tag_name()[..], "div");
+    assert_eq!(select(&nodes, "div > span"), ["b"]);
+}
+
+#[test]
+fn id_and_class_selectors_keep_their_case() {
+    let nodes = html(
+        "<div id=\"r\"><p id=\"Intro\" class=\"myClass\"></p>\
+                      <p id=\"intro\" class=\"myclass\"></p></div>",
+    );
+    assert_eq!(select(&nodes, ".myClass"), ["Intro"]);
+    assert_eq!(select(&nodes, ".myclass"), ["intro"]);
+    assert_eq!(select(&nodes, "#Intro"), ["Intro"]);
+}
+
+#[test]
+fn attribute_presence_and_equality() {
+    let nodes = html(
+        "<div id=\"r\"><input id=\"a\" type=\"checkbox\">\
+                      <span id=\"b\" TYPE=\"CheckBox\" data-x=\"\"></span></div>",
+    );
+    assert_eq!(select(&nodes, "[type]"), ["a", "b"]);
+    assert_eq!(select(&nodes, "[type=checkbox]"), ["a"]);
+    assert_eq!(select(&nodes, "[data-x=\"\"]"), ["b"]);
+}
+
+#[test]
+fn attribute_operators() {
+    let nodes = html(
+        "<div id=\"r\"><a id=\"a\" href=\"https://x.org/a.pdf\" class=\"x  y\" \
+                      data-x=\"pyq\" lang=\"en-US\"></a><a id=\"b\" href=\"http://x.org\" \
+                      class=\"xy\" lang=\"en\"></a><a id=\"c\" lang=\"english\"></a></div>",
+    );
+    assert_eq!(select(&nodes, "a[href^=\"https\"]"), ["a"]);
+    assert_eq!(select(&nodes, "[href$=\".pdf\"]"), ["a"]);
+    assert_eq!(select(&nodes, "[data-x*=y]"), ["a"]);
+    assert_eq!(select(&nodes, "[class~=x]"), ["a"]);
+    assert_eq!(select(&nodes, "[lang|=en]"), ["a", "b"]);
+}
+
+#[test]
+fn empty_or_whitespace_attribute_values_never_match_substrings() {
+    let nodes = html("<div id=\"r\"><a id=\"a\" href=\"x\" class=\"x y\"></a></div>");
+    assert!(select(&nodes, "[href^=\"\"]").is_empty());
+    assert!(select(&nodes, "[href*=\"\"]").is_empty());
+    assert!(select(&nodes, "[class~=\"x y\"]").is_empty());
+}
+
+#[test]
+fn attribute_case_sensitivity_flags() {
+    let nodes = html(
+        "<div id=\"r\"><a id=\"a\" title=\"doc.PDF\"></a>\
+                      <a id=\"b\" title=\"doc.pdf\"></a></div>",
+    );
+    assert_eq!(select(&nodes, "[title$=\".pdf\" i]"), ["a", "b"]);
+    assert_eq!(select(&nodes, "[title$=\".pdf\" s]"), ["b"]);
+    assert_eq!(select(&nodes, "[title$=\".pdf\"]"), ["b"]);
+}
+
+fn list() -> Vec<Node> {
+    html(
+        "<ul id=\"r\"><li id=\"a\" class=\"row\"></li><li id=\"b\"></li>\
+          <li id=\"c\" class=\"row\"><p id=\"p\"></p></li><p id=\"d\"></p>\
+          <li id=\"e\" class=\"row\"></li><li id=\"f\"><b id=\"g\"></b></li></ul>",
+    )
+}
+
+fn specificity(selector: &str) -> Specificity {
+    let stylesheet = CssParser::new(&format!("{0} {{}}", selector)).parse_stylesheet();
+    let rules = stylesheet.style_rules(&MediaEnvironment::default());
+    rules[0].get_selectors()[0].specificity()
+}
+
+#[test]
+fn child_position_pseudo_classes() {
+    let nodes = list();
+    assert_eq!(select(&nodes, "li:first-child"), ["a"]);
+    assert_eq!(select(&nodes, "li:last-child"), ["f"]);
+    assert_eq!(select(&nodes, ":only-child"), ["r", "p", "g"]);
+    assert_eq!(select(&nodes, "li:nth-child(2n+1)"), ["a", "c", "e"]);
+    assert_eq!(select(&nodes, ":nth-child(even)"), ["b", "d", "f"]);
+    assert_eq!(select(&nodes, "li:nth-child(-n+2)"), ["a", "b"]);
+    assert_eq!(select(&nodes, "li:nth-last-child(1)"), ["f"]);
+}
+
+#[test]
+fn nth_child_of_a_selector_counts_only_matching_siblings() {
+    let nodes = list();
+    assert_eq!(select(&nodes, ":nth-child(odd of .row)"), ["a", "e"]);
+    assert_eq!(select(&nodes, ":nth-child(2 of .row)"), ["c"]);
+}
+
+#[test]
+fn type_position_pseudo_classes() {
+    let nodes = list();
+    assert_eq!(select(&nodes, "li:first-of-type"), ["a"]);
+    assert_eq!(select(&nodes, "li:nth-of-type(2)"), ["b"]);
+    assert_eq!(select(&nodes, "ul > :nth-last-of-type(1)"), ["d", "f"]);
+    assert_eq!(select(&nodes, "ul > :only-of-type"), ["d"]);
+}
+
+#[test]
+fn empty_and_root() {
+    let nodes = list();
+    assert_eq!(select(&nodes, ":empty"), ["a", "b", "p", "d", "e", "g"]);
+    assert_eq!(select(&nodes, ":root"), ["r"]);
+}
+
+#[test]
+fn logical_pseudo_classes() {
+    let nodes = list();
+    assert_eq!(select(&nodes, "li:not(.row)"), ["b", "f"]);
+    assert_eq!(select(&nodes, "li:not(.row, :first-child)"), ["b", "f"]);
+    assert_eq!(select(&nodes, ":is(p, b)"), ["p", "d", "g"]);
+    assert_eq!(select(&nodes, ":where(p, b)"), ["p", "d", "g"]);
+}
+
+#[test]
+fn is_and_where_forgive_invalid_selectors_but_not_does_not() {
+    let nodes = list();
+    assert_eq!(select(&nodes, "li:is(.row, :bogus)"), ["a", "c", "e"]);
+    assert_eq!(select(&nodes, "li:where(:bogus, #b)"), ["b"]);
+
+    let stylesheet = CssParser::new("li:not(.row, :bogus) {}").parse_stylesheet();
+    assert!(stylesheet
+        .style_rules(&MediaEnvironment::default())
+        .is_empty());
+}
+
+#[test]
+fn has_matches_relative_selectors() {
+    let nodes = list();
+    assert_eq!(select(&nodes, "li:has(p)"), ["c"]);
+    assert_eq!(select(&nodes, "li:has(> p, b)"), ["c", "f"]);
+    assert_eq!(select(&nodes, "li:has(+ p)"), ["c"]);
+    assert_eq!(select(&nodes, "li:has(~ p)"), ["a", "b", "c"]);
+    assert_eq!(select(&nodes, "ul:has(.row + li)"), ["r"]);
+}
+
+#[test]
+fn where_has_no_specificity() {
+    assert_eq!(specificity("li:where(#a, .b)"), Specificity(0, 0, 1));
+    assert_eq!(specificity("li:is(#a, .b)"), Specificity(1, 0, 1));
+    assert_eq!(specificity("li:not(.a, .b.c)"), Specificity(0, 2, 1));
+    assert_eq!(specificity("li:nth-child(2 of #a)"), Specificity(1, 1, 1));
+}
+
+#[test]
+fn specificity_counts_ids_classes_and_types() {
+    assert_eq!(specificity("*"), Specificity(0, 0, 0));
+    assert_eq!(specificity("li"), Specificity(0, 0, 1));
+    assert_eq!(specificity("ul ol + li"), Specificity(0, 0, 3));
+    assert_eq!(specificity("h1 + *[rel=up]"), Specificity(0, 1, 1));
+    assert_eq!(specificity("ul ol li.red"), Specificity(0, 1, 3));
+    assert_eq!(specificity("li.red.level"), Specificity(0, 2, 1));
+    assert_eq!(specificity("#x34y"), Specificity(1, 0, 0));
+    assert_eq!(specificity("#a #b.c:first-child"), Specificity(2, 2, 0));
+    assert_eq!(specificity("li::before"), Specificity(0, 0, 2));
+}
+
+#[test]
+fn specificity_compares_ids_then_classes_then_types() {
+    assert!(specificity("#a") > specificity(".a.b.c.d.e.f.g.h.i.j.k"));
+    assert!(specificity(".a") > specificity("a b c d e f g h i j k"));
+    assert!(specificity("a.b") > specificity(".b"));
+    assert_eq!(specificity(".a b"), specificity("b.a"));
+}
+
+#[test]
+fn combinators() {
+    let nodes = list();
+    assert_eq!(select(&nodes, "ul p"), ["p", "d"]);
+    assert_eq!(select(&nodes, "ul > p"), ["d"]);
+    assert_eq!(select(&nodes, "li > p, li > b"), ["p", "g"]);
+    assert_eq!(select(&nodes, "li + li"), ["b", "c", "f"]);
+    assert_eq!(select(&nodes, "p + li"), ["e"]);
+    assert_eq!(select(&nodes, "p ~ li"), ["e", "f"]);
+    assert_eq!(select(&nodes, "#b ~ .row"), ["c", "e"]);
+    assert_eq!(select(&nodes, "ul > li + li ~ .row p"), ["p"]);
+}
+
+#[test]
+fn combinators_backtrack_past_a_failed_candidate() {
+    let nodes = html(
+        "<div class=\"a\"><div class=\"b\"><div class=\"b\">\
+          <span class=\"c\" id=\"t\"></span></div></div></div>",
+    );
+    assert_eq!(select(&nodes, ".a > .b .c"), ["t"]);
+    assert_eq!(select(&nodes, ".a .b > .b > .c"), ["t"]);
+    assert!(select(&nodes, ".a > .b > .c").is_empty());
+
+    let nodes = html(
+        "<div><p class=\"a\"></p><p class=\"b\"></p><p class=\"b\"></p>\
+          <p class=\"c\" id=\"u\"></p></div>",
+    );
+    assert_eq!(select(&nodes, ".a + .b ~ .c"), ["u"]);
+    assert_eq!(select(&nodes, "div > .a ~ .b + .c"), ["u"]);
+    assert!(select(&nodes, ".a + .b + .c").is_empty());
+}
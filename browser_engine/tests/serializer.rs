@@ -0,0 +1,88 @@
+extern crate browser_engine;
+use browser_engine::css_parser::CssParser;
+use browser_engine::serializer::{serialize_stylesheet, Mode};
+
+const STYLESHEET: &str = r#"
+@import url("print.css") layer(base.reset) print;
+@namespace svg url("http://www.w3.org/2000/svg");
+
+html, body > div.main#content:first-child::before {
+    display: block;
+    color: RGB(255, 0, 0);
+    margin: 0 auto;
+    content: "\201C" counter(item) ". ";
+}
+
+a:not(.plain):nth-child(2n+1 of .item), li:has(> img), svg|rect, [data-kind~="link" i] + p ~ span {
+    background-color: #0a0b0c80;
+    width: calc(100% - 2 * (1em + 4px));
+    height: clamp(10px, 50%, 0.5in) !important;
+    font-family: "Fira Sans", serif;
+}
+
+:root {
+    --spacing: 0.5em;
+    --empty:;
+    padding: var(--spacing) 1px;
+}
+
+@media screen and (min-width: 600px), print {
+    p {
+        color: hsl(120 100% 25% / 0.25);
+        border-left-color: lab(50 20 -30);
+    }
+
+    @media (orientation: landscape) {
+        p {}
+    }
+}
+"#;
+
+fn round_trip(css: &str, mode: Mode) -> (String, String) {
+    let first = serialize_stylesheet(&CssParser::new(css).parse_stylesheet(), mode);
+    let second = serialize_stylesheet(&CssParser::new(&first).parse_stylesheet(), mode);
+    (first, second)
+}
+
+#[test]
+fn pretty_output_round_trips() {
+    let (first, second) = round_trip(STYLESHEET, Mode::Pretty);
+    assert_eq!(first, second);
+}
+
+#[test]
+fn minified_output_round_trips() {
+    let (first, second) = round_trip(STYLESHEET, Mode::Minify);
+    assert_eq!(first, second);
+}
+
+#[test]
+fn modes_parse_to_the_same_stylesheet() {
+    let (pretty, _) = round_trip(STYLESHEET, Mode::Pretty);
+    let (minified, _) = round_trip(STYLESHEET, Mode::Minify);
+
+    let from_minified = CssParser::new(&minified).parse_stylesheet();
+    assert_eq!(serialize_stylesheet(&from_minified, Mode::Pretty), pretty);
+}
+
+#[test]
+fn pretty_output() {
+    let css = "@media print{a>b,c{color:#f00;margin-top:.5em !important}}";
+    let sheet = CssParser::new(css).parse_stylesheet();
+
+    assert_eq!(
+        serialize_stylesheet(&sheet, Mode::Pretty),
+        "@media print {\n    a > b, c {\n        color: rgb(255, 0, 0);\n        margin-top: 0.5em !important;\n    }\n}"
+    );
+}
+
+#[test]
+fn minified_output() {
+    let css = "a > b, c {\n    color: rgba(255, 255, 255, 0.5);\n    width: calc(2 * 10px);\n}\n\nd {\n    color: rgb(170 187 204);\n}";
+    let sheet = CssParser::new(css).parse_stylesheet();
+
+    assert_eq!(
+        serialize_stylesheet(&sheet, Mode::Minify),
+        "a>b,c{color:rgba(255,255,255,.5);width:calc(2*10px)}d{color:#abc}"
+    );
+}
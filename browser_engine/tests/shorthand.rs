@@ -0,0 +1,84 @@
+extern crate browser_engine;
+use browser_engine::css_parser::CssParser;
+use browser_engine::serializer::{serialize_declaration_block, Mode};
+
+fn expand(declaration: &str) -> String {
+    let declarations = CssParser::new(declaration).parse_declaration_list();
+    serialize_declaration_block(&declarations, Mode::Minify)
+}
+
+#[test]
+fn box_shorthands_repeat_their_sides() {
+    assert_eq!(
+        expand("margin: 1px"),
+        "margin-top:1px;margin-right:1px;margin-bottom:1px;margin-left:1px"
+    );
+    assert_eq!(
+        expand("padding: 1px 2px 3px"),
+        "padding-top:1px;padding-right:2px;padding-bottom:3px;padding-left:2px"
+    );
+    assert_eq!(
+        expand("margin: -1px auto"),
+        "margin-top:-1px;margin-right:auto;margin-bottom:-1px;margin-left:auto"
+    );
+}
+
+#[test]
+fn border_resets_omitted_parts() {
+    assert_eq!(
+        expand("border-top: dashed"),
+        "border-top-width:medium;border-top-style:dashed;border-top-color:currentcolor"
+    );
+}
+
+#[test]
+fn css_wide_keywords_set_every_longhand() {
+    assert_eq!(
+        expand("padding: inherit"),
+        "padding-top:inherit;padding-right:inherit;padding-bottom:inherit;padding-left:inherit"
+    );
+}
+
+#[test]
+fn negative_values_invalidate_the_whole_shorthand() {
+    assert_eq!(expand("padding: -5px 10px"), "");
+    assert_eq!(expand("border: -1px solid red"), "");
+    assert_eq!(expand("border-width: 1px -2px"), "");
+    assert_eq!(expand("font: -12px serif"), "");
+}
+
+#[test]
+fn invalid_components_invalidate_the_whole_shorthand() {
+    assert_eq!(expand("margin: 1px 2px 3px 4px 5px"), "");
+    assert_eq!(expand("border: solid solid"), "");
+    assert_eq!(expand("border-color: red nope"), "");
+    assert_eq!(expand("background: red, blue"), "");
+}
+
+#[test]
+fn border_sets_width_style_and_color_of_every_side() {
+    let longhands = [
+        "border-top-width:2px;border-top-style:solid;border-top-color:#f00",
+        "border-right-width:2px;border-right-style:solid;border-right-color:#f00",
+        "border-bottom-width:2px;border-bottom-style:solid;border-bottom-color:#f00",
+        "border-left-width:2px;border-left-style:solid;border-left-color:#f00",
+    ];
+    assert_eq!(expand("border: red solid 2px"), longhands.join(";"));
+}
+
+#[test]
+fn background_layers_keep_their_values() {
+    assert_eq!(
+        expand("background: url(a.png) no-repeat, url(b.png) 10px 20px / 50% fixed blue")
+            .split(';')
+            .filter(|longhand| longhand.starts_with("background-image")
+                || longhand.starts_with("background-position")
+                || longhand.starts_with("background-color"))
+            .collect::<Vec<_>>(),
+        [
+            "background-image:url(\"a.png\"),url(\"b.png\")",
+            "background-position:0% 0%,10px 20px",
+            "background-color:#00f",
+        ]
+    );
+}
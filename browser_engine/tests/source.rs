@@ -0,0 +1,100 @@
+extern crate browser_engine;
+use browser_engine::css_parser::CssParser;
+use browser_engine::dom::Node;
+use browser_engine::html_parser::HtmlParser;
+use browser_engine::layout::{layout_tree, Dimensions};
+use browser_engine::media::MediaEnvironment;
+use browser_engine::source::SourceLocation;
+use browser_engine::style::StyledNode;
+use browser_engine::xml_parser::XmlParser;
+
+// The byte span and the line:column range, which counts characters.
+fn at(location: &SourceLocation) -> String {
+    format!("{0:?} {1:?}", location.span, location)
+}
+
+fn elements(node: &Node) -> Vec<&Node> {
+    node.get_children()
+        .iter()
+        .filter(|child| child.get_element().is_some())
+        .collect()
+}
+
+#[test]
+fn html_columns_count_characters() {
+    let nodes = HtmlParser::new("<div>\n  <p>é€</p><span>x</span>\n</div>").parse_nodes();
+    let children = elements(&nodes[0]);
+
+    assert_eq!(at(nodes[0].get_location()), "0..41 1:1-3:7");
+    assert_eq!(at(children[0].get_location()), "8..20 2:3-2:12");
+    assert_eq!(
+        at(children[0].get_children()[0].get_location()),
+        "11..16 2:6-2:8"
+    );
+    assert_eq!(at(children[1].get_location()), "20..34 2:12-2:26");
+}
+
+#[test]
+fn css_rules_and_declarations_across_crlf_and_cr() {
+    let css = "a {\r\n  color: red;\r\n}\r\n.é { width: 1px }\rb { color: blue }";
+    let stylesheet = CssParser::new(css).parse_stylesheet();
+    let rules = stylesheet.style_rules(&MediaEnvironment::default());
+
+    let nodes = HtmlParser::new("<a style=\"\"></a>").parse_nodes();
+    let styled = StyledNode::new(&nodes[0], &stylesheet);
+    assert_eq!(
+        at(styled.declaration("color").unwrap().get_location()),
+        "7..17 2:3-2:13"
+    );
+
+    assert_eq!(at(rules[0].get_location()), "0..21 1:1-3:2");
+    assert_eq!(at(rules[1].get_location()), "23..41 4:1-4:18");
+    assert_eq!(at(rules[2].get_location()), "42..59 5:1-5:18");
+}
+
+#[test]
+fn xml_lines_end_at_crlf_and_cr() {
+    let xml = "<a>\r\n  <é x=\"1\"/>\r<b>t</b>\r\n</a>";
+    let nodes = XmlParser::new(xml).parse_nodes().unwrap();
+    let children = elements(&nodes[0]);
+
+    assert_eq!(at(children[0].get_location()), "7..18 2:3-2:13");
+    assert_eq!(at(children[1].get_location()), "19..27 3:1-3:9");
+    assert_eq!(
+        at(children[1].get_children()[0].get_location()),
+        "22..23 3:4-3:5"
+    );
+}
+
+#[test]
+fn files_are_named_in_locations() {
+    let stylesheet = CssParser::new_with_file("a { color: red }", "a.css").parse_stylesheet();
+    let rules = stylesheet.style_rules(&MediaEnvironment::default());
+    assert_eq!(format!("{0:?}", rules[0].get_location()), "a.css:1:1-1:17");
+
+    let nodes = HtmlParser::new_with_file("<p></p>", "a.html").parse_nodes();
+    assert_eq!(format!("{0:?}", nodes[0].get_location()), "a.html:1:1-1:8");
+}
+
+#[test]
+fn a_hit_box_leads_back_to_its_markup_and_rules() {
+    let html = "<div>\n  <p>é€</p>\n</div>";
+    let css = "div, p { display: block }\r\np {\r\n  height: 10px\r\n}";
+    let nodes = HtmlParser::new(html).parse_nodes();
+    let stylesheet = CssParser::new(css).parse_stylesheet();
+    let styled = StyledNode::new(&nodes[0], &stylesheet);
+
+    let mut containing_block = Dimensions::default();
+    containing_block.content.width = 100.0;
+    let root_box = layout_tree(&styled, containing_block, &MediaEnvironment::default());
+
+    let hit = root_box.hit_test(5.0, 5.0).unwrap();
+    assert_eq!(at(hit.get_location()), "8..20 2:3-2:12");
+
+    let p = hit.styled_node;
+    assert_eq!(
+        at(p.declaration("height").unwrap().get_location()),
+        "34..46 3:3-3:15"
+    );
+    assert_eq!(at(p.matched_rules()[1].get_location()), "27..49 2:1-4:2");
+}
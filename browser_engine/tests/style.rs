@@ -0,0 +1,229 @@
+extern crate browser_engine;
+use browser_engine::css::{Origin, StyleSheet};
+use browser_engine::css_parser::CssParser;
+use browser_engine::html_parser::HtmlParser;
+use browser_engine::media::MediaEnvironment;
+use browser_engine::serializer::{serialize_value, Mode};
+use browser_engine::style::StyledNode;
+
+fn stylesheet(css: &str, origin: Origin) -> StyleSheet {
+    let mut stylesheet = CssParser::new(css).parse_stylesheet();
+    stylesheet.set_origin(origin);
+    stylesheet
+}
+
+// The value of `property` on the root element.
+fn value(html: &str, user_agent: &str, user: &str, author: &str, property: &str) -> String {
+    let nodes = HtmlParser::new(html).parse_nodes();
+    let stylesheets = [
+        stylesheet(user_agent, Origin::UserAgent),
+        stylesheet(user, Origin::User),
+        stylesheet(author, Origin::Author),
+    ];
+    let stylesheets: Vec<&StyleSheet> = stylesheets.iter().collect();
+    let node = StyledNode::new_with_cascade(&nodes[0], &stylesheets, &MediaEnvironment::default());
+
+    node.value(property)
+        .map_or(String::new(), |value| serialize_value(value, Mode::Minify))
+}
+
+#[test]
+fn revert_rolls_back_to_the_user_agent_origin() {
+    let display = value(
+        "<div></div>",
+        "div { display: block }",
+        "",
+        "div { display: inline-block } div { display: revert }",
+        "display",
+    );
+    assert_eq!(display, "block");
+}
+
+#[test]
+fn revert_skips_every_declaration_of_its_origin() {
+    let width = value(
+        "<div style=\"width: 30px\"></div>",
+        "div { width: 10px }",
+        "div { width: 20px }",
+        "div { width: revert !important }",
+        "width",
+    );
+    assert_eq!(width, "20px");
+}
+
+#[test]
+fn revert_in_the_user_origin_rolls_back_further() {
+    let width = value(
+        "<div></div>",
+        "div { width: 10px }",
+        "div { width: revert }",
+        "div { width: revert }",
+        "width",
+    );
+    assert_eq!(width, "10px");
+}
+
+#[test]
+fn revert_without_a_lower_origin_acts_as_unset() {
+    let display = value("<div></div>", "", "", "div { display: revert }", "display");
+    assert_eq!(display, "");
+}
+
+#[test]
+fn important_is_split_off_the_value() {
+    let nodes = HtmlParser::new("<div></div>").parse_nodes();
+    let stylesheet =
+        CssParser::new("div { width: 1px ! IMPORTANT; height: 2px }").parse_stylesheet();
+    let node = StyledNode::new(&nodes[0], &stylesheet);
+
+    assert!(node.declaration("width").unwrap().is_important());
+    assert_eq!(
+        serialize_value(node.value("width").unwrap(), Mode::Minify),
+        "1px"
+    );
+    assert!(!node.declaration("height").unwrap().is_important());
+
+    let stylesheet = CssParser::new("div { width: 1px !important 2px }").parse_stylesheet();
+    assert!(StyledNode::new(&nodes[0], &stylesheet)
+        .declaration("width")
+        .is_none());
+}
+
+#[test]
+fn important_beats_specificity_and_order() {
+    let width = value(
+        "<div id=\"a\"></div>",
+        "",
+        "",
+        "div { width: 1px !important } #a { width: 2px } div { width: 3px }",
+        "width",
+    );
+    assert_eq!(width, "1px");
+}
+
+#[test]
+fn normal_declarations_follow_the_origin_order() {
+    let width = |user_agent: &str, user: &str, author: &str| {
+        value("<div></div>", user_agent, user, author, "width")
+    };
+
+    assert_eq!(
+        width(
+            "div { width: 1px }",
+            "div { width: 2px }",
+            "div { width: 3px }"
+        ),
+        "3px"
+    );
+    assert_eq!(width("div { width: 1px }", "div { width: 2px }", ""), "2px");
+    assert_eq!(
+        width("#x, div { width: 1px }", "div { width: 2px }", ""),
+        "2px"
+    );
+}
+
+#[test]
+fn important_declarations_reverse_the_origin_order() {
+    let width = |user_agent: &str, user: &str, author: &str| {
+        value("<div></div>", user_agent, user, author, "width")
+    };
+
+    assert_eq!(
+        width(
+            "div { width: 1px !important }",
+            "div { width: 2px !important }",
+            "div { width: 3px !important }"
+        ),
+        "1px"
+    );
+    assert_eq!(
+        width(
+            "div { width: 1px }",
+            "div { width: 2px !important }",
+            "div { width: 3px !important }"
+        ),
+        "2px"
+    );
+    assert_eq!(
+        width(
+            "div { width: 1px }",
+            "div { width: 2px }",
+            "div { width: 3px !important }"
+        ),
+        "3px"
+    );
+}
+
+#[test]
+fn inline_styles_are_author_declarations() {
+    let width = |html: &str, author: &str| value(html, "", "", author, "width");
+
+    assert_eq!(
+        width(
+            "<div id=\"a\" style=\"width: 1px\"></div>",
+            "#a { width: 2px }"
+        ),
+        "1px"
+    );
+    assert_eq!(
+        width(
+            "<div style=\"width: 1px\"></div>",
+            "div { width: 2px !important }"
+        ),
+        "2px"
+    );
+    assert_eq!(
+        width(
+            "<div style=\"width: 1px !important\"></div>",
+            "div { width: 2px !important }"
+        ),
+        "1px"
+    );
+    assert_eq!(
+        value(
+            "<div style=\"width: 1px !important\"></div>",
+            "",
+            "div { width: 2px !important }",
+            "",
+            "width"
+        ),
+        "2px"
+    );
+}
+
+#[test]
+fn specificity_wins_over_source_order() {
+    let width = |author: &str| value("<div id=\"a\" class=\"b\"></div>", "", "", author, "width");
+
+    assert_eq!(width("#a { width: 1px } .b { width: 2px }"), "1px");
+    assert_eq!(width(".b { width: 1px } div { width: 2px }"), "1px");
+    assert_eq!(width("div.b { width: 1px } .b { width: 2px }"), "1px");
+}
+
+#[test]
+fn equal_specificity_is_broken_by_source_order() {
+    let width = |author: &str| {
+        value(
+            "<div id=\"a\" class=\"b c\"></div>",
+            "",
+            "",
+            author,
+            "width",
+        )
+    };
+
+    assert_eq!(width("div { width: 1px } div { width: 2px }"), "2px");
+    assert_eq!(width(".c { width: 1px } .b { width: 2px }"), "2px");
+    assert_eq!(width(".b { width: 1px } .c { width: 2px }"), "2px");
+    assert_eq!(width("div { width: 1px; width: 2px }"), "2px");
+}
+
+#[test]
+fn a_rule_uses_its_most_specific_matching_selector() {
+    let width = |author: &str| value("<div class=\"b\"></div>", "", "", author, "width");
+
+    assert_eq!(width("#x, div { width: 1px } .b { width: 2px }"), "2px");
+    assert_eq!(width(".b, div { width: 1px } .b { width: 2px }"), "2px");
+    assert_eq!(width(".b { width: 2px } div, .b { width: 1px }"), "1px");
+    assert_eq!(width("div.b, div { width: 1px } .b { width: 2px }"), "1px");
+}
@@ -0,0 +1,180 @@
+extern crate browser_engine;
+use browser_engine::dom::{ElementData, Node, NodeType};
+use browser_engine::xml_parser::{XmlParser, XML_NAMESPACE};
+
+const SVG: &str = "http://www.w3.org/2000/svg";
+
+fn parse(xml: &str) -> Node {
+    let mut nodes = XmlParser::new(xml).parse_nodes().unwrap();
+    assert_eq!(nodes.len(), 1);
+    nodes.remove(0)
+}
+
+fn error(xml: &str) -> String {
+    XmlParser::new(xml).parse_nodes().unwrap_err().to_string()
+}
+
+fn element(node: &Node) -> &ElementData {
+    match *node.get_node_type() {
+        NodeType::Element(ref element) => element,
+        ref other => panic!("expected an element but found {0:?}", other),
+    }
+}
+
+fn namespace(node: &Node) -> Option<&str> {
+    element(node)
+        .get_namespace()
+        .map(|namespace| &namespace[..])
+}
+
+fn text(node: &Node) -> &str {
+    match *node.get_node_type() {
+        NodeType::Text(ref text) => text,
+        ref other => panic!("expected a text node but found {0:?}", other),
+    }
+}
+
+#[test]
+fn default_namespace_applies_to_descendants() {
+    let root = parse("<svg xmlns=\"http://www.w3.org/2000/svg\"><g><rect/></g></svg>");
+    let g = &root.get_children()[0];
+
+    assert_eq!(namespace(&root), Some(SVG));
+    assert_eq!(namespace(g), Some(SVG));
+    assert_eq!(namespace(&g.get_children()[0]), Some(SVG));
+}
+
+#[test]
+fn prefixed_names_resolve_to_their_namespace() {
+    let root = parse("<a xmlns:s=\"http://www.w3.org/2000/svg\"><s:rect/><b/></a>");
+    let rect = &root.get_children()[0];
+
+    assert_eq!(namespace(&root), None);
+    assert_eq!(&element(rect).get_tag_name()[..], "rect");
+    assert_eq!(namespace(rect), Some(SVG));
+    assert_eq!(namespace(&root.get_children()[1]), None);
+}
+
+#[test]
+fn namespace_declarations_are_scoped_to_their_element() {
+    assert!(error("<a><b xmlns:s=\"urn:s\"/><s:c/></a>").ends_with("undeclared namespace prefix s"));
+}
+
+#[test]
+fn xml_prefix_is_always_bound() {
+    let root = parse("<a xml:lang=\"en\"/>");
+    assert_eq!(element(&root).get_attribute("xml:lang"), Some("en"));
+
+    let xml = format!("<a xmlns:xml=\"{0}\"/>", XML_NAMESPACE);
+    assert!(XmlParser::new(&xml).parse_nodes().is_ok());
+    assert!(error("<a xmlns:xml=\"urn:other\"/>").ends_with("cannot rebind reserved prefix xml"));
+}
+
+#[test]
+fn undeclared_prefixes_are_errors() {
+    assert!(error("<s:a/>").ends_with("undeclared namespace prefix s"));
+    assert!(error("<a s:b=\"1\"/>").ends_with("undeclared namespace prefix s"));
+}
+
+#[test]
+fn cdata_is_kept_as_text() {
+    let root = parse("<a>x<![CDATA[<b>&amp;]]>y</a>");
+
+    assert_eq!(root.get_children().len(), 1);
+    assert_eq!(text(&root.get_children()[0]), "x<b>&amp;y");
+}
+
+#[test]
+fn processing_instructions_keep_their_target_and_data() {
+    let root = parse("<a><?style href=\"a.css\"?><?empty?></a>");
+
+    match *root.get_children()[0].get_node_type() {
+        NodeType::ProcessingInstruction(ref target, ref data) => {
+            assert_eq!(target, "style");
+            assert_eq!(data, "href=\"a.css\"");
+        }
+        ref other => panic!("expected a processing instruction but found {0:?}", other),
+    }
+    match *root.get_children()[1].get_node_type() {
+        NodeType::ProcessingInstruction(ref target, ref data) => {
+            assert_eq!(target, "empty");
+            assert_eq!(data, "");
+        }
+        ref other => panic!("expected a processing instruction but found {0:?}", other),
+    }
+
+    assert!(error("<a><?xml version=\"1.0\"?></a>")
+        .ends_with("the xml declaration must be at the start of the document"));
+}
+
+#[test]
+fn entity_and_character_references_are_expanded() {
+    let root = parse("<a b=\"&quot;&#65;&#x42;\">&lt;&gt;&amp;&apos;&#x263A;</a>");
+
+    assert_eq!(element(&root).get_attribute("b"), Some("\"AB"));
+    assert_eq!(text(&root.get_children()[0]), "<>&'\u{263A}");
+
+    assert!(error("<a>&nbsp;</a>").ends_with("undefined entity &nbsp;"));
+    assert!(error("<a>&#0;</a>").ends_with("invalid character reference &#0;"));
+}
+
+#[test]
+fn mismatched_close_tags_are_errors() {
+    assert_eq!(
+        error("<a><b></a></b>"),
+        "1:10: expected </b> but found </a>"
+    );
+    assert!(error("<s:a xmlns:s=\"urn:s\"></a>").ends_with("expected </s:a> but found </a>"));
+    assert!(error("<a><b></b>").ends_with("unexpected end of input inside element"));
+}
+
+#[test]
+fn only_xml_whitespace_separates_markup() {
+    assert!(error("<a\u{A0}b=\"1\"/>").ends_with("expected whitespace between attributes"));
+    assert!(error("<a b=\"1\"\u{2003}c=\"2\"/>").ends_with("expected whitespace between attributes"));
+    assert!(XmlParser::new("<a\r\n\tb=\"1\"\n/>").parse_nodes().is_ok());
+
+    let root = parse("<a b=\"x\u{A0}y\">\u{A0}</a>");
+    assert_eq!(element(&root).get_attribute("b"), Some("x\u{A0}y"));
+    assert_eq!(text(&root.get_children()[0]), "\u{A0}");
+}
+
+#[test]
+fn attribute_whitespace_is_normalized_to_spaces() {
+    let root = parse("<a b=\"x\ty\nz\r\nw\rv\" c=\"&#xA;&#9;\"/>");
+
+    assert_eq!(element(&root).get_attribute("b"), Some("x y z w v"));
+    assert_eq!(element(&root).get_attribute("c"), Some("\n\t"));
+}
+
+#[test]
+fn line_ends_are_normalized() {
+    let root = parse("<a>x\r\ny\rz<![CDATA[\r\n]]><!--\r\n--><?p a\rb?></a>");
+
+    assert_eq!(text(&root.get_children()[0]), "x\ny\nz\n");
+    assert_eq!(
+        *root.get_children()[1].get_node_type(),
+        NodeType::Comment(String::from("\n"))
+    );
+    assert_eq!(
+        *root.get_children()[2].get_node_type(),
+        NodeType::ProcessingInstruction(String::from("p"), String::from("a\nb"))
+    );
+}
+
+#[test]
+fn names_follow_the_xml_name_productions() {
+    assert_eq!(
+        &element(&parse("<\u{C0}\u{B7}-.1/>")).get_tag_name()[..],
+        "\u{C0}\u{B7}-.1"
+    );
+    assert_eq!(
+        &element(&parse("<\u{10000}/>")).get_tag_name()[..],
+        "\u{10000}"
+    );
+
+    assert!(error("<a\u{D7}/>").ends_with("expected whitespace between attributes"));
+    assert!(error("<\u{F7}/>").ends_with("expected a name"));
+    assert!(error("<\u{B7}a/>").ends_with("expected a name"));
+    assert!(error("<a><-b/></a>").ends_with("expected a name"));
+}